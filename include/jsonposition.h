@@ -0,0 +1,64 @@
+/* C interface to jsonposition, built with the `ffi` feature. */
+
+#ifndef JSONPOSITION_H
+#define JSONPOSITION_H
+
+#include <stdbool.h>
+#include <stddef.h>
+#include <stdint.h>
+
+#ifdef __cplusplus
+extern "C" {
+#endif
+
+/*
+ * Every pointer returned through an out parameter is owned by the caller and must be
+ * released with the matching jp_*_free function, never with free().
+ * Input pointers are only borrowed for the duration of the call.
+ * Offsets are in bytes.
+ */
+
+typedef enum JpStatus {
+    JP_OK = 0,
+    /* A required pointer argument was null */
+    JP_NULL_POINTER = 1,
+    /* The json is invalid, its byte offset is written to error_offset if that isn't null */
+    JP_INVALID_JSON = 2,
+    /* The path contains a nul byte, so can't be returned as a C string */
+    JP_INTERIOR_NUL = 3
+} JpStatus;
+
+typedef struct JpPath JpPath;
+
+typedef struct JpSegment {
+    /* UTF-8 bytes of the object key, not nul terminated, or NULL for an array index.
+       Borrowed from the path and valid until it is freed. */
+    const uint8_t *key;
+    size_t key_len;
+    /* Array index, zero for an object key */
+    size_t index;
+} JpSegment;
+
+/* Writes the dot path to offset, such as "$.2.a", to out. Release it with jp_string_free. */
+JpStatus jp_dot_path(const uint8_t *json, size_t len, size_t offset, char **out, size_t *error_offset);
+
+/* Writes the JSON Pointer to offset, such as "/2/a", to out. Release it with jp_string_free. */
+JpStatus jp_pointer_path(const uint8_t *json, size_t len, size_t offset, char **out, size_t *error_offset);
+
+void jp_string_free(char *text);
+
+/* Writes the path to offset to out. Release it with jp_path_free. */
+JpStatus jp_path_segments(const uint8_t *json, size_t len, size_t offset, JpPath **out, size_t *error_offset);
+
+size_t jp_path_len(const JpPath *path);
+
+/* Writes the segment at i to segment, returning false if i is out of range. */
+bool jp_path_segment(const JpPath *path, size_t i, JpSegment *segment);
+
+void jp_path_free(JpPath *path);
+
+#ifdef __cplusplus
+}
+#endif
+
+#endif
@@ -0,0 +1,169 @@
+use std::io;
+use std::pin::Pin;
+use std::task::{Context, Poll};
+
+use tokio::io::{AsyncRead, AsyncReadExt, ReadBuf};
+
+use crate::parser::ParseEvent;
+use crate::path::Path;
+use crate::stream::StreamScanner;
+
+const BUFFER_SIZE: usize = 8 * 1024;
+
+/// Constructs the path to a byte offset in json read from an async `reader`,
+/// the same as [`path_from_reader`](crate::path_from_reader) without blocking the runtime.
+///
+/// # Examples
+///
+/// ```
+/// # tokio::runtime::Builder::new_current_thread().build().unwrap().block_on(async {
+/// use jsonposition::path_from_async_reader;
+///
+/// let json = r#"[null, 9, {"a": "b"}]"#;
+///
+/// let path = path_from_async_reader(json.as_bytes(), json.find("b").unwrap()).await.expect("Invalid JSON");
+/// assert_eq!(path.to_string(), "$.2.a");
+/// # });
+/// ```
+///
+/// # Errors
+///
+/// Returns any error from reading, or an [`Error`](crate::Error) converted to [`std::io::ErrorKind::InvalidData`]
+/// if the json before the offset is invalid.
+pub async fn path_from_async_reader<R: AsyncRead + Unpin>(mut reader: R, offset: usize) -> io::Result<Path> {
+    let mut scanner = StreamScanner::new();
+    let mut buffer = vec![0; BUFFER_SIZE];
+    while !scanner.reached(offset) {
+        match reader.read(&mut buffer).await? {
+            0 => {
+                scanner.finish()?;
+                break;
+            }
+            len => scanner.feed_to(&buffer[..len], offset)?
+        }
+    }
+    Ok(scanner.path().clone())
+}
+
+/// Events of a json document read from an async reader, with byte offsets from the start of the document.
+///
+/// The document is read a chunk at a time as events are taken,
+/// and after the first error no more events are produced.
+///
+/// # Examples
+///
+/// ```
+/// # tokio::runtime::Builder::new_current_thread().build().unwrap().block_on(async {
+/// use jsonposition::{AsyncEvents, Kind, ParseEvent};
+///
+/// let mut events = AsyncEvents::new(&b"[1]"[..]);
+///
+/// assert_eq!(events.next_event().await.unwrap().unwrap(), ParseEvent::StartArray(0));
+/// assert_eq!(events.next_event().await.unwrap().unwrap(), ParseEvent::Value(1..2, Kind::Number));
+/// assert_eq!(events.next_event().await.unwrap().unwrap(), ParseEvent::EndArray(0..3));
+/// assert!(events.next_event().await.is_none());
+/// # });
+/// ```
+pub struct AsyncEvents<R> {
+    reader: R,
+    scanner: StreamScanner,
+    buffer: Box<[u8]>,
+    /// Error to yield once the events before it have been taken
+    error: Option<io::Error>,
+    finished: bool
+}
+
+impl<R: AsyncRead + Unpin> AsyncEvents<R> {
+    pub fn new(reader: R) -> Self {
+        AsyncEvents { reader, scanner: StreamScanner::new(), buffer: vec![0; BUFFER_SIZE].into_boxed_slice(), error: None, finished: false }
+    }
+
+    /// Path the document has been read up to, see [`StreamScanner::path`].
+    pub fn path(&self) -> &Path {
+        self.scanner.path()
+    }
+
+    /// Reads the next event, or `None` at the end of the document.
+    pub async fn next_event(&mut self) -> Option<io::Result<ParseEvent>> {
+        std::future::poll_fn(|cx| self.poll_next_event(cx)).await
+    }
+
+    /// Polls for the next event, reading more of the document if needed.
+    pub fn poll_next_event(&mut self, cx: &mut Context<'_>) -> Poll<Option<io::Result<ParseEvent>>> {
+        loop {
+            if let Some(event) = self.scanner.next_event() {
+                return Poll::Ready(Some(Ok(event)));
+            }
+            if self.finished {
+                return Poll::Ready(self.error.take().map(Err));
+            }
+
+            let mut buf = ReadBuf::new(&mut self.buffer);
+            let result = match Pin::new(&mut self.reader).poll_read(cx, &mut buf) {
+                Poll::Ready(Ok(())) if buf.filled().is_empty() => {
+                    self.finished = true;
+                    self.scanner.finish().map_err(io::Error::from)
+                }
+                Poll::Ready(Ok(())) => {
+                    let len = buf.filled().len();
+                    self.scanner.feed(&self.buffer[..len]).map_err(io::Error::from)
+                }
+                Poll::Ready(Err(e)) => Err(e),
+                Poll::Pending => return Poll::Pending
+            };
+            if let Err(e) = result {
+                self.finished = true;
+                self.error = Some(e);
+            }
+        }
+    }
+}
+
+/// Yields the same events as [`AsyncEvents::next_event`], for use with stream combinators.
+#[cfg(feature = "futures")]
+impl<R: AsyncRead + Unpin> futures_core::Stream for AsyncEvents<R> {
+    type Item = io::Result<ParseEvent>;
+
+    fn poll_next(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
+        self.get_mut().poll_next_event(cx)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn async_reader() {
+        let text = r#" [{}, {"é": "}", "b": [[], 1, {"c": null}]}, 9] "#;
+        for offset in 0..=text.len() + 1 {
+            let expected = crate::path_bytes(text.as_bytes(), offset).unwrap();
+            assert_eq!(path_from_async_reader(text.as_bytes(), offset).await.unwrap(), expected, "{}", offset);
+        }
+
+        // Tests events match the parser, and errors end them
+        let mut events = AsyncEvents::new(text.as_bytes());
+        for expected in crate::Parser::new(text) {
+            assert_eq!(events.next_event().await.unwrap().unwrap(), expected.unwrap());
+        }
+        assert!(events.next_event().await.is_none());
+
+        let mut events = AsyncEvents::new(&b"[1,]"[..]);
+        while let Some(Ok(_)) = events.next_event().await {}
+        assert!(events.next_event().await.is_none());
+    }
+
+    #[cfg(feature = "futures")]
+    #[tokio::test]
+    async fn stream() {
+        use futures_core::Stream;
+
+        let mut events = AsyncEvents::new(&br#"{"a": [1, 2]}"#[..]);
+        let mut count = 0;
+        while let Some(event) = std::future::poll_fn(|cx| Pin::new(&mut events).poll_next(cx)).await {
+            event.unwrap();
+            count += 1;
+        }
+        assert_eq!(count, 7);
+    }
+}
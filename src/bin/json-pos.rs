@@ -0,0 +1,306 @@
+use std::io::{self, BufWriter, Read, Write};
+use std::process::ExitCode;
+
+use jsonposition::Path;
+
+const USAGE: &str = "\
+Prints the path to a byte offset or line and column in a json document,
+or with flatten the path and raw value of every leaf
+
+Usage: json-pos <FILE> (--offset <BYTES> | --position <LINE:COLUMN>) [--format <FORMAT>]
+       json-pos --stdin (--offset <BYTES> | --position <LINE:COLUMN>) [--format <FORMAT>]
+       json-pos flatten (<FILE> | --stdin) [--csv] [--format <FORMAT>]
+
+Options:
+  --offset <BYTES>          Byte offset into the document
+  --position <LINE:COLUMN>  One based line and column, with the column counted in chars
+  --format <FORMAT>         Output format: dot, pointer, bracket or jq [default: dot]
+  --stdin                   Read the document from stdin instead of a file
+  --csv                     Print flattened leaves as path,value,offset,length rows after a header,
+                            instead of a tab between the path and value
+  -h, --help                Print this message";
+
+/// Where to read the document from
+#[derive(Debug, PartialEq, Eq)]
+enum Input {
+    File(String),
+    Stdin
+}
+
+/// Where in the document to find the path of
+#[derive(Debug, PartialEq, Eq)]
+enum Target {
+    Offset(usize),
+    /// Zero based line and column
+    Position(usize, usize)
+}
+
+/// How to print the path
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Format {
+    /// `$.1.fields.2`
+    Dot,
+    /// `/1/fields/2`
+    Pointer,
+    /// `$[1]["fields"][2]`
+    Bracket,
+    /// `.[1].fields[2]`
+    Jq
+}
+
+impl Format {
+    fn parse(name: &str) -> Result<Format, String> {
+        match name {
+            "dot" => Ok(Format::Dot),
+            "pointer" => Ok(Format::Pointer),
+            "bracket" => Ok(Format::Bracket),
+            "jq" => Ok(Format::Jq),
+            _ => Err(format!("unknown format '{}', expected dot, pointer, bracket or jq", name))
+        }
+    }
+
+    fn write(self, path: &Path) -> String {
+        match self {
+            Format::Dot => path.to_string(),
+            Format::Pointer => path.to_pointer(),
+            Format::Bracket => path.to_bracket(),
+            Format::Jq => path.to_jq()
+        }
+    }
+}
+
+/// What to print
+#[derive(Debug, PartialEq, Eq)]
+enum Command {
+    /// The path to a place in the document
+    Path(Target),
+    /// Every leaf of the document, as csv rows if `csv` is set
+    Flatten { csv: bool }
+}
+
+#[derive(Debug, PartialEq, Eq)]
+struct Args {
+    input: Input,
+    command: Command,
+    format: Format
+}
+
+/// Parses a one based `line:column` to a zero based line and column
+fn parse_position(value: &str) -> Option<Target> {
+    let (line, column) = value.split_once(':')?;
+    let (line, column): (usize, usize) = (line.parse().ok()?, column.parse().ok()?);
+    Some(Target::Position(line.checked_sub(1)?, column.checked_sub(1)?))
+}
+
+/// Parses the arguments after the program name, `Ok(None)` if help was asked for
+fn parse_args(args: impl IntoIterator<Item = String>) -> Result<Option<Args>, String> {
+    let mut args = args.into_iter().peekable();
+    let flatten = args.next_if(|arg| arg == "flatten").is_some();
+    let mut file = None;
+    let mut stdin = false;
+    let mut target = None;
+    let mut csv = false;
+    let mut format = Format::Dot;
+
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "-h" | "--help" => return Ok(None),
+            "--stdin" => stdin = true,
+            "--csv" if flatten => csv = true,
+            "--offset" | "--position" if flatten => return Err(format!("{} can't be given with flatten", arg)),
+            "--offset" | "--position" if target.is_some() => return Err("only one of --offset or --position can be given".to_string()),
+            "--offset" => {
+                let value = args.next().ok_or("--offset needs a value")?;
+                target = Some(Target::Offset(value.parse().map_err(|_| format!("invalid offset '{}'", value))?));
+            }
+            "--position" => {
+                let value = args.next().ok_or("--position needs a value")?;
+                target = Some(parse_position(&value).ok_or_else(|| format!("invalid position '{}', expected LINE:COLUMN from 1:1", value))?);
+            }
+            "--format" => format = Format::parse(&args.next().ok_or("--format needs a value")?)?,
+            _ if arg.starts_with('-') => return Err(format!("unknown option '{}'", arg)),
+            _ if file.is_some() => return Err(format!("unexpected argument '{}'", arg)),
+            _ => file = Some(arg)
+        }
+    }
+
+    let input = match (file, stdin) {
+        (Some(_), true) => return Err("a file can't be given with --stdin".to_string()),
+        (Some(file), false) => Input::File(file),
+        (None, true) => Input::Stdin,
+        (None, false) => return Err("no file given, pass one or --stdin".to_string())
+    };
+    let command = match target {
+        _ if flatten => Command::Flatten { csv },
+        Some(target) => Command::Path(target),
+        None => return Err("--offset or --position is required".to_string())
+    };
+
+    Ok(Some(Args { input, command, format }))
+}
+
+fn read(input: &Input) -> io::Result<Vec<u8>> {
+    match input {
+        Input::File(file) => std::fs::read(file),
+        Input::Stdin => {
+            let mut bytes = Vec::new();
+            io::stdin().read_to_end(&mut bytes)?;
+            Ok(bytes)
+        }
+    }
+}
+
+/// Quotes a csv field if it holds a comma, quote or line break
+fn csv_field(field: &str) -> String {
+    if field.contains([',', '"', '\n', '\r']) {
+        format!("\"{}\"", field.replace('"', "\"\""))
+    } else {
+        field.to_string()
+    }
+}
+
+/// Prints the path and raw value of every leaf of a document
+fn write_leaves(text: &str, csv: bool, format: Format) -> Result<(), String> {
+    let mut out = BufWriter::new(io::stdout().lock());
+    let write_error = |err: io::Error| err.to_string();
+    if csv {
+        writeln!(out, "path,value,offset,length").map_err(write_error)?;
+    }
+
+    let mut leaves = jsonposition::flatten(text);
+    while let Some(leaf) = leaves.next() {
+        let (_, value) = leaf.map_err(|err| err.to_string())?;
+        let path = format.write(leaves.path());
+        let row = if csv {
+            let span = leaves.span();
+            writeln!(out, "{},{},{},{}", csv_field(&path), csv_field(value), span.start, span.len())
+        } else {
+            writeln!(out, "{}\t{}", path, value)
+        };
+        row.map_err(write_error)?;
+    }
+    out.flush().map_err(write_error)
+}
+
+fn main() -> ExitCode {
+    let args = match parse_args(std::env::args().skip(1)) {
+        Ok(Some(args)) => args,
+        Ok(None) => {
+            println!("{}", USAGE);
+            return ExitCode::SUCCESS;
+        }
+        Err(err) => {
+            eprintln!("error: {}\n\n{}", err, USAGE);
+            return ExitCode::from(2);
+        }
+    };
+
+    let bytes = match read(&args.input) {
+        Ok(bytes) => bytes,
+        Err(err) => {
+            eprintln!("error: {}", err);
+            return ExitCode::FAILURE;
+        }
+    };
+
+    let target = match args.command {
+        Command::Path(target) => target,
+        Command::Flatten { csv } => {
+            let result = match std::str::from_utf8(&bytes) {
+                Ok(text) => write_leaves(text, csv, args.format),
+                Err(err) => Err(format!("document is not UTF-8, {}", err))
+            };
+            return match result {
+                Ok(()) => ExitCode::SUCCESS,
+                Err(err) => {
+                    eprintln!("error: {}", err);
+                    ExitCode::FAILURE
+                }
+            };
+        }
+    };
+
+    let path = match target {
+        Target::Offset(offset) => jsonposition::path_bytes(&bytes, offset).map_err(|err| err.to_string()),
+        Target::Position(line, column) => match std::str::from_utf8(&bytes) {
+            Ok(text) => jsonposition::path_at(text, line, column).map_err(|err| err.to_string()),
+            Err(err) => Err(format!("document is not UTF-8, {}", err))
+        }
+    };
+
+    match path {
+        Ok(path) => {
+            println!("{}", args.format.write(&path));
+            ExitCode::SUCCESS
+        }
+        Err(err) => {
+            eprintln!("error: {}", err);
+            ExitCode::FAILURE
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn parse(args: &str) -> Result<Option<Args>, String> {
+        parse_args(args.split_whitespace().map(String::from))
+    }
+
+    #[test]
+    fn args() {
+        let file = || Input::File("file.json".to_string());
+        assert_eq!(parse("file.json --offset 12"), Ok(Some(Args { input: file(), command: Command::Path(Target::Offset(12)), format: Format::Dot })));
+        assert_eq!(parse("--offset 0 --stdin"), Ok(Some(Args { input: Input::Stdin, command: Command::Path(Target::Offset(0)), format: Format::Dot })));
+        assert_eq!(
+            parse("file.json --position 12:8 --format jq"),
+            Ok(Some(Args { input: file(), command: Command::Path(Target::Position(11, 7)), format: Format::Jq }))
+        );
+        assert_eq!(parse("flatten file.json --csv"), Ok(Some(Args { input: file(), command: Command::Flatten { csv: true }, format: Format::Dot })));
+        assert_eq!(
+            parse("flatten --stdin --format pointer"),
+            Ok(Some(Args { input: Input::Stdin, command: Command::Flatten { csv: false }, format: Format::Pointer }))
+        );
+        assert_eq!(parse("--help"), Ok(None));
+
+        // Tests invalid arguments
+        assert!(parse("file.json").is_err());
+        assert!(parse("--offset 12").is_err());
+        assert!(parse("file.json --offset").is_err());
+        assert!(parse("file.json --offset -1").is_err());
+        assert!(parse("file.json --stdin --offset 1").is_err());
+        assert!(parse("a.json b.json --offset 1").is_err());
+        assert!(parse("a.json --line 1").is_err());
+        assert!(parse("a.json --offset 1 --position 1:1").is_err());
+        assert!(parse("a.json --position 0:1").is_err());
+        assert!(parse("a.json --position 12").is_err());
+        assert!(parse("a.json --offset 1 --format yaml").is_err());
+        assert!(parse("a.json --offset 1 --csv").is_err());
+        assert!(parse("flatten a.json --offset 1").is_err());
+        assert!(parse("flatten").is_err());
+        assert!(parse("a.json flatten").is_err());
+    }
+
+    #[test]
+    fn formats() {
+        let path: Path = "$.1.fields.2".parse().unwrap();
+        let mut spaced = Path::new();
+        spaced.push(0);
+        spaced.push("a b");
+
+        assert_eq!(Format::Dot.write(&path), "$.1.fields.2");
+        assert_eq!(Format::Pointer.write(&path), "/1/fields/2");
+        assert_eq!(Format::Bracket.write(&path), r#"$[1]["fields"][2]"#);
+        assert_eq!(Format::Jq.write(&path), ".[1].fields[2]");
+        assert_eq!(Format::Jq.write(&spaced), r#".[0]["a b"]"#);
+        assert_eq!(Format::Jq.write(&Path::new()), ".");
+    }
+
+    #[test]
+    fn csv_fields() {
+        assert_eq!(csv_field("$.a.0"), "$.a.0");
+        assert_eq!(csv_field("12"), "12");
+        assert_eq!(csv_field(r#""b, c""#), r#""""b, c""""#);
+    }
+}
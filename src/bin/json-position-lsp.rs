@@ -0,0 +1,20 @@
+//! Language server offering json paths to any editor, over stdio.
+
+use std::process::ExitCode;
+
+use lsp_server::Connection;
+
+fn main() -> ExitCode {
+    let (connection, io_threads) = Connection::stdio();
+    let result = jsonposition::lsp::serve(&connection);
+    // Closing the connection lets the writer thread finish
+    drop(connection);
+
+    match result.map_err(|err| err.to_string()).and_then(|_| io_threads.join().map_err(|err| err.to_string())) {
+        Ok(()) => ExitCode::SUCCESS,
+        Err(err) => {
+            eprintln!("error: {}", err);
+            ExitCode::FAILURE
+        }
+    }
+}
@@ -0,0 +1,389 @@
+use core::ops::Range;
+use alloc::collections::BTreeMap;
+use alloc::string::String;
+use alloc::vec::Vec;
+
+use crate::edit::escape;
+use crate::error::Error;
+use crate::flatten::unflatten;
+use crate::path::{Index, Path};
+use crate::spanned::{SpannedMember, SpannedNode, SpannedValue};
+
+/// What happened to the value at the path of a [`Change`]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum ChangeKind {
+    /// Only in the second document
+    Added,
+    /// Only in the first document
+    Removed,
+    /// In both documents with different values
+    Changed
+}
+
+/// Difference between two documents found by [`diff`]
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Change {
+    pub kind: ChangeKind,
+    pub path: Path,
+    /// Span of the value in the first document, `None` if it was added
+    pub a: Option<Range<usize>>,
+    /// Span of the value in the second document, `None` if it was removed
+    pub b: Option<Range<usize>>
+}
+
+/// Error returned by [`diff`], [`to_patch`] and [`merge_patch`]
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum DiffError {
+    /// The first document is invalid json.
+    InvalidA(Error),
+    /// The second document is invalid json.
+    InvalidB(Error)
+}
+
+impl core::fmt::Display for DiffError {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        match self {
+            DiffError::InvalidA(err) => write!(f, "first document: {}", err),
+            DiffError::InvalidB(err) => write!(f, "second document: {}", err)
+        }
+    }
+}
+
+#[cfg(feature = "std")]
+impl std::error::Error for DiffError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            DiffError::InvalidA(err) | DiffError::InvalidB(err) => Some(err)
+        }
+    }
+}
+
+/// Position of the member used for each key, the last one if a key is repeated
+fn positions<'m>(members: &'m [SpannedMember]) -> BTreeMap<&'m str, usize> {
+    members.iter().enumerate().map(|(i, member)| (member.key.as_str(), i)).collect()
+}
+
+/// Adds the changes between two values at `path` to `changes`
+fn compare(a: &SpannedValue, b: &SpannedValue, path: &mut Path, changes: &mut Vec<Change>) {
+    if a.raw == b.raw {
+        return;
+    }
+    let change = |kind, a: Option<&SpannedValue>, b: Option<&SpannedValue>, path: &Path| Change {
+        kind,
+        path: path.clone(),
+        a: a.map(|a| a.span.clone()),
+        b: b.map(|b| b.span.clone())
+    };
+
+    match (&a.node, &b.node) {
+        (SpannedNode::Object(a_members), SpannedNode::Object(b_members)) => {
+            let (a_keys, b_keys) = (positions(a_members), positions(b_members));
+            for (i, member) in a_members.iter().enumerate() {
+                if a_keys[member.key.as_str()] != i {
+                    continue;
+                }
+                path.push(member.key.as_str());
+                match b_keys.get(member.key.as_str()) {
+                    Some(j) => compare(&member.value, &b_members[*j].value, path, changes),
+                    None => changes.push(change(ChangeKind::Removed, Some(&member.value), None, path))
+                }
+                path.pop();
+            }
+            for (i, member) in b_members.iter().enumerate() {
+                if b_keys[member.key.as_str()] == i && !a_keys.contains_key(member.key.as_str()) {
+                    path.push(member.key.as_str());
+                    changes.push(change(ChangeKind::Added, None, Some(&member.value), path));
+                    path.pop();
+                }
+            }
+        }
+        (SpannedNode::Array(a_elements), SpannedNode::Array(b_elements)) => {
+            for (i, (a, b)) in a_elements.iter().zip(b_elements).enumerate() {
+                path.push(i);
+                compare(a, b, path, changes);
+                path.pop();
+            }
+            // Removed elements go last first, so each index is still right once the ones after it are gone
+            for (i, a) in a_elements.iter().enumerate().skip(b_elements.len()).rev() {
+                path.push(i);
+                changes.push(change(ChangeKind::Removed, Some(a), None, path));
+                path.pop();
+            }
+            for (i, b) in b_elements.iter().enumerate().skip(a_elements.len()) {
+                path.push(i);
+                changes.push(change(ChangeKind::Added, None, Some(b), path));
+                path.pop();
+            }
+        }
+        _ => {
+            // Scalars are compared by their raw text
+            changes.push(change(ChangeKind::Changed, Some(a), Some(b), path));
+        }
+    }
+}
+
+/// Compares two raw json strings structurally, listing the paths whose values were added, removed or changed
+/// along with their spans in each document. Formatting differences between the documents aren't changes.
+///
+/// Object members are matched by key, in the order of the first document and then the keys only the second one has,
+/// and array elements by index. Scalars are compared by their raw text, so `1.0` and `1` differ.
+/// A value changed to one of a different kind, such as an object to an array, is a single change.
+/// Elements removed from the end of an array are listed last first, so each change applies in order to the document before it.
+///
+/// # Examples
+///
+/// ```
+/// use jsonposition::{diff, ChangeKind};
+///
+/// let a = r#"{"name": "a", "tags": [1, 2, 3], "old": true}"#;
+/// let b = r#"{
+///   "name": "b",
+///   "tags": [1, 2],
+///   "new": null
+/// }"#;
+///
+/// let changes = diff(a, b).expect("Invalid JSON");
+/// let found: Vec<(ChangeKind, String)> = changes.iter().map(|change| (change.kind, change.path.to_string())).collect();
+/// assert_eq!(found, [
+///     (ChangeKind::Changed, "$.name".to_string()),
+///     (ChangeKind::Removed, "$.tags.2".to_string()),
+///     (ChangeKind::Removed, "$.old".to_string()),
+///     (ChangeKind::Added, "$.new".to_string())
+/// ]);
+/// assert_eq!(&b[changes[0].b.clone().unwrap()], r#""b""#);
+/// ```
+///
+/// # Errors
+///
+/// Returns a [`DiffError`] if either document is invalid json.
+pub fn diff(a: &str, b: &str) -> Result<Vec<Change>, DiffError> {
+    let (a, b) = parse_both(a, b)?;
+    let mut changes = Vec::new();
+    compare(&a, &b, &mut Path::new(), &mut changes);
+    Ok(changes)
+}
+
+fn parse_both<'a, 'b>(a: &'a str, b: &'b str) -> Result<(SpannedValue<'a>, SpannedValue<'b>), DiffError> {
+    Ok((SpannedValue::parse(a).map_err(DiffError::InvalidA)?, SpannedValue::parse(b).map_err(DiffError::InvalidB)?))
+}
+
+/// Operation of a [`PatchOperation`]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum PatchOp {
+    Add,
+    Remove,
+    Replace
+}
+
+impl core::fmt::Display for PatchOp {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        write!(f, "{}", match self {
+            PatchOp::Add => "add",
+            PatchOp::Remove => "remove",
+            PatchOp::Replace => "replace"
+        })
+    }
+}
+
+/// Operation of a [`Patch`], with the spans of the values it was derived from
+///
+/// Formatting an operation with [`Display`](core::fmt::Display) gives its RFC 6902 json object, without the spans.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PatchOperation<'b> {
+    pub op: PatchOp,
+    pub path: Path,
+    /// Raw text of the new value in the second document, `None` for [`PatchOp::Remove`]
+    pub value: Option<&'b str>,
+    /// Span of the old value in the first document, `None` for [`PatchOp::Add`]
+    pub a: Option<Range<usize>>,
+    /// Span of the new value in the second document, `None` for [`PatchOp::Remove`]
+    pub b: Option<Range<usize>>
+}
+
+impl core::fmt::Display for PatchOperation<'_> {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        write!(f, "{{\"op\":\"{}\",\"path\":\"{}\"", self.op, escape(&self.path.to_pointer()))?;
+        if let Some(value) = self.value {
+            write!(f, ",\"value\":{}", value)?;
+        }
+        write!(f, "}}")
+    }
+}
+
+/// RFC 6902 JSON Patch turning one document into another, made by [`to_patch`]
+///
+/// Formatting a patch with [`Display`](core::fmt::Display) gives the json array of its operations.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Patch<'b> {
+    pub operations: Vec<PatchOperation<'b>>
+}
+
+impl core::fmt::Display for Patch<'_> {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        write!(f, "[")?;
+        for (i, operation) in self.operations.iter().enumerate() {
+            if i > 0 {
+                write!(f, ",")?;
+            }
+            write!(f, "{}", operation)?;
+        }
+        write!(f, "]")
+    }
+}
+
+/// Computes the RFC 6902 JSON Patch turning the raw json string `a` into `b`, with an operation for each [`Change`] from [`diff`].
+/// New values are copied from `b` as they are written, and every operation keeps the spans it was derived from,
+/// so a reviewer can be shown the source of each one.
+///
+/// # Examples
+///
+/// ```
+/// use jsonposition::{to_patch, PatchOp};
+///
+/// let a = r#"{"port": 80, "hosts": ["a", "b"]}"#;
+/// let b = r#"{"port": 8080, "hosts": ["a"], "tls": {"on": true}}"#;
+///
+/// let patch = to_patch(a, b).expect("Invalid JSON");
+/// assert_eq!(patch.to_string(), concat!(
+///     r#"[{"op":"replace","path":"/port","value":8080},"#,
+///     r#"{"op":"remove","path":"/hosts/1"},"#,
+///     r#"{"op":"add","path":"/tls","value":{"on": true}}]"#
+/// ));
+///
+/// let replace = &patch.operations[0];
+/// assert_eq!(replace.op, PatchOp::Replace);
+/// assert_eq!((&a[replace.a.clone().unwrap()], &b[replace.b.clone().unwrap()]), ("80", "8080"));
+/// ```
+///
+/// # Errors
+///
+/// Returns a [`DiffError`] if either document is invalid json.
+pub fn to_patch<'b>(a: &str, b: &'b str) -> Result<Patch<'b>, DiffError> {
+    let operations = diff(a, b)?.into_iter().map(|change| PatchOperation {
+        op: match change.kind {
+            ChangeKind::Added => PatchOp::Add,
+            ChangeKind::Removed => PatchOp::Remove,
+            ChangeKind::Changed => PatchOp::Replace
+        },
+        path: change.path,
+        value: change.b.clone().map(|span| &b[span]),
+        a: change.a,
+        b: change.b
+    });
+    Ok(Patch { operations: operations.collect() })
+}
+
+/// Computes the RFC 7386 JSON Merge Patch turning the raw json string `a` into `b`, from the same changes as [`diff`], as compact json.
+///
+/// A merge patch can only describe changes to object members, so an array with any change inside it is replaced whole,
+/// and a root that isn't an object in both documents makes the patch the whole of `b`.
+/// Removed members are set to `null`, which also means a member added or changed to `null` can't be told apart from one removed.
+/// New values are copied from `b` as they are written.
+///
+/// # Examples
+///
+/// ```
+/// use jsonposition::merge_patch;
+///
+/// let a = r#"{"port": 80, "hosts": ["a", "b"], "tls": {"on": false, "cert": "x"}}"#;
+/// let b = r#"{"port": 80, "hosts": ["a"], "tls": {"on": true}}"#;
+///
+/// let patch = merge_patch(a, b).expect("Invalid JSON");
+/// assert_eq!(patch, r#"{"hosts":["a"],"tls":{"on":true,"cert":null}}"#);
+/// ```
+///
+/// # Errors
+///
+/// Returns a [`DiffError`] if either document is invalid json.
+pub fn merge_patch(a: &str, b: &str) -> Result<String, DiffError> {
+    let (a, b) = parse_both(a, b)?;
+    if !matches!((&a.node, &b.node), (SpannedNode::Object(_), SpannedNode::Object(_))) {
+        return Ok(String::from(b.raw));
+    }
+    let mut changes = Vec::new();
+    compare(&a, &b, &mut Path::new(), &mut changes);
+
+    let mut leaves: Vec<(Path, &str)> = Vec::new();
+    for change in changes {
+        // Everything from the first array down is replaced along with the array
+        let objects = change.path.iter().position(|index| matches!(index, Index::Array(_))).unwrap_or(change.path.len());
+        let (path, raw) = if objects < change.path.len() {
+            let path = Path::from(change.path.as_slice()[..objects].to_vec());
+            let raw = b.get(&path).map_or("null", |value| value.raw);
+            (path, raw)
+        } else {
+            (change.path, change.b.map_or("null", |span| &b.raw[span.start - b.span.start..span.end - b.span.start]))
+        };
+        if leaves.last().map(|(last, _)| last) != Some(&path) {
+            leaves.push((path, raw));
+        }
+    }
+
+    // Changes never overlap, so their leaves always make a document
+    Ok(unflatten(leaves).ok().flatten().unwrap_or_else(|| String::from("{}")))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use alloc::string::ToString;
+
+    #[test]
+    fn differences() {
+        let a = r#"{"a": [1, {"b": 2}, [3]], "c": {"d": "x", "d": "y"}, "e": {}}"#;
+        let b = r#"{"e": [], "a": [1, {"b": 2.0, "f": null}, [3, 4, 5]], "c": {"d": "y"}}"#;
+
+        let found: Vec<(ChangeKind, String, Option<&str>, Option<&str>)> = diff(a, b)
+            .unwrap()
+            .into_iter()
+            .map(|change| (change.kind, change.path.to_string(), change.a.map(|span| &a[span]), change.b.map(|span| &b[span])))
+            .collect();
+        assert_eq!(found, [
+            (ChangeKind::Changed, "$.a.1.b".to_string(), Some("2"), Some("2.0")),
+            (ChangeKind::Added, "$.a.1.f".to_string(), None, Some("null")),
+            (ChangeKind::Added, "$.a.2.1".to_string(), None, Some("4")),
+            (ChangeKind::Added, "$.a.2.2".to_string(), None, Some("5")),
+            (ChangeKind::Changed, "$.e".to_string(), Some("{}"), Some("[]"))
+        ]);
+
+        // Tests removals from the end of an array go last first
+        let removed: Vec<String> = diff("[1, 2, 3]", "[0]").unwrap().iter().map(|change| change.path.to_string()).collect();
+        assert_eq!(removed, ["$.0", "$.2", "$.1"]);
+
+        assert!(diff("{\n}", " {}").unwrap().is_empty());
+        assert_eq!(diff("1", "2").unwrap(), [Change { kind: ChangeKind::Changed, path: Path::new(), a: Some(0..1), b: Some(0..1) }]);
+        assert!(matches!(diff("[1,]", "[]"), Err(DiffError::InvalidA(_))));
+        assert!(matches!(diff("[]", "[1,]"), Err(DiffError::InvalidB(_))));
+    }
+
+    #[test]
+    fn patches() {
+        let a = r#"{"a/b": [1, 2, 3], "c~": "x", "d": 0}"#;
+        let b = "{\"a/b\": [1], \"c~\": \"y\\n\", \"e\": [\n  null\n]}";
+
+        let patch = to_patch(a, b).unwrap();
+        assert_eq!(patch.to_string(), concat!(
+            r#"[{"op":"remove","path":"/a~1b/2"},{"op":"remove","path":"/a~1b/1"},"#,
+            r#"{"op":"replace","path":"/c~0","value":"y\n"},{"op":"remove","path":"/d"},"#,
+            "{\"op\":\"add\",\"path\":\"/e\",\"value\":[\n  null\n]}]"
+        ));
+        assert!(patch.operations.iter().all(|operation| operation.value.is_some() == operation.b.is_some()));
+        assert_eq!(patch.operations[3].a.clone().map(|span| &a[span]), Some("0"));
+
+        assert_eq!(to_patch("[]", "[]").unwrap().to_string(), "[]");
+        assert!(matches!(to_patch("[]", "{"), Err(DiffError::InvalidB(_))));
+    }
+
+    #[test]
+    fn merge_patches() {
+        let a = r#"{"a": {"b": [1, {"c": 2}], "d": 3}, "e": [], "f": {"g": 1}, "h": null}"#;
+        let b = r#"{"a": {"b": [1, {"c": 4}], "d": 3}, "e": [5], "f": "g", "i": {"j": true}}"#;
+        assert_eq!(merge_patch(a, b).unwrap(), r#"{"a":{"b":[1, {"c": 4}]},"e":[5],"f":"g","h":null,"i":{"j": true}}"#);
+
+        assert_eq!(merge_patch(r#"{"a": 1}"#, " {\"a\": 1} ").unwrap(), "{}");
+        assert_eq!(merge_patch("{}", " {\"a\": {\"b\": 1}}").unwrap(), r#"{"a":{"b": 1}}"#);
+        assert_eq!(merge_patch("[1]", "[2]").unwrap(), "[2]");
+        assert_eq!(merge_patch(r#"{"a": 1}"#, "2").unwrap(), "2");
+        assert!(matches!(merge_patch("{", "{}"), Err(DiffError::InvalidA(_))));
+    }
+}
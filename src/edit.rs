@@ -0,0 +1,507 @@
+use core::ops::Range;
+use alloc::format;
+use alloc::string::String;
+use alloc::vec::Vec;
+
+use crate::error::Error;
+use crate::index::{Cursor, DocumentIndex};
+use crate::lines::{indent_at, indentation};
+use crate::locate::{locate, Kind};
+use crate::options::Options;
+use crate::path::{Index, Path};
+use crate::scan::{key_text, Scanner};
+use crate::span::span_of;
+
+/// Replacement of a byte range of a text, computed without touching anything around it
+/// so comments, whitespace and the formatting of the rest of the document are kept.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Edit {
+    /// Byte range of the original text to replace, empty for an insertion
+    pub span: Range<usize>,
+    /// Replacement text
+    pub text: String
+}
+
+impl Edit {
+    /// Replacement of `span` in `text`, narrowed to leave out any start and end the old and new text share.
+    pub(crate) fn minimal(text: &str, span: Range<usize>, replacement: &str) -> Edit {
+        let old = &text[span.clone()];
+        let prefix: usize = old.chars().zip(replacement.chars()).take_while(|(a, b)| a == b).map(|(c, _)| c.len_utf8()).sum();
+        let (old_rest, new_rest) = (&old[prefix..], &replacement[prefix..]);
+        let suffix: usize = old_rest.chars().rev().zip(new_rest.chars().rev()).take_while(|(a, b)| a == b).map(|(c, _)| c.len_utf8()).sum();
+
+        Edit {
+            span: span.start + prefix..span.end - suffix,
+            text: String::from(&new_rest[..new_rest.len() - suffix])
+        }
+    }
+
+    /// Applies the edit to the text it was computed for, returning the edited text.
+    pub fn apply(&self, text: &str) -> String {
+        let mut edited = String::with_capacity(text.len() - self.span.len() + self.text.len());
+        edited.push_str(&text[..self.span.start]);
+        edited.push_str(&self.text);
+        edited.push_str(&text[self.span.end..]);
+        edited
+    }
+}
+
+/// Escapes a string for writing between the quotes of a json string.
+pub(crate) fn escape(value: &str) -> String {
+    let mut escaped = String::with_capacity(value.len());
+    for c in value.chars() {
+        match c {
+            '"' => escaped.push_str("\\\""),
+            '\\' => escaped.push_str("\\\\"),
+            '\n' => escaped.push_str("\\n"),
+            '\r' => escaped.push_str("\\r"),
+            '\t' => escaped.push_str("\\t"),
+            c if u32::from(c) < 0x20 => escaped.push_str(&format!("\\u{:04x}", u32::from(c))),
+            c => escaped.push(c)
+        }
+    }
+    escaped
+}
+
+/// Computes the edit renaming the object key at a byte offset in a raw json string.
+/// The new name is escaped as needed, and only the part of the key that changes is replaced.
+///
+/// Returns `None` if the offset isn't on a key.
+///
+/// # Examples
+///
+/// ```
+/// use jsonposition::rename_key;
+///
+/// let json = r#"{"user_name": "a", "age": 3}"#;
+///
+/// let edit = rename_key(json, 3, "user_id").expect("Invalid JSON").unwrap();
+/// assert_eq!(&json[edit.span.clone()], "name");
+/// assert_eq!(edit.apply(json), r#"{"user_id": "a", "age": 3}"#);
+/// ```
+///
+/// # Errors
+///
+/// Returns an [`Error`](crate::Error) if the input json is invalid.
+pub fn rename_key(text: &str, offset: usize, new_name: &str) -> Result<Option<Edit>, Error> {
+    Ok(locate(text, offset)?.filter(|location| location.in_key()).map(|location| {
+        // Keeps the quotes, which may be single quotes in json5
+        let inner = location.span.start + 1..location.span.end - 1;
+        Edit::minimal(text, inner, &escape(new_name))
+    }))
+}
+
+/// Computes the edit replacing the value at a path in a raw json string with `new_raw`,
+/// leaving everything around the value as it is. Only the part of the value that changes is replaced.
+///
+/// Returns `None` if the document has no value at the path.
+///
+/// # Examples
+///
+/// ```
+/// use jsonposition::set_raw;
+///
+/// let json = "{\n  \"port\": 8080,\n  \"hosts\":   []\n}";
+///
+/// let edit = set_raw(json, &"$.hosts".parse().unwrap(), r#"["a", "b"]"#).expect("Invalid JSON").unwrap();
+/// assert_eq!(edit.apply(json), "{\n  \"port\": 8080,\n  \"hosts\":   [\"a\", \"b\"]\n}");
+/// ```
+///
+/// # Errors
+///
+/// Returns an [`Error`](crate::Error) if the input json is invalid, or if `new_raw` isn't a single json value,
+/// with offsets into `new_raw` for the latter.
+pub fn set_raw(text: &str, path: &Path, new_raw: &str) -> Result<Option<Edit>, Error> {
+    let span = span_of(text, path)?;
+    Scanner::new(new_raw.as_bytes()).try_for_each(|event| event.map(|_| ()))?;
+    Ok(span.map(|span| Edit::minimal(text, span, new_raw.trim())))
+}
+
+/// Computes the edit inserting a member or element into the object or array at a path in a raw json string.
+/// An object gets the member `key: raw_value` after its last member, and an array gets `raw_value` before the element at `index`,
+/// or at the end if `index` is its length.
+///
+/// Commas are added where needed, and the new member or element is laid out like the existing ones:
+/// on its own line with the same indentation if they are, and with the same spacing around the colon.
+/// An empty container spanning lines gets the member indented one level, by the document's first indentation, past its own line.
+///
+/// Returns `None` if the document has no object or array at the path, if the key is already in the object,
+/// or if the index is past the end of the array.
+///
+/// # Examples
+///
+/// ```
+/// use jsonposition::{insert_at, Path};
+///
+/// let json = "{\n    \"a\": 1,\n    \"b\": [1, 3]\n}";
+///
+/// let edit = insert_at(json, &Path::new(), "c", "true").expect("Invalid JSON").unwrap();
+/// assert_eq!(edit.apply(json), "{\n    \"a\": 1,\n    \"b\": [1, 3],\n    \"c\": true\n}");
+///
+/// let edit = insert_at(json, &"$.b".parse().unwrap(), 1, "2").expect("Invalid JSON").unwrap();
+/// assert_eq!(edit.apply(json), "{\n    \"a\": 1,\n    \"b\": [1, 2, 3]\n}");
+/// ```
+///
+/// # Errors
+///
+/// Returns an [`Error`](crate::Error) if the input json is invalid, or if `raw_value` isn't a single json value,
+/// with offsets into `raw_value` for the latter.
+pub fn insert_at(text: &str, path: &Path, key_or_index: impl Into<Index>, raw_value: &str) -> Result<Option<Edit>, Error> {
+    let index = DocumentIndex::new(text)?;
+    Scanner::new(raw_value.as_bytes()).try_for_each(|event| event.map(|_| ()))?;
+    let raw_value = raw_value.trim();
+
+    let Some(container) = index.cursor_to(path) else { return Ok(None) };
+    let children: Vec<_> = container.children().collect();
+    // Start of each child, including the key of a member
+    let start_of = |child: &Cursor| child.key_span().unwrap_or_else(|| child.span()).start;
+
+    let (item, before) = match (container.kind(), key_or_index.into()) {
+        (Kind::Object, Index::Object(key)) => {
+            if children.iter().any(|child| child.path().last() == Some(&Index::Object(key.clone()))) {
+                return Ok(None);
+            }
+            // Copies the spacing around the colon of the first member
+            let colon = children.first().and_then(|child| Some(&text[child.key_span()?.end..child.span().start])).unwrap_or(": ");
+            (format!("\"{}\"{}{}", escape(&key), colon, raw_value), None)
+        }
+        (Kind::Array, Index::Array(i)) if i <= children.len() => (String::from(raw_value), children.get(i)),
+        _ => return Ok(None)
+    };
+
+    let span = container.span();
+    let edit = match children.first() {
+        Some(first) => {
+            // Whitespace before the first child separates every child from the comma before it
+            let separator = &text[span.start + 1..start_of(first)];
+            let separator = if separator.is_empty() { " " } else { separator };
+            match before {
+                Some(next) => Edit { span: start_of(next)..start_of(next), text: format!("{},{}", item, separator) },
+                None => {
+                    let end = children[children.len() - 1].span().end;
+                    Edit { span: end..end, text: format!(",{}{}", separator, item) }
+                }
+            }
+        }
+        None => {
+            let inner = span.start + 1..span.end - 1;
+            if text[inner.clone()].contains('\n') {
+                let indent = indent_at(text, span.start);
+                let unit = text.lines().map(indentation).find(|indent| !indent.is_empty()).unwrap_or("  ");
+                Edit { span: inner, text: format!("\n{}{}{}\n{}", indent, unit, item, indent) }
+            } else {
+                Edit { span: inner, text: item }
+            }
+        }
+    };
+
+    Ok(Some(edit))
+}
+
+/// Computes the edit removing the member or element at a path in a raw json string,
+/// along with its key, the comma separating it from the next one and the whitespace up to it.
+/// The last member or element takes the comma before it instead, and removing the only one empties the container.
+///
+/// Returns `None` if the document has no value at the path, or if the path is the root.
+///
+/// # Examples
+///
+/// ```
+/// use jsonposition::remove_at;
+///
+/// let json = "{\n  \"a\": 1,\n  \"b\": [1, 2, 3]\n}";
+///
+/// let edit = remove_at(json, &"$.a".parse().unwrap()).expect("Invalid JSON").unwrap();
+/// assert_eq!(edit.apply(json), "{\n  \"b\": [1, 2, 3]\n}");
+///
+/// let edit = remove_at(json, &"$.b.2".parse().unwrap()).expect("Invalid JSON").unwrap();
+/// assert_eq!(edit.apply(json), "{\n  \"a\": 1,\n  \"b\": [1, 2]\n}");
+/// ```
+///
+/// # Errors
+///
+/// Returns an [`Error`](crate::Error) if the input json is invalid.
+pub fn remove_at(text: &str, path: &Path) -> Result<Option<Edit>, Error> {
+    let index = DocumentIndex::new(text)?;
+    let Some(value) = index.cursor_to(path) else { return Ok(None) };
+    let Some(container) = value.parent() else { return Ok(None) };
+    let start_of = |child: &Cursor| child.key_span().unwrap_or_else(|| child.span()).start;
+
+    let span = match (value.prev_sibling(), value.next_sibling()) {
+        (_, Some(next)) => start_of(&value)..start_of(&next),
+        (Some(prev), None) => prev.span().end..value.span().end,
+        (None, None) => container.span().start + 1..container.span().end - 1
+    };
+
+    Ok(Some(Edit { span, text: String::new() }))
+}
+
+/// Offset of the first byte at or after `offset` that isn't whitespace
+fn skip_whitespace(text: &str, offset: usize) -> usize {
+    text.len() - text[offset..].trim_start().len()
+}
+
+/// Offset of the first byte at or after `offset` that isn't whitespace, or part of a comment if `comments` are allowed
+fn skip_trivia(text: &str, mut offset: usize, comments: bool) -> usize {
+    loop {
+        let rest = &text[offset..];
+        let trimmed = rest.trim_start();
+        offset += rest.len() - trimmed.len();
+        let comment = if !comments {
+            return offset;
+        } else if trimmed.starts_with("//") {
+            trimmed.find('\n').unwrap_or(trimmed.len())
+        } else if let Some(comment) = trimmed.strip_prefix("/*") {
+            comment.find("*/").map_or(trimmed.len(), |end| end + 4)
+        } else {
+            return offset;
+        };
+        offset += comment;
+    }
+}
+
+/// Length of a comment after `offset` on the same line, along with the spaces before it,
+/// or 0 if there isn't one or `comments` aren't allowed
+fn trailing_comment(text: &str, offset: usize, comments: bool) -> usize {
+    let rest = &text[offset..];
+    let trimmed = rest.trim_start_matches([' ', '\t']);
+    let comment = if !comments {
+        return 0;
+    } else if trimmed.starts_with("//") {
+        trimmed.find(['\r', '\n']).unwrap_or(trimmed.len())
+    } else if let Some(comment) = trimmed.strip_prefix("/*") {
+        match comment.find("*/") {
+            Some(end) if !comment[..end].contains('\n') => end + 4,
+            _ => return 0
+        }
+    } else {
+        return 0;
+    };
+    rest.len() - trimmed.len() + comment
+}
+
+/// Computes the edit sorting the members of the object at a path in a raw json string by key, in byte order.
+/// See [`sort_keys_at_with`].
+///
+/// Returns `None` if the document has no object at the path.
+///
+/// # Examples
+///
+/// ```
+/// use jsonposition::{sort_keys_at, Path};
+///
+/// let json = "{\n  \"b\": [2, 1],\n  \"c\": {\"y\": 1, \"x\": 2},\n  \"a\": 3\n}";
+///
+/// let edit = sort_keys_at(json, &Path::new()).expect("Invalid JSON").unwrap();
+/// assert_eq!(edit.apply(json), "{\n  \"a\": 3,\n  \"b\": [2, 1],\n  \"c\": {\"y\": 1, \"x\": 2}\n}");
+/// ```
+///
+/// # Errors
+///
+/// Returns an [`Error`](crate::Error) if the input json is invalid.
+pub fn sort_keys_at(text: &str, path: &Path) -> Result<Option<Edit>, Error> {
+    sort_keys_at_with(text, path, &Options::default())
+}
+
+/// Computes the edit sorting the members of the object at a path in a raw json string by key, in byte order,
+/// scanning with the given [`Options`]. Keys are compared without their quotes, and members with the same key keep their order.
+///
+/// Each member is moved as written, along with the comments on the lines before it and the comments after it up to the end of the line of its comma.
+/// The whitespace between members and the commas stay where they are, so the layout of the object doesn't change.
+/// Only the object itself is sorted, not the objects inside it.
+///
+/// Returns `None` if the document has no object at the path, or if the object has no closing `}`,
+/// as at the end of text scanned with [`Options::lenient`] or [`Options::allow_truncated`],
+/// or if a lenient scan found values in it that aren't members.
+///
+/// # Examples
+///
+/// ```
+/// use jsonposition::{sort_keys_at_with, Options, Path};
+///
+/// let json = "{\n  // Port to listen on\n  \"port\": 80, // not 8080\n  \"host\": \"::\"\n}";
+///
+/// let edit = sort_keys_at_with(json, &Path::new(), &Options::new().allow_comments(true)).expect("Invalid JSON").unwrap();
+/// assert_eq!(edit.apply(json), "{\n  \"host\": \"::\",\n  // Port to listen on\n  \"port\": 80 // not 8080\n}");
+/// ```
+///
+/// # Errors
+///
+/// Returns an [`Error`](crate::Error) if the input json is invalid.
+pub fn sort_keys_at_with(text: &str, path: &Path, options: &Options) -> Result<Option<Edit>, Error> {
+    let index = DocumentIndex::with_options(text, options)?;
+    let Some(object) = index.cursor_to(path).filter(|cursor| cursor.kind() == Kind::Object) else { return Ok(None) };
+    // An object closed at the end of lenient or truncated text may end inside one of its members
+    let span = object.span();
+    if span.len() < 2 || text.as_bytes()[span.end - 1] != b'}' {
+        return Ok(None);
+    }
+    let inner = span.start + 1..span.end - 1;
+
+    /// Member as written, and the comments after it up to the end of the line
+    struct Member<'a> {
+        key: String,
+        body: &'a str,
+        tail: String
+    }
+    let mut members = Vec::new();
+    // Text between one member and the next, from the end of a comma or trailing comment up to the next member's comments or key
+    let mut gaps = Vec::new();
+    let mut end = inner.start;
+    let mut trailing_comma = false;
+    let comments = options.comments || options.json5;
+    for child in object.children() {
+        // Lenient scans may give values without keys, or take a stray token after a member as part of it
+        let Some(key_span) = child.key_span().filter(|key| key.start >= end) else { return Ok(None) };
+        let start = skip_whitespace(text, end);
+        let value_end = child.span().end;
+        let after = skip_trivia(text, value_end, comments);
+        // Comments between the value and its comma are written after the comma
+        let (tail, tail_end) = if text[after..].starts_with(',') {
+            trailing_comma = true;
+            let tail_end = after + 1 + trailing_comment(text, after + 1, comments);
+            (format!("{}{}", text[value_end..after].trim_end(), &text[after + 1..tail_end]), tail_end)
+        } else {
+            trailing_comma = false;
+            let tail_end = value_end + trailing_comment(text, value_end, comments);
+            (String::from(&text[value_end..tail_end]), tail_end)
+        };
+        gaps.push(&text[end..start]);
+        members.push(Member { key: key_text(text.as_bytes(), &key_span, options.json5), body: &text[start..value_end], tail });
+        end = tail_end;
+    }
+
+    members.sort_by(|a, b| a.key.cmp(&b.key));
+    let mut sorted = String::with_capacity(inner.len());
+    for (i, (gap, member)) in gaps.iter().zip(&members).enumerate() {
+        sorted.push_str(gap);
+        sorted.push_str(member.body);
+        if i + 1 < members.len() || trailing_comma {
+            sorted.push(',');
+        }
+        sorted.push_str(&member.tail);
+    }
+    sorted.push_str(&text[end..inner.end]);
+
+    Ok(Some(Edit::minimal(text, inner, &sorted)))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn edits() {
+        let text = "aé€bc";
+        assert_eq!(Edit::minimal(text, 0..text.len(), "aé€xc"), Edit { span: 6..7, text: "x".into() });
+        assert_eq!(Edit::minimal(text, 1..6, "é"), Edit { span: 3..6, text: "".into() });
+        assert_eq!(Edit::minimal(text, 0..1, "a"), Edit { span: 1..1, text: "".into() });
+        assert_eq!(Edit { span: 1..6, text: "-".into() }.apply(text), "a-bc");
+
+        assert_eq!(escape("a\"b\\c\nd\u{1}é"), r#"a\"b\\c\nd\u0001é"#);
+    }
+
+    #[test]
+    fn rename() {
+        let json = r#"[{"a": 1, "bb": {"cc": 2}}]"#;
+        let rename = |offset: usize, name: &str| rename_key(json, offset, name).unwrap().map(|edit| edit.apply(json));
+
+        assert_eq!(rename(json.find("bb").unwrap(), "b\"c").as_deref(), Some(r#"[{"a": 1, "b\"c": {"cc": 2}}]"#));
+        assert_eq!(rename(json.find("cc").unwrap() - 1, "").as_deref(), Some(r#"[{"a": 1, "bb": {"": 2}}]"#));
+        assert_eq!(rename_key(json, json.find("a").unwrap(), "a").unwrap(), Some(Edit { span: 4..4, text: "".into() }));
+        assert_eq!(rename(json.find("2").unwrap(), "x"), None);
+        assert!(rename_key("[1,]", 1, "x").is_err());
+    }
+
+    #[test]
+    fn replace() {
+        let json = r#"{"a": [1, 2.5], "b": "x"}"#;
+        let set = |path: &str, raw: &str| set_raw(json, &path.parse().unwrap(), raw).unwrap();
+
+        assert_eq!(set("$.a.1", "2.75"), Some(Edit { span: 12..12, text: "7".into() }));
+        assert_eq!(set("$.b", " {\"c\": null} ").unwrap().apply(json), r#"{"a": [1, 2.5], "b": {"c": null}}"#);
+        assert_eq!(set("$", "[]").unwrap().apply(json), "[]");
+        assert_eq!(set("$.c", "1"), None);
+
+        assert!(set_raw(json, &Path::new(), "[1,]").is_err());
+        assert!(set_raw(json, &Path::new(), "1 2").is_err());
+        assert!(set_raw("[1,]", &Path::new(), "1").is_err());
+    }
+
+    #[test]
+    fn insert() {
+        let insert = |json: &str, path: &str, index: Index, raw: &str| insert_at(json, &path.parse().unwrap(), index, raw).unwrap().map(|edit| edit.apply(json));
+
+        // Tests single line containers
+        let json = r#"{"a":1, "b": [1, 2], "c": {}}"#;
+        assert_eq!(insert(json, "$", "d".into(), "null").unwrap(), r#"{"a":1, "b": [1, 2], "c": {}, "d":null}"#);
+        assert_eq!(insert(json, "$.b", 0.into(), "0").unwrap(), r#"{"a":1, "b": [0, 1, 2], "c": {}}"#);
+        assert_eq!(insert(json, "$.b", 2.into(), " 3 ").unwrap(), r#"{"a":1, "b": [1, 2, 3], "c": {}}"#);
+        assert_eq!(insert(json, "$.c", "q\"".into(), "[]").unwrap(), r#"{"a":1, "b": [1, 2], "c": {"q\"": []}}"#);
+
+        // Tests multi-line containers
+        let json = "{\n\t\"a\": [\n\t\t1\n\t],\n\t\"b\": {\n\t}\n}";
+        assert_eq!(insert(json, "$.a", 1.into(), "2").unwrap(), "{\n\t\"a\": [\n\t\t1,\n\t\t2\n\t],\n\t\"b\": {\n\t}\n}");
+        assert_eq!(insert(json, "$.b", "c".into(), "3").unwrap(), "{\n\t\"a\": [\n\t\t1\n\t],\n\t\"b\": {\n\t\t\"c\": 3\n\t}\n}");
+
+        // Tests invalid insertions
+        assert_eq!(insert(json, "$", "a".into(), "1"), None);
+        assert_eq!(insert(json, "$.a", 2.into(), "1"), None);
+        assert_eq!(insert(json, "$.a", "x".into(), "1"), None);
+        assert_eq!(insert(json, "$.a.0", 0.into(), "1"), None);
+        assert_eq!(insert(json, "$.c", 0.into(), "1"), None);
+        assert!(insert_at(json, &Path::new(), "c", "1,").is_err());
+    }
+
+    #[test]
+    fn remove() {
+        let json = r#"{"a": [1, 2, {"b": null}], "c": "d"}"#;
+        let remove = |path: &str| remove_at(json, &path.parse().unwrap()).unwrap().map(|edit| edit.apply(json));
+
+        assert_eq!(remove("$.a").unwrap(), r#"{"c": "d"}"#);
+        assert_eq!(remove("$.c").unwrap(), r#"{"a": [1, 2, {"b": null}]}"#);
+        assert_eq!(remove("$.a.1").unwrap(), r#"{"a": [1, {"b": null}], "c": "d"}"#);
+        assert_eq!(remove("$.a.2").unwrap(), r#"{"a": [1, 2], "c": "d"}"#);
+        assert_eq!(remove("$.a.2.b").unwrap(), r#"{"a": [1, 2, {}], "c": "d"}"#);
+        assert_eq!(remove_at("[\n  1\n]", &"$.0".parse().unwrap()).unwrap().unwrap().apply("[\n  1\n]"), "[]");
+
+        assert_eq!(remove("$"), None);
+        assert_eq!(remove("$.e"), None);
+        assert!(remove_at("[1,]", &Path::new()).is_err());
+    }
+
+    #[test]
+    fn sort_keys() {
+        let sort = |json: &str, path: &str, options: &Options| sort_keys_at_with(json, &path.parse().unwrap(), options).unwrap().map(|edit| edit.apply(json));
+        let strict = Options::new();
+
+        let json = r#"{"b": 1, "a": 0, "a": {"d": [], "c": null}}"#;
+        assert_eq!(sort(json, "$", &strict).unwrap(), r#"{"a": 0, "a": {"d": [], "c": null}, "b": 1}"#);
+        assert_eq!(sort(json, "$.a", &strict).unwrap(), r#"{"b": 1, "a": 0, "a": {"c": null, "d": []}}"#);
+        assert_eq!(sort("{ }", "$", &strict).unwrap(), "{ }");
+        assert_eq!(sort_keys_at(json, &Path::new()).unwrap(), sort_keys_at_with(json, &Path::new(), &strict).unwrap());
+
+        // Tests comments move with their members, and a trailing comma stays last
+        let jsonc = "{\n  \"b\": 2, /* two, */\n  /* a */\n  // one\n  \"a\": 1 // end\n  ,\n}";
+        let options = Options::new().allow_comments(true).allow_trailing_commas(true);
+        assert_eq!(sort(jsonc, "$", &options).unwrap(), "{\n  /* a */\n  // one\n  \"a\": 1, // end\n  \"b\": 2, /* two, */\n}");
+
+        assert_eq!(sort(json, "$.b", &strict), None);
+        assert_eq!(sort(json, "$.c", &strict), None);
+        assert!(sort_keys_at(jsonc, &Path::new()).is_err());
+
+        // Tests objects closed implicitly at the end of the text
+        let truncated = Options::new().allow_truncated(true);
+        assert_eq!(sort(" {", "$", &truncated), None);
+        assert_eq!(sort(r#"{"b": 1, "a": [2"#, "$", &truncated), None);
+        assert_eq!(sort(r#"{"b": 1, "a": [2"#, "$.a", &truncated), None);
+        assert_eq!(sort(r#"[{"b": 1, "a": 2}"#, "$.0", &truncated).unwrap(), r#"[{"a": 2, "b": 1}"#);
+        assert_eq!(sort(r#"{"b":1, "a":2"#, "$", &Options::new().lenient(true)), None);
+
+        // Tests comments are only moved when they are allowed
+        let lenient = Options::new().lenient(true);
+        let json = "{\n  // c\n  \"b\": 2, /* two */\n  \"a\": [1,],\n}";
+        assert_eq!(sort(json, "$", &lenient), None);
+        assert_eq!(sort("{\"b\": 1, \"a\": \"//\"}", "$", &strict).unwrap(), "{\"a\": \"//\", \"b\": 1}");
+    }
+}
@@ -0,0 +1,138 @@
+use alloc::string::String;
+
+use crate::lines::Position;
+
+/// Kind of problem described by an [`Error`]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[non_exhaustive]
+pub enum ErrorKind {
+    /// A character that can't appear at this point in json
+    InvalidCharacter,
+    /// A string is not allowed here, such as a key without a comma before it
+    InvalidQuote,
+    /// A comma is not allowed here, such as a trailing comma
+    InvalidComma,
+    /// A colon is not allowed here, such as one outside of an object
+    InvalidColon,
+    /// A `}` that doesn't close an object
+    OrphanCurlyBrace,
+    /// A `]` that doesn't close an array
+    OrphanSquareBrace,
+    /// The document ended before its root value was complete
+    UnexpectedEnd,
+    /// An offset or position past the end of the document
+    OffsetOutOfRange,
+    /// Containers are nested deeper than allowed
+    DepthExceeded,
+    /// The input is longer than [`Limits::max_bytes`](crate::Limits::max_bytes), at the offset of the limit
+    SizeExceeded,
+    /// The input has more values than [`Limits::max_nodes`](crate::Limits::max_nodes)
+    NodesExceeded,
+    /// Scanning took longer than [`Limits::time_budget`](crate::Limits::time_budget)
+    TimeExceeded,
+    /// Scanning was stopped through [`Options::cancel_flag`](crate::Options::cancel_flag)
+    Cancelled
+}
+
+impl core::fmt::Display for ErrorKind {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        write!(f, "{}", match self {
+            ErrorKind::InvalidCharacter => "invalid character",
+            ErrorKind::InvalidQuote => "invalid quote",
+            ErrorKind::InvalidComma => "invalid comma",
+            ErrorKind::InvalidColon => "invalid colon",
+            ErrorKind::OrphanCurlyBrace => "orphan curly brace",
+            ErrorKind::OrphanSquareBrace => "orphan square brace",
+            ErrorKind::UnexpectedEnd => "unexpected end of json",
+            ErrorKind::OffsetOutOfRange => "offset out of range",
+            ErrorKind::DepthExceeded => "maximum depth exceeded",
+            ErrorKind::SizeExceeded => "maximum size exceeded",
+            ErrorKind::NodesExceeded => "maximum number of values exceeded",
+            ErrorKind::TimeExceeded => "time budget exceeded",
+            ErrorKind::Cancelled => "scan cancelled"
+        })
+    }
+}
+
+/// Error produced while scanning json, along with where in the text it happened.
+///
+/// # Examples
+///
+/// ```
+/// use jsonposition::{path, ErrorKind};
+///
+/// let error = path("[\n  1,\n  2,\n]", 0).unwrap_err();
+/// assert_eq!(error.kind(), ErrorKind::OrphanSquareBrace);
+/// assert_eq!((error.line(), error.column()), (3, 0));
+/// assert_eq!(error.to_string(), "orphan square brace at line 4 column 1");
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Error {
+    kind: ErrorKind,
+    offset: usize,
+    position: Position
+}
+
+impl Error {
+    /// Creates an error at a byte offset into `bytes`, working out its line and column.
+    pub(crate) fn new(kind: ErrorKind, bytes: &[u8], offset: usize) -> Self {
+        let before = &bytes[..offset.min(bytes.len())];
+        let line_start = before.iter().rposition(|b| *b == b'\n').map_or(0, |i| i + 1);
+        let position = Position {
+            line: before.iter().filter(|b| **b == b'\n').count(),
+            column: String::from_utf8_lossy(&before[line_start..]).chars().count()
+        };
+        Error { kind, offset, position }
+    }
+
+    /// Creates an error at a byte offset whose line and column are already known.
+    pub(crate) fn at(kind: ErrorKind, offset: usize, position: Position) -> Self {
+        Error { kind, offset, position }
+    }
+
+    /// Moves an error found in a part of `bytes` starting at `start` to its place in the whole of `bytes`.
+    pub(crate) fn offset_by(self, bytes: &[u8], start: usize) -> Self {
+        Error::new(self.kind, bytes, start + self.offset)
+    }
+
+    pub fn kind(&self) -> ErrorKind {
+        self.kind
+    }
+
+    /// Byte offset of the problem in the text
+    pub fn offset(&self) -> usize {
+        self.offset
+    }
+
+    /// Zero based line and column of the problem, with the column counted in chars
+    pub fn position(&self) -> Position {
+        self.position
+    }
+
+    /// Zero based line of the problem
+    pub fn line(&self) -> usize {
+        self.position.line
+    }
+
+    /// Zero based column of the problem, counted in chars
+    pub fn column(&self) -> usize {
+        self.position.column
+    }
+}
+
+impl core::fmt::Display for Error {
+    /// Formats the error with one based lines and columns, as most editors display them.
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        write!(f, "{} at line {} column {}", self.kind, self.position.line + 1, self.position.column + 1)
+    }
+}
+
+#[cfg(feature = "std")]
+impl std::error::Error for Error {}
+
+#[cfg(feature = "std")]
+impl From<Error> for std::io::Error {
+    fn from(err: Error) -> std::io::Error {
+        std::io::Error::new(std::io::ErrorKind::InvalidData, err)
+    }
+}
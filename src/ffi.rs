@@ -0,0 +1,214 @@
+use std::ffi::{c_char, CString};
+use std::ptr;
+
+use crate::path::{Index, Path};
+
+// Ownership rules: every pointer handed out by a `jp_` function is owned by the caller
+// and must be released with the matching `_free` function, never with `free()`.
+// Input pointers are only borrowed for the duration of the call.
+
+/// Result of a call through the C interface
+#[repr(C)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum JpStatus {
+    /// The call succeeded and its output was written
+    Ok = 0,
+    /// A required pointer argument was null
+    NullPointer = 1,
+    /// The json is invalid, its byte offset is written to `error_offset` if that isn't null
+    InvalidJson = 2,
+    /// The path contains a nul byte, so can't be returned as a C string
+    InteriorNul = 3
+}
+
+/// Path to an element, owned by the caller and released with [`jp_path_free`]
+pub struct JpPath(Path);
+
+/// One segment of a [`JpPath`], borrowed from it and valid until the path is freed
+#[repr(C)]
+#[derive(Debug, Clone, Copy)]
+pub struct JpSegment {
+    /// UTF-8 bytes of the object key, not nul terminated, or null for an array index
+    pub key: *const u8,
+    /// Length of `key` in bytes
+    pub key_len: usize,
+    /// Array index, zero for an object key
+    pub index: usize
+}
+
+/// Scans `len` bytes of json for the path to a byte offset
+unsafe fn resolve(json: *const u8, len: usize, offset: usize, error_offset: *mut usize) -> Result<Path, JpStatus> {
+    if json.is_null() {
+        return Err(JpStatus::NullPointer);
+    }
+
+    crate::path_bytes(std::slice::from_raw_parts(json, len), offset).map_err(|err| {
+        if !error_offset.is_null() {
+            *error_offset = err.offset();
+        }
+        JpStatus::InvalidJson
+    })
+}
+
+/// Writes a formatted path to `out` as a C string owned by the caller
+unsafe fn write_string(text: String, out: *mut *mut c_char) -> JpStatus {
+    match CString::new(text) {
+        Ok(text) => {
+            *out = text.into_raw();
+            JpStatus::Ok
+        }
+        Err(_) => JpStatus::InteriorNul
+    }
+}
+
+/// Constructs the dot path to a byte offset in `len` bytes of json, such as `$.2.a`.
+/// On success the path is written to `out` as a nul terminated string, to be released with [`jp_string_free`].
+///
+/// # Safety
+///
+/// `json` must point to `len` readable bytes, and `out` must be valid for writes.
+/// `error_offset` must be null or valid for writes.
+#[no_mangle]
+pub unsafe extern "C" fn jp_dot_path(json: *const u8, len: usize, offset: usize, out: *mut *mut c_char, error_offset: *mut usize) -> JpStatus {
+    if out.is_null() {
+        return JpStatus::NullPointer;
+    }
+    match resolve(json, len, offset, error_offset) {
+        Ok(path) => write_string(path.to_string(), out),
+        Err(status) => status
+    }
+}
+
+/// Constructs the JSON Pointer to a byte offset in `len` bytes of json, such as `/2/a`.
+/// On success the pointer is written to `out` as a nul terminated string, to be released with [`jp_string_free`].
+///
+/// # Safety
+///
+/// The same as [`jp_dot_path`].
+#[no_mangle]
+pub unsafe extern "C" fn jp_pointer_path(json: *const u8, len: usize, offset: usize, out: *mut *mut c_char, error_offset: *mut usize) -> JpStatus {
+    if out.is_null() {
+        return JpStatus::NullPointer;
+    }
+    match resolve(json, len, offset, error_offset) {
+        Ok(path) => write_string(path.to_pointer(), out),
+        Err(status) => status
+    }
+}
+
+/// Releases a string returned by [`jp_dot_path`] or [`jp_pointer_path`]. Does nothing if `text` is null.
+///
+/// # Safety
+///
+/// `text` must be null or a string returned by this library that hasn't been freed yet.
+#[no_mangle]
+pub unsafe extern "C" fn jp_string_free(text: *mut c_char) {
+    if !text.is_null() {
+        drop(CString::from_raw(text));
+    }
+}
+
+/// Constructs the path to a byte offset in `len` bytes of json, as separate segments.
+/// On success the path is written to `out`, to be read with [`jp_path_len`] and [`jp_path_segment`]
+/// then released with [`jp_path_free`].
+///
+/// # Safety
+///
+/// The same as [`jp_dot_path`].
+#[no_mangle]
+pub unsafe extern "C" fn jp_path_segments(json: *const u8, len: usize, offset: usize, out: *mut *mut JpPath, error_offset: *mut usize) -> JpStatus {
+    if out.is_null() {
+        return JpStatus::NullPointer;
+    }
+    match resolve(json, len, offset, error_offset) {
+        Ok(path) => {
+            *out = Box::into_raw(Box::new(JpPath(path)));
+            JpStatus::Ok
+        }
+        Err(status) => status
+    }
+}
+
+/// Number of segments in a path, zero for the root or a null path.
+///
+/// # Safety
+///
+/// `path` must be null or a path returned by [`jp_path_segments`] that hasn't been freed yet.
+#[no_mangle]
+pub unsafe extern "C" fn jp_path_len(path: *const JpPath) -> usize {
+    path.as_ref().map_or(0, |path| path.0.len())
+}
+
+/// Writes the segment of a path at `i` to `segment`, returning `false` if `i` is out of range.
+/// Keys are borrowed from the path, so are only valid until it is freed.
+///
+/// # Safety
+///
+/// `path` must be null or a path returned by [`jp_path_segments`] that hasn't been freed yet,
+/// and `segment` must be valid for writes.
+#[no_mangle]
+pub unsafe extern "C" fn jp_path_segment(path: *const JpPath, i: usize, segment: *mut JpSegment) -> bool {
+    let Some(index) = path.as_ref().and_then(|path| path.0.as_slice().get(i)) else {
+        return false;
+    };
+    if segment.is_null() {
+        return false;
+    }
+
+    *segment = match index {
+        Index::Array(index) => JpSegment { key: ptr::null(), key_len: 0, index: *index },
+        Index::Object(key) => JpSegment { key: key.as_ptr(), key_len: key.len(), index: 0 }
+    };
+    true
+}
+
+/// Releases a path returned by [`jp_path_segments`]. Does nothing if `path` is null.
+///
+/// # Safety
+///
+/// `path` must be null or a path returned by [`jp_path_segments`] that hasn't been freed yet.
+#[no_mangle]
+pub unsafe extern "C" fn jp_path_free(path: *mut JpPath) {
+    if !path.is_null() {
+        drop(Box::from_raw(path));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::ffi::CStr;
+
+    #[test]
+    fn c_interface() {
+        let json = r#"[null, 9, {"a": "b"}]"#;
+        let offset = json.find('b').unwrap();
+
+        unsafe {
+            let mut text = ptr::null_mut();
+            assert_eq!(jp_dot_path(json.as_ptr(), json.len(), offset, &mut text, ptr::null_mut()), JpStatus::Ok);
+            assert_eq!(CStr::from_ptr(text).to_str().unwrap(), "$.2.a");
+            jp_string_free(text);
+
+            let mut path = ptr::null_mut();
+            assert_eq!(jp_path_segments(json.as_ptr(), json.len(), offset, &mut path, ptr::null_mut()), JpStatus::Ok);
+            assert_eq!(jp_path_len(path), 2);
+
+            let mut segment = JpSegment { key: ptr::null(), key_len: 0, index: 0 };
+            assert!(jp_path_segment(path, 0, &mut segment));
+            assert!(segment.key.is_null());
+            assert_eq!(segment.index, 2);
+            assert!(jp_path_segment(path, 1, &mut segment));
+            assert_eq!(std::slice::from_raw_parts(segment.key, segment.key_len), b"a");
+            assert!(!jp_path_segment(path, 2, &mut segment));
+            jp_path_free(path);
+
+            // Tests errors
+            let mut error_offset = 0;
+            let invalid = "[1,]";
+            assert_eq!(jp_pointer_path(invalid.as_ptr(), invalid.len(), 0, &mut text, &mut error_offset), JpStatus::InvalidJson);
+            assert_eq!(error_offset, 3);
+            assert_eq!(jp_dot_path(ptr::null(), 0, 0, &mut text, ptr::null_mut()), JpStatus::NullPointer);
+        }
+    }
+}
@@ -0,0 +1,52 @@
+use std::fs::File;
+use std::io;
+
+use memmap2::Mmap;
+
+use crate::options::Options;
+use crate::path::Path;
+use crate::scan;
+
+/// Constructs the path to a byte offset in a json file, memory mapping it rather than reading it into memory.
+/// The whole file is validated, the same as [`path_bytes`](crate::path_bytes).
+///
+/// The file must not be changed by another process while it is being scanned.
+///
+/// # Examples
+///
+/// ```no_run
+/// use jsonposition::path_in_file;
+///
+/// let path = path_in_file("export.json".as_ref(), 1024).expect("Invalid JSON");
+/// println!("{}", path);
+/// ```
+///
+/// # Errors
+///
+/// Returns any error from opening or mapping the file,
+/// or an [`Error`](crate::Error) converted to [`std::io::ErrorKind::InvalidData`] if the json is invalid.
+pub fn path_in_file(file: &std::path::Path, offset: usize) -> io::Result<Path> {
+    let file = File::open(file)?;
+    // SAFETY: the mapping is only read, and the caller is told not to modify the file while it is mapped
+    let map = unsafe { Mmap::map(&file)? };
+    Ok(scan::path_to(&map, offset, &Options::default())?)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn file() {
+        let json = r#"[null, 9, {"a": "b"}]"#;
+        let file = std::env::temp_dir().join(format!("jsonposition-{}.json", std::process::id()));
+        std::fs::write(&file, json).unwrap();
+
+        let path = path_in_file(&file, json.find("b").unwrap());
+        let missing = path_in_file(&file.with_extension("missing"), 0);
+        std::fs::remove_file(&file).unwrap();
+
+        assert_eq!(path.unwrap().to_string(), "$.2.a");
+        assert_eq!(missing.unwrap_err().kind(), io::ErrorKind::NotFound);
+    }
+}
@@ -0,0 +1,295 @@
+use core::ops::Range;
+use alloc::collections::BTreeMap;
+use alloc::string::{String, ToString};
+use alloc::vec::Vec;
+
+use crate::edit::escape;
+use crate::error::Error;
+use crate::path::{Index, Path};
+use crate::scan::{Event, Scanner, Walker};
+
+/// Iterator over the leaves of a raw json string, created by [`flatten`]
+pub struct Flatten<'a> {
+    walker: Walker<'a>,
+    /// Start of the container opened by the previous event, which is a leaf if it closes straight away
+    opened: Option<usize>,
+    span: Range<usize>
+}
+
+/// Flattens a raw json string into the dot path and raw text of each leaf, in the order they appear.
+/// Leaves are strings, numbers, booleans, nulls and empty objects or arrays.
+///
+/// The document is scanned as it is iterated, and the iterator stops after yielding an error.
+///
+/// # Examples
+///
+/// ```
+/// use jsonposition::flatten;
+///
+/// let json = r#"{"a": [1, "b"], "c": {"d": null, "e": []}}"#;
+///
+/// let leaves: Vec<(String, &str)> = flatten(json).collect::<Result<_, _>>().expect("Invalid JSON");
+/// assert_eq!(leaves, [
+///     ("$.a.0".to_string(), "1"),
+///     ("$.a.1".to_string(), r#""b""#),
+///     ("$.c.d".to_string(), "null"),
+///     ("$.c.e".to_string(), "[]")
+/// ]);
+/// ```
+pub fn flatten(text: &str) -> Flatten<'_> {
+    Flatten { walker: Walker::new(text), opened: None, span: 0..0 }
+}
+
+impl Flatten<'_> {
+    /// Path to the leaf yielded last, before it was formatted
+    pub fn path(&self) -> &Path {
+        self.walker.path()
+    }
+
+    /// Span of the leaf yielded last, including the quotes of a string
+    pub fn span(&self) -> Range<usize> {
+        self.span.clone()
+    }
+}
+
+impl<'a> Iterator for Flatten<'a> {
+    type Item = Result<(String, &'a str), Error>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        loop {
+            let event = match self.walker.next()? {
+                Ok(event) => event,
+                Err(e) => return Some(Err(e))
+            };
+            let opened = self.opened.take();
+            let span = match event {
+                Event::StartObject(start) | Event::StartArray(start) => {
+                    self.opened = Some(start);
+                    continue;
+                }
+                Event::EndObject(span) | Event::EndArray(span) if opened.is_some() => span,
+                Event::Scalar(span) => span,
+                _ => continue
+            };
+            self.span = span.clone();
+            return Some(Ok((self.walker.path().to_string(), &self.walker.text()[span])));
+        }
+    }
+}
+
+/// Error returned by [`unflatten`]
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum UnflattenError {
+    /// The raw value at the contained path isn't a single json value, with offsets into the raw value.
+    InvalidValue(Path, Error),
+    /// The leaf at the contained path overlaps an earlier one, as the same path or one starting with the other,
+    /// or its path mixes up an object and an array.
+    Conflict(Path),
+    /// The leaf at the contained path skips elements at the end of an array.
+    MissingIndex(Path)
+}
+
+impl core::fmt::Display for UnflattenError {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        match self {
+            UnflattenError::InvalidValue(path, err) => write!(f, "invalid value at {}: {}", path, err),
+            UnflattenError::Conflict(path) => write!(f, "conflicting value at {}", path),
+            UnflattenError::MissingIndex(path) => write!(f, "missing array elements before {}", path)
+        }
+    }
+}
+
+#[cfg(feature = "std")]
+impl std::error::Error for UnflattenError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            UnflattenError::InvalidValue(_, err) => Some(err),
+            UnflattenError::Conflict(_) | UnflattenError::MissingIndex(_) => None
+        }
+    }
+}
+
+/// Value being built by [`unflatten`]
+enum Node {
+    /// Placeholder for a value the current leaf is about to fill in
+    Vacant,
+    Leaf(String),
+    /// Members in the order their keys first appear, and the position of each key
+    Object(Vec<(String, Node)>, BTreeMap<String, usize>),
+    Array(Vec<Node>)
+}
+
+impl Node {
+    /// Puts a leaf at a path below this node, creating the objects and arrays on the way.
+    fn insert(&mut self, path: &Path, raw: String) -> Result<(), UnflattenError> {
+        let mut node = self;
+        for index in path {
+            if let Node::Vacant = node {
+                *node = match index {
+                    Index::Array(_) => Node::Array(Vec::new()),
+                    Index::Object(_) => Node::Object(Vec::new(), BTreeMap::new())
+                };
+            }
+            node = match (node, index) {
+                (Node::Array(elements), Index::Array(i)) => {
+                    if *i == elements.len() {
+                        elements.push(Node::Vacant);
+                    }
+                    elements.get_mut(*i).ok_or_else(|| UnflattenError::MissingIndex(path.clone()))?
+                }
+                // An index into an object is a key written in digits
+                (Node::Object(members, positions), index) => {
+                    let key = index.to_string();
+                    let i = *positions.entry(key.clone()).or_insert_with(|| {
+                        members.push((key, Node::Vacant));
+                        members.len() - 1
+                    });
+                    &mut members[i].1
+                }
+                _ => return Err(UnflattenError::Conflict(path.clone()))
+            };
+        }
+
+        match node {
+            Node::Vacant => {
+                *node = Node::Leaf(raw);
+                Ok(())
+            }
+            _ => Err(UnflattenError::Conflict(path.clone()))
+        }
+    }
+
+    fn write(&self, out: &mut String) {
+        match self {
+            Node::Vacant => {}
+            Node::Leaf(raw) => out.push_str(raw),
+            Node::Object(members, _) => {
+                out.push('{');
+                for (i, (key, value)) in members.iter().enumerate() {
+                    if i > 0 {
+                        out.push(',');
+                    }
+                    out.push('"');
+                    out.push_str(&escape(key));
+                    out.push_str("\":");
+                    value.write(out);
+                }
+                out.push('}');
+            }
+            Node::Array(elements) => {
+                out.push('[');
+                for (i, element) in elements.iter().enumerate() {
+                    if i > 0 {
+                        out.push(',');
+                    }
+                    element.write(out);
+                }
+                out.push(']');
+            }
+        }
+    }
+}
+
+/// Builds a compact json string from the path and raw text of each of its leaves, the inverse of [`flatten`].
+/// Leaves sharing the start of their paths are merged into the same objects and arrays,
+/// with object members in the order their keys first appear.
+///
+/// Array indices must count up from 0, though leaves of different elements can be given in any order.
+/// An index into an object is taken as a key. Returns `None` if there are no leaves.
+///
+/// # Examples
+///
+/// ```
+/// use jsonposition::{flatten, unflatten, Path};
+///
+/// let leaves = [("$.a.0", "1"), ("$.b", "{}"), ("$.a.1.c", r#""d""#)];
+///
+/// let json = unflatten(leaves.map(|(path, raw)| (path.parse::<Path>().unwrap(), raw))).unwrap().unwrap();
+/// assert_eq!(json, r#"{"a":[1,{"c":"d"}],"b":{}}"#);
+///
+/// let pretty = "{\n  \"a\": [1, 2]\n}";
+/// let leaves = flatten(pretty).map(|leaf| leaf.map(|(path, raw)| (path.parse::<Path>().unwrap(), raw)));
+/// assert_eq!(unflatten(leaves.collect::<Result<Vec<_>, _>>().unwrap()).unwrap().unwrap(), r#"{"a":[1,2]}"#);
+/// ```
+///
+/// # Errors
+///
+/// Returns an [`UnflattenError`] if a raw value isn't a single json value, if two leaves overlap,
+/// or if an array index skips elements.
+pub fn unflatten<I, R>(leaves: I) -> Result<Option<String>, UnflattenError>
+where
+    I: IntoIterator<Item = (Path, R)>,
+    R: AsRef<str>
+{
+    let mut root = Node::Vacant;
+    for (path, raw) in leaves {
+        let raw = raw.as_ref();
+        Scanner::new(raw.as_bytes()).try_for_each(|event| event.map(|_| ())).map_err(|err| UnflattenError::InvalidValue(path.clone(), err))?;
+        root.insert(&path, String::from(raw.trim()))?;
+    }
+
+    if let Node::Vacant = root {
+        return Ok(None);
+    }
+    let mut out = String::new();
+    root.write(&mut out);
+    Ok(Some(out))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn leaves() {
+        let json = r#" [{}, [[ ], {"a": "x.y", "b": [true, -1.5e3]}], "z"] "#;
+
+        let leaves: Vec<(String, &str)> = flatten(json).map(Result::unwrap).collect();
+        assert_eq!(leaves, [
+            ("$.0".to_string(), "{}"),
+            ("$.1.0".to_string(), "[ ]"),
+            ("$.1.1.a".to_string(), r#""x.y""#),
+            ("$.1.1.b.0".to_string(), "true"),
+            ("$.1.1.b.1".to_string(), "-1.5e3"),
+            ("$.2".to_string(), r#""z""#)
+        ]);
+
+        let mut spans = flatten(json);
+        spans.nth(2).unwrap().unwrap();
+        assert_eq!((spans.path(), &json[spans.span()]), (&"$.1.1.a".parse().unwrap(), r#""x.y""#));
+
+        assert_eq!(flatten("7").map(Result::unwrap).collect::<Vec<_>>(), [("$".to_string(), "7")]);
+        assert_eq!(flatten("{}").map(Result::unwrap).collect::<Vec<_>>(), [("$".to_string(), "{}")]);
+
+        // Tests that leaves before an error are still yielded
+        let mut invalid = flatten("[1, 2,]");
+        assert_eq!(invalid.next().unwrap().unwrap(), ("$.0".to_string(), "1"));
+        assert_eq!(invalid.next().unwrap().unwrap(), ("$.1".to_string(), "2"));
+        assert!(invalid.next().unwrap().is_err());
+        assert!(invalid.next().is_none());
+    }
+
+    #[test]
+    fn unflattened() {
+        let unflat = |leaves: &[(&str, &str)]| unflatten(leaves.iter().map(|(path, raw)| (path.parse::<Path>().unwrap(), *raw)));
+
+        let json = r#" [{}, [[ ], {"a": "x.y", "b": [true, -1.5e3]}], "z"] "#;
+        let leaves: Vec<(Path, &str)> = flatten(json).map(|leaf| leaf.map(|(path, raw)| (path.parse().unwrap(), raw))).collect::<Result<_, _>>().unwrap();
+        assert_eq!(unflatten(leaves).unwrap().as_deref(), Some(r#"[{},[[ ],{"a":"x.y","b":[true,-1.5e3]}],"z"]"#));
+        assert_eq!(unflatten([(Path::from(vec![Index::from("\"\n")]), "1")]).unwrap().as_deref(), Some(r#"{"\"\n":1}"#));
+
+        assert_eq!(unflat(&[("$.0", "2"), ("$", "3")]), Err(UnflattenError::Conflict(Path::new())));
+        assert_eq!(unflat(&[("$", "1"), ("$.0", "2")]), Err(UnflattenError::Conflict("$.0".parse().unwrap())));
+        assert_eq!(unflat(&[("$.a", "{}"), ("$.a.b", "2")]), Err(UnflattenError::Conflict("$.a.b".parse().unwrap())));
+        assert_eq!(unflat(&[("$.a", "1"), ("$.a", "2")]), Err(UnflattenError::Conflict("$.a".parse().unwrap())));
+        assert_eq!(unflat(&[("$.0", "1"), ("$.a", "2")]), Err(UnflattenError::Conflict("$.a".parse().unwrap())));
+        assert_eq!(unflat(&[("$.0.a", "1"), ("$.2.a", "2")]), Err(UnflattenError::MissingIndex("$.2.a".parse().unwrap())));
+        assert!(matches!(unflat(&[("$.a", "[1,]")]), Err(UnflattenError::InvalidValue(..))));
+        assert!(matches!(unflat(&[("$.a", " ")]), Err(UnflattenError::InvalidValue(..))));
+
+        // Tests that digit keys join an object and leaves of earlier elements can come later
+        assert_eq!(unflat(&[("$.a", "1"), ("$.0", "2")]).unwrap().as_deref(), Some(r#"{"a":1,"0":2}"#));
+        assert_eq!(unflat(&[("$.0.a", "1"), ("$.1", "2"), ("$.0.b", "3")]).unwrap().as_deref(), Some(r#"[{"a":1,"b":3},2]"#));
+        assert_eq!(unflat(&[]), Ok(None));
+    }
+}
@@ -0,0 +1,337 @@
+use alloc::format;
+use alloc::string::{String, ToString};
+
+use crate::path::{is_identifier, Path};
+
+/// When [`format_path`] writes a key quoted in brackets
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum Quoting {
+    /// Every key is written after the separator as it is, as in `$.a b.c`
+    #[default]
+    Never,
+    /// Keys that aren't identifiers are quoted, as in `$["a b"].c`
+    WhenNeeded,
+    /// Every key is quoted, as in `$["a b"]["c"]`
+    Always
+}
+
+/// Options controlling how [`format_path`] writes a [`Path`].
+///
+/// The defaults give dot notation with every key written as it is,
+/// unlike [`dot_path`](crate::dot_path) which quotes keys that would be ambiguous.
+///
+/// # Examples
+///
+/// ```
+/// use jsonposition::{format_path, Path, PathOptions, Quoting};
+///
+/// let path: Path = "$.items.0.file name".parse().unwrap();
+///
+/// assert_eq!(format_path(&path, &PathOptions::new()), "$.items.0.file name");
+/// assert_eq!(format_path(&path, &PathOptions::new().root("").separator("/")), "items/0/file name");
+///
+/// let options = PathOptions::new().root("doc").bracket_indices(true).quoting(Quoting::WhenNeeded).quote('\'');
+/// assert_eq!(format_path(&path, &options), "doc.items[0]['file name']");
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PathOptions {
+    pub(crate) root: String,
+    pub(crate) separator: String,
+    pub(crate) bracket_indices: bool,
+    pub(crate) quoting: Quoting,
+    pub(crate) quote: char
+}
+
+impl Default for PathOptions {
+    fn default() -> Self {
+        PathOptions { root: String::from("$"), separator: String::from("."), bracket_indices: false, quoting: Quoting::Never, quote: '"' }
+    }
+}
+
+impl PathOptions {
+    /// Creates the options for dot notation, `$.1.fields.2`.
+    pub fn new() -> Self {
+        PathOptions::default()
+    }
+
+    /// Sets the text the path starts with, `$` by default.
+    /// With an empty root the separator before the first segment is left out too, as in `fields.2`.
+    pub fn root(mut self, root: &str) -> Self {
+        self.root = root.to_string();
+        self
+    }
+
+    /// Sets the text written before each key and array index that isn't in brackets, `.` by default.
+    pub fn separator(mut self, separator: &str) -> Self {
+        self.separator = separator.to_string();
+        self
+    }
+
+    /// Writes array indices in brackets, as in `$.fields[2]`, instead of after the separator.
+    pub fn bracket_indices(mut self, bracket_indices: bool) -> Self {
+        self.bracket_indices = bracket_indices;
+        self
+    }
+
+    /// Sets which keys are quoted in brackets, [`Quoting::Never`] by default.
+    pub fn quoting(mut self, quoting: Quoting) -> Self {
+        self.quoting = quoting;
+        self
+    }
+
+    /// Sets the quote around keys in brackets, `"` by default.
+    /// Quoted keys are escaped like a json string, with the quote itself escaped by a backslash.
+    pub fn quote(mut self, quote: char) -> Self {
+        self.quote = quote;
+        self
+    }
+
+    /// Writes the separator before a segment, unless it comes straight after an empty root
+    fn separate(&self, out: &mut String, first: bool) {
+        if !first || !self.root.is_empty() {
+            out.push_str(&self.separator);
+        }
+    }
+}
+
+/// Writes a key between quotes, escaping it like a json string
+fn push_quoted(out: &mut String, key: &str, quote: char) {
+    out.push(quote);
+    for c in key.chars() {
+        match c {
+            '\\' => out.push_str("\\\\"),
+            '\n' => out.push_str("\\n"),
+            '\r' => out.push_str("\\r"),
+            '\t' => out.push_str("\\t"),
+            c if c == quote => {
+                out.push('\\');
+                out.push(c);
+            }
+            c if u32::from(c) < 0x20 => out.push_str(&format!("\\u{:04x}", u32::from(c))),
+            c => out.push(c)
+        }
+    }
+    out.push(quote);
+}
+
+/// Callbacks writing each part of a [`Path`] for [`Path::format`], to produce notations that
+/// [`PathOptions`] can't describe without post-processing the string.
+///
+/// Each segment is appended to `out` in order from the root, `first` being set for the segment written first.
+///
+/// # Examples
+///
+/// ```
+/// use jsonposition::{Path, PathFormatter};
+///
+/// /// Elasticsearch field path, which leaves array indices out
+/// struct Field;
+///
+/// impl PathFormatter for Field {
+///     fn index(&self, _out: &mut String, _index: usize, _first: bool) {}
+///
+///     fn key(&self, out: &mut String, key: &str, _first: bool) {
+///         if !out.is_empty() {
+///             out.push('.');
+///         }
+///         out.push_str(key);
+///     }
+/// }
+///
+/// let path: Path = "$.user.addresses.0.city".parse().unwrap();
+/// assert_eq!(path.format(&Field), "user.addresses.city");
+/// ```
+pub trait PathFormatter {
+    /// Starts the output before any segment is written, writing nothing by default
+    fn root(&self, _out: &mut String) {}
+
+    /// Appends an array index
+    fn index(&self, out: &mut String, index: usize, first: bool);
+
+    /// Appends an object key
+    fn key(&self, out: &mut String, key: &str, first: bool);
+}
+
+impl PathFormatter for PathOptions {
+    fn root(&self, out: &mut String) {
+        out.push_str(&self.root);
+    }
+
+    fn index(&self, out: &mut String, index: usize, first: bool) {
+        if self.bracket_indices {
+            out.push('[');
+            out.push_str(&index.to_string());
+            out.push(']');
+        } else {
+            self.separate(out, first);
+            out.push_str(&index.to_string());
+        }
+    }
+
+    fn key(&self, out: &mut String, key: &str, first: bool) {
+        if self.quoting == Quoting::Always || (self.quoting == Quoting::WhenNeeded && !is_identifier(key)) {
+            out.push('[');
+            push_quoted(out, key, self.quote);
+            out.push(']');
+        } else {
+            self.separate(out, first);
+            out.push_str(key);
+        }
+    }
+}
+
+/// Path filter for [jq](https://jqlang.github.io/jq/manual/#object-identifier-index), used by [`Path::to_jq`]
+pub(crate) struct Jq;
+
+impl PathFormatter for Jq {
+    fn root(&self, out: &mut String) {
+        out.push('.');
+    }
+
+    fn index(&self, out: &mut String, index: usize, _first: bool) {
+        out.push('[');
+        out.push_str(&index.to_string());
+        out.push(']');
+    }
+
+    fn key(&self, out: &mut String, key: &str, first: bool) {
+        if !is_identifier(key) {
+            out.push('[');
+            push_quoted(out, key, '"');
+            out.push(']');
+        } else {
+            if !first {
+                out.push('.');
+            }
+            out.push_str(key);
+        }
+    }
+}
+
+/// JavaScript property accessors on a variable, used by [`Path::to_js`]
+pub(crate) struct Js<'a>(pub(crate) &'a str);
+
+impl PathFormatter for Js<'_> {
+    fn root(&self, out: &mut String) {
+        out.push_str(self.0);
+    }
+
+    fn index(&self, out: &mut String, index: usize, _first: bool) {
+        out.push('[');
+        out.push_str(&index.to_string());
+        out.push(']');
+    }
+
+    fn key(&self, out: &mut String, key: &str, _first: bool) {
+        // `$` is allowed anywhere in a JavaScript identifier
+        let identifier = key.starts_with(|c: char| c.is_ascii_alphabetic() || c == '_' || c == '$')
+            && key.chars().all(|c| c.is_ascii_alphanumeric() || c == '_' || c == '$');
+        if identifier {
+            out.push('.');
+            out.push_str(key);
+        } else {
+            out.push('[');
+            push_quoted(out, key, '"');
+            out.push(']');
+        }
+    }
+}
+
+/// Python subscripts on a variable, used by [`Path::to_python`]
+pub(crate) struct Python<'a>(pub(crate) &'a str);
+
+impl PathFormatter for Python<'_> {
+    fn root(&self, out: &mut String) {
+        out.push_str(self.0);
+    }
+
+    fn index(&self, out: &mut String, index: usize, _first: bool) {
+        out.push('[');
+        out.push_str(&index.to_string());
+        out.push(']');
+    }
+
+    fn key(&self, out: &mut String, key: &str, _first: bool) {
+        // Dicts have no attribute access, so every key is subscripted
+        out.push('[');
+        push_quoted(out, key, '"');
+        out.push(']');
+    }
+}
+
+/// Formats a path in the notation described by [`PathOptions`],
+/// for tools that expect a different syntax from the ones [`Path`] writes itself.
+///
+/// # Examples
+///
+/// ```
+/// use jsonposition::{format_path, Path, PathOptions, Quoting};
+///
+/// let path: Path = "$.config.items.0.name".parse().unwrap();
+///
+/// let options = PathOptions::new().root("").bracket_indices(true);
+/// assert_eq!(format_path(&path, &options), "config.items[0].name");
+///
+/// let options = PathOptions::new().bracket_indices(true).quoting(Quoting::Always);
+/// assert_eq!(format_path(&path, &options), path.to_bracket());
+/// ```
+pub fn format_path(path: &Path, options: &PathOptions) -> String {
+    path.format(options)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::path::Index;
+    use alloc::vec;
+
+    #[test]
+    fn formats() {
+        let path: Path = vec![Index::Array(1), Index::Object(String::from("a b")), Index::Object(String::from("c")), Index::Array(2)].into();
+
+        assert_eq!(format_path(&path, &PathOptions::new()), "$.1.a b.c.2");
+        assert_eq!(format_path(&path, &PathOptions::new().bracket_indices(true).quoting(Quoting::Always)), r#"$[1]["a b"]["c"][2]"#);
+        assert_eq!(format_path(&path, &PathOptions::new().bracket_indices(true).quoting(Quoting::WhenNeeded)), r#"$[1]["a b"].c[2]"#);
+        assert_eq!(format_path(&path, &PathOptions::new().root("").separator("/")), "1/a b/c/2");
+        assert_eq!(format_path(&path, &PathOptions::new().root("").bracket_indices(true)), "[1].a b.c[2]");
+        assert_eq!(format_path(&path, &PathOptions::new().root("x").separator("::")), "x::1::a b::c::2");
+
+        // Tests root paths and escaping with another quote
+        assert_eq!(format_path(&Path::new(), &PathOptions::new()), "$");
+        assert_eq!(format_path(&Path::new(), &PathOptions::new().root("")), "");
+        let key: Path = vec![Index::Object(String::from("it's \"\\\n"))].into();
+        assert_eq!(format_path(&key, &PathOptions::new().quoting(Quoting::Always).quote('\'')), r#"$['it\'s "\\\n']"#);
+        assert_eq!(format_path(&key, &PathOptions::new().quoting(Quoting::Always)), r#"$["it's \"\\\n"]"#);
+    }
+
+    /// Protobuf field mask, in lower camel case with indices left out
+    struct FieldMask;
+
+    impl PathFormatter for FieldMask {
+        fn index(&self, _out: &mut String, _index: usize, _first: bool) {}
+
+        fn key(&self, out: &mut String, key: &str, _first: bool) {
+            if !out.is_empty() {
+                out.push('.');
+            }
+            let mut words = key.split('_');
+            out.extend(words.next());
+            for word in words {
+                let mut chars = word.chars();
+                out.extend(chars.next().map(|c| c.to_ascii_uppercase()));
+                out.push_str(chars.as_str());
+            }
+        }
+    }
+
+    #[test]
+    fn formatter() {
+        let path: Path = "$.user_info.0.home_address.zip_code".parse().unwrap();
+        assert_eq!(path.format(&FieldMask), "userInfo.homeAddress.zipCode");
+        assert_eq!(Path::new().format(&FieldMask), "");
+        assert_eq!(path.format(&PathOptions::new()), path.to_string());
+        assert_eq!(path.format(&Jq), ".user_info[0].home_address.zip_code");
+        assert_eq!(path.format(&Js("obj")), "obj.user_info[0].home_address.zip_code");
+        assert_eq!(path.format(&Python("data")), r#"data["user_info"][0]["home_address"]["zip_code"]"#);
+    }
+}
@@ -0,0 +1,120 @@
+use core::ops::Range;
+use alloc::string::String;
+
+use crate::error::Error;
+use crate::locate::{locate, Kind};
+use crate::path::Path;
+
+/// Longest preview in chars before it is cut short
+const PREVIEW_LENGTH: usize = 80;
+
+/// Summary of the value or key under an offset, for an editor hover tooltip.
+///
+/// Formatting a hover with [`Display`](core::fmt::Display) gives the dot path and kind on the first line
+/// and the preview on the second.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Hover {
+    /// Path to the value, or to the value of the member for a key
+    pub path: Path,
+    /// Kind of the value, [`Kind::ObjectKey`] for a key
+    pub kind: Kind,
+    /// Span of the value or key
+    pub span: Range<usize>,
+    /// Raw text of the value or key on a single line, cut short with `…` past 80 chars
+    pub preview: String
+}
+
+impl core::fmt::Display for Hover {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        write!(f, "{} ({})\n{}", self.path, self.kind, self.preview)
+    }
+}
+
+/// Raw text on a single line, with each run of whitespace spanning lines replaced by a space.
+/// Strings can't contain a raw line break, so only the whitespace between tokens is changed.
+fn preview(raw: &str) -> String {
+    let mut preview = String::new();
+    let mut chars = 0;
+    let mut rest = raw;
+
+    while let Some(c) = rest.chars().next() {
+        let run = rest.len() - rest.trim_start().len();
+        let c = if run > 0 && rest[..run].contains('\n') {
+            rest = &rest[run..];
+            ' '
+        } else {
+            rest = &rest[c.len_utf8()..];
+            c
+        };
+
+        if chars == PREVIEW_LENGTH {
+            preview.push('…');
+            break;
+        }
+        preview.push(c);
+        chars += 1;
+    }
+
+    preview
+}
+
+/// Finds the path, kind and a short preview of the innermost value or key containing a byte offset in a raw json string,
+/// for showing in an editor hover tooltip.
+///
+/// Returns `None` if the offset is outside of the root value.
+///
+/// # Examples
+///
+/// ```
+/// use jsonposition::{hover, Kind};
+///
+/// let json = "{\n  \"a\": [\n    1,\n    2\n  ]\n}";
+///
+/// let hover = hover(json, json.find("[").unwrap()).expect("Invalid JSON").unwrap();
+/// assert_eq!(hover.kind, Kind::Array);
+/// assert_eq!(hover.preview, "[ 1, 2 ]");
+/// assert_eq!(hover.to_string(), "$.a (array)\n[ 1, 2 ]");
+/// ```
+///
+/// # Errors
+///
+/// Returns an [`Error`](crate::Error) if the input json is invalid.
+pub fn hover(text: &str, offset: usize) -> Result<Option<Hover>, Error> {
+    Ok(locate(text, offset)?.map(|location| Hover {
+        preview: preview(&text[location.span.clone()]),
+        path: location.path,
+        kind: location.kind,
+        span: location.span
+    }))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn previews() {
+        assert_eq!(preview("{\n  \"a\": \"b  c\",\r\n  \"d\": 1\n}"), r#"{ "a": "b  c", "d": 1 }"#);
+        assert_eq!(preview("[1, 2]"), "[1, 2]");
+
+        let long = format!("\"{}\"", "é".repeat(100));
+        let cut = preview(&long);
+        assert_eq!(cut.chars().count(), PREVIEW_LENGTH + 1);
+        assert!(cut.ends_with("éé…"));
+        assert_eq!(preview(&long[..PREVIEW_LENGTH * 2 - 1]).chars().count(), PREVIEW_LENGTH);
+    }
+
+    #[test]
+    fn hovers() {
+        let json = r#"[9, {"field": true}]"#;
+
+        let found = hover(json, json.find("true").unwrap()).unwrap().unwrap();
+        assert_eq!(found.to_string(), "$.1.field (boolean)\ntrue");
+        assert_eq!(&json[found.span], "true");
+
+        let found = hover(json, json.find("field").unwrap()).unwrap().unwrap();
+        assert_eq!((found.kind, found.preview.as_str()), (Kind::ObjectKey, r#""field""#));
+
+        assert_eq!(hover(json, 100).unwrap(), None);
+    }
+}
@@ -0,0 +1,920 @@
+use core::ops::Range;
+use alloc::vec::Vec;
+
+use crate::error::Error;
+use crate::locate::Kind;
+use crate::options::Options;
+use crate::path::{Index, Path, RelativePath};
+use crate::scan::{self, Event, PathTracker, Walker};
+
+/// Value or key recorded by a [`DocumentIndex`]
+#[derive(Debug, Clone)]
+struct Node {
+    path: Path,
+    span: Range<usize>,
+    kind: Kind,
+    /// Position of the enclosing node in the index
+    parent: Option<usize>
+}
+
+/// Index over a json document built in a single scan, for looking up many offsets quickly.
+///
+/// Building the index validates the document, and each lookup afterwards is a binary search
+/// rather than a scan, so it suits tools querying the same text on every cursor move.
+/// Offsets are byte offsets into the indexed text.
+///
+/// # Examples
+///
+/// ```
+/// use jsonposition::DocumentIndex;
+///
+/// let json = r#"[null, 9, {"a": [1, 2]}]"#;
+/// let index = DocumentIndex::new(json).expect("Invalid JSON");
+///
+/// assert_eq!(index.path_at(json.find("2").unwrap()).to_string(), "$.2.a.1");
+/// assert_eq!(index.path_at(json.find("9").unwrap()).to_string(), "$.1");
+/// ```
+#[derive(Debug, Clone)]
+pub struct DocumentIndex {
+    /// Start of each event that changes the path, and the path after it
+    steps: Vec<(usize, Path)>,
+    /// Every value and key, sorted by where they start with outer nodes first
+    nodes: Vec<Node>,
+    /// Options the text was scanned with, for scanning it again after an edit
+    options: Options
+}
+
+impl DocumentIndex {
+    /// Indexes a raw json string.
+    ///
+    /// # Errors
+    ///
+    /// Returns an [`Error`](crate::Error) if the input json is invalid.
+    pub fn new(text: &str) -> Result<Self, Error> {
+        DocumentIndex::with_options(text, &Options::default())
+    }
+
+    /// Indexes a raw json string, scanned with the given [`Options`].
+    ///
+    /// # Errors
+    ///
+    /// Returns an [`Error`](crate::Error) if the input json is invalid for the given options.
+    pub fn with_options(text: &str, options: &Options) -> Result<Self, Error> {
+        DocumentIndex::build(Walker::with_options(text, options), options)
+    }
+
+    /// Indexes a raw json string, scanned with the given [`Options`],
+    /// calling `progress` with the number of bytes indexed so far roughly every megabyte and once at the end.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use jsonposition::{DocumentIndex, Options};
+    ///
+    /// let json = r#"[null, 9, {"a": [1, 2]}]"#;
+    /// let mut reports = Vec::new();
+    /// DocumentIndex::with_progress(json, &Options::new(), |bytes| reports.push(bytes)).expect("Invalid JSON");
+    ///
+    /// assert_eq!(reports, [json.len()]);
+    /// ```
+    ///
+    /// # Errors
+    ///
+    /// Returns an [`Error`](crate::Error) if the input json is invalid for the given options.
+    pub fn with_progress(text: &str, options: &Options, progress: impl FnMut(usize)) -> Result<Self, Error> {
+        let mut walker = Walker::with_options(text, options);
+        walker.on_progress(progress);
+        DocumentIndex::build(walker, options)
+    }
+
+    fn build(mut walker: Walker, options: &Options) -> Result<Self, Error> {
+        let bytes = walker.text().as_bytes();
+        let mut steps = Vec::new();
+        let mut nodes = Vec::new();
+        let mut tracker = PathTracker::with_options(options);
+        while let Some(event) = walker.next() {
+            let event = event?;
+            let node = match &event {
+                Event::Scalar(span) => Some((span.clone(), Kind::of_scalar(&bytes[span.clone()]))),
+                Event::Key(span) => Some((span.clone(), Kind::ObjectKey)),
+                Event::EndObject(span) => Some((span.clone(), Kind::Object)),
+                Event::EndArray(span) => Some((span.clone(), Kind::Array)),
+                _ => None
+            };
+            if let Some((span, kind)) = node {
+                nodes.push(Node { path: walker.path().clone(), span, kind, parent: None });
+            }
+
+            if !matches!(event, Event::Scalar(_)) {
+                tracker.update(bytes, &event);
+                steps.push((walker.start_of(&event), tracker.path().clone()));
+            }
+        }
+
+        // Containers are only complete once they end, sort them ahead of what they contain
+        nodes.sort_by_key(|node| (node.span.start, core::cmp::Reverse(node.span.end)));
+        let mut open: Vec<usize> = Vec::new();
+        for i in 0..nodes.len() {
+            while open.last().is_some_and(|j| nodes[*j].span.end <= nodes[i].span.start) {
+                open.pop();
+            }
+            nodes[i].parent = open.last().copied();
+            open.push(i);
+        }
+
+        Ok(DocumentIndex { steps, nodes, options: options.clone() })
+    }
+
+    /// Updates the index for the replacement of the byte range `range` of the indexed text by `new_text`,
+    /// where `text` is the whole text after the edit.
+    ///
+    /// Only the innermost object or array around the edit is scanned again, and the spans after it are moved,
+    /// so small edits to a large document are much faster than indexing it again.
+    /// The whole text is indexed again if the edit touches the brackets of every container around it,
+    /// if the container doesn't scan as a single value on its own,
+    /// or if the index was built with [`Options::lenient`], [`Options::allow_truncated`], [`Options::allow_multiple_documents`],
+    /// [`Limits::max_bytes`](crate::Limits::max_bytes) or [`Limits::max_nodes`](crate::Limits::max_nodes),
+    /// which all depend on the document as a whole.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use jsonposition::DocumentIndex;
+    ///
+    /// let json = r#"{"a": [1, 2], "b": null}"#;
+    /// let mut index = DocumentIndex::new(json).expect("Invalid JSON");
+    ///
+    /// // Replaces `1` with `{"c": 0}`
+    /// let edited = r#"{"a": [{"c": 0}, 2], "b": null}"#;
+    /// index.apply_edit(edited, 7..8, r#"{"c": 0}"#).expect("Invalid JSON");
+    ///
+    /// assert_eq!(index.path_at(edited.find('0').unwrap()).to_string(), "$.a.0.c");
+    /// assert_eq!(index.path_at(edited.find("null").unwrap()).to_string(), "$.b");
+    /// ```
+    ///
+    /// # Errors
+    ///
+    /// Returns an [`Error`](crate::Error) if the edited json is invalid, leaving the index as it was.
+    pub fn apply_edit(&mut self, text: &str, range: Range<usize>, new_text: &str) -> Result<(), Error> {
+        let options = &self.options;
+        let incremental = !options.lenient && !options.truncated && !options.multiple && options.limits.bytes.is_none() && options.limits.nodes.is_none();
+        // Moves an offset after the edit to where it is in the edited text
+        let shift = |offset: usize| offset + new_text.len() - range.len();
+        let container = incremental.then(|| self.container_around(&range)).flatten()
+            .map(|c| (c, self.nodes[c].span.clone()))
+            .filter(|(_, old)| text.get(old.start..shift(old.end)).is_some());
+        let Some((c, old)) = container else {
+            *self = DocumentIndex::with_options(text, &self.options)?;
+            return Ok(());
+        };
+        let new = old.start..shift(old.end);
+
+        // The container is scanned on its own, so containers around it don't count towards the depth
+        let prefix = self.nodes[c].path.clone();
+        let mut options = self.options.clone();
+        options.max_depth = options.max_depth.map(|depth| depth.saturating_sub(prefix.len()));
+        // Anything but a single value filling the container's place, such as an edit splitting it in two,
+        // needs the text around the container to tell if it is valid
+        let part = DocumentIndex::with_options(&text[new.clone()], &options).ok().filter(|part| {
+            part.nodes.first().is_some_and(|root| root.span == (0..new.len())) && part.nodes.iter().filter(|node| node.parent.is_none()).count() == 1
+        });
+        let Some(part) = part else {
+            *self = DocumentIndex::with_options(text, &self.options)?;
+            return Ok(());
+        };
+
+        let end = c + 1 + self.nodes[c + 1..].partition_point(|node| node.span.start < old.end);
+        let (removed, added) = (end - c, part.nodes.len());
+        for node in &mut self.nodes[..c] {
+            // Only the containers around the edit end after it
+            if node.span.end >= old.end {
+                node.span.end = shift(node.span.end);
+            }
+        }
+        for node in &mut self.nodes[end..] {
+            node.span = shift(node.span.start)..shift(node.span.end);
+            node.parent = node.parent.map(|parent| if parent >= end { parent + added - removed } else { parent });
+        }
+        let parent = self.nodes[c].parent;
+        self.nodes.splice(c..end, part.nodes.into_iter().map(|node| Node {
+            path: prefix.join(&node.path),
+            span: node.span.start + new.start..node.span.end + new.start,
+            kind: node.kind,
+            parent: node.parent.map_or(parent, |i| Some(i + c))
+        }));
+
+        let first = self.steps.partition_point(|(start, _)| *start < old.start);
+        let last = self.steps.partition_point(|(start, _)| *start < old.end);
+        for (start, _) in &mut self.steps[last..] {
+            *start = shift(*start);
+        }
+        self.steps.splice(first..last, part.steps.into_iter().map(|(start, path)| (start + new.start, prefix.join(&path))));
+
+        Ok(())
+    }
+
+    /// Position of the innermost object or array whose brackets are both outside of a byte range
+    fn container_around(&self, range: &Range<usize>) -> Option<usize> {
+        let mut next = self.nodes.partition_point(|node| node.span.start < range.start).checked_sub(1);
+        while let Some(i) = next {
+            let node = &self.nodes[i];
+            if matches!(node.kind, Kind::Object | Kind::Array) && node.span.start < range.start && range.end < node.span.end {
+                return Some(i);
+            }
+            next = node.parent;
+        }
+        None
+    }
+
+    /// Every value and key with its path, span and kind, in the order they start with containers first.
+    /// A key comes directly before the value of its member.
+    #[cfg(feature = "lsp")]
+    pub(crate) fn nodes(&self) -> impl Iterator<Item = (&Path, &Range<usize>, Kind)> {
+        self.nodes.iter().map(|node| (&node.path, &node.span, node.kind))
+    }
+
+    /// Path to a byte offset, the same as [`path_bytes`](crate::path_bytes) on the indexed text.
+    pub fn path_at(&self, offset: usize) -> Path {
+        match self.steps.partition_point(|(start, _)| *start < offset) {
+            0 => Path::new(),
+            i => self.steps[i - 1].1.clone()
+        }
+    }
+
+    /// Every value and key whose span contains a byte offset, from the root down.
+    /// Keys have the path of their value, the same as in [`locate`](crate::locate).
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use jsonposition::{DocumentIndex, Kind};
+    ///
+    /// let json = r#"{"a": [1, 2]}"#;
+    /// let index = DocumentIndex::new(json).expect("Invalid JSON");
+    ///
+    /// let kinds: Vec<Kind> = index.nodes_containing(json.find("2").unwrap()).into_iter().map(|(_, _, kind)| kind).collect();
+    /// assert_eq!(kinds, [Kind::Object, Kind::Array, Kind::Number]);
+    /// ```
+    pub fn nodes_containing(&self, offset: usize) -> Vec<(Path, Range<usize>, Kind)> {
+        // Every node containing the offset is the last node to start at or before it, or one of its ancestors
+        let mut found = Vec::new();
+        let mut next = self.nodes.partition_point(|node| node.span.start <= offset).checked_sub(1);
+        while let Some(i) = next {
+            let node = &self.nodes[i];
+            if node.span.contains(&offset) {
+                found.push((node.path.clone(), node.span.clone(), node.kind));
+            }
+            next = node.parent;
+        }
+        found.reverse();
+        found
+    }
+
+    /// Every value and key whose span overlaps a byte range, in the order they start.
+    /// An empty range is treated as a single offset, the same as [`DocumentIndex::nodes_containing`].
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use jsonposition::DocumentIndex;
+    ///
+    /// let json = r#"[1, 2, 3, 4]"#;
+    /// let index = DocumentIndex::new(json).expect("Invalid JSON");
+    ///
+    /// let paths: Vec<String> = index.nodes_intersecting(4..8).into_iter().map(|(path, _, _)| path.to_string()).collect();
+    /// assert_eq!(paths, ["$", "$.1", "$.2"]);
+    /// ```
+    pub fn nodes_intersecting(&self, range: Range<usize>) -> Vec<(Path, Range<usize>, Kind)> {
+        if range.is_empty() {
+            return self.nodes_containing(range.start);
+        }
+
+        let mut found: Vec<_> = self.nodes_containing(range.start).into_iter().filter(|(_, span, _)| span.start < range.start).collect();
+        let first = self.nodes.partition_point(|node| node.span.start < range.start);
+        let last = self.nodes.partition_point(|node| node.span.start < range.end);
+        found.extend(self.nodes[first..last].iter().map(|node| (node.path.clone(), node.span.clone(), node.kind)));
+        found
+    }
+
+    /// Cursor on the root value, `None` if the index holds no values.
+    pub fn cursor(&self) -> Option<Cursor<'_>> {
+        self.nodes.iter().position(|node| node.kind != Kind::ObjectKey).map(|node| Cursor { index: self, node })
+    }
+
+    /// Cursor on the innermost value containing a byte offset, `None` if the offset is outside of the root value.
+    /// An offset on an object key gives the value of that member,
+    /// or the object if the key has no value, as lenient and truncated text allow.
+    pub fn cursor_at(&self, offset: usize) -> Option<Cursor<'_>> {
+        let mut next = self.nodes.partition_point(|node| node.span.start <= offset).checked_sub(1);
+        while let Some(i) = next {
+            let node = &self.nodes[i];
+            if node.span.contains(&offset) {
+                if node.kind != Kind::ObjectKey {
+                    return Some(Cursor { index: self, node: i });
+                }
+                // The value of a member is the node after its key
+                let value = self.nodes.get(i + 1).filter(|value| value.kind != Kind::ObjectKey && value.parent == node.parent);
+                return match value {
+                    Some(_) => Some(Cursor { index: self, node: i + 1 }),
+                    None => node.parent.map(|parent| Cursor { index: self, node: parent })
+                };
+            }
+            next = node.parent;
+        }
+        None
+    }
+
+    /// Cursor on the value at a path, `None` if the document has no value there.
+    /// The last member is taken if a key is repeated, as serde_json does.
+    pub fn cursor_to(&self, path: &Path) -> Option<Cursor<'_>> {
+        path.iter().try_fold(self.cursor()?, |cursor, index| match index {
+            Index::Array(i) => cursor.children().nth(*i).filter(|_| cursor.kind() == Kind::Array),
+            Index::Object(_) => cursor.children().filter(|child| child.path().last() == Some(index)).last().filter(|_| cursor.kind() == Kind::Object)
+        })
+    }
+
+    /// Elements of the array or members of the object at a path, in order.
+    /// Lists nothing for a scalar, and returns `None` if the document has no value at the path.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use jsonposition::{DocumentIndex, Index, Path};
+    ///
+    /// let json = r#"{"a": 1, "b": [true]}"#;
+    /// let index = DocumentIndex::new(json).expect("Invalid JSON");
+    ///
+    /// let children = index.children(&Path::new()).unwrap();
+    /// assert_eq!(children[1].index, Index::Object("b".to_string()));
+    /// assert_eq!(&json[children[1].key_span.clone().unwrap()], r#""b""#);
+    /// assert_eq!(&json[children[1].span.clone()], "[true]");
+    /// ```
+    pub fn children(&self, path: &Path) -> Option<Vec<Child>> {
+        let cursor = self.cursor_to(path)?;
+        Some(cursor.children().filter_map(|child| Some(Child {
+            index: child.path().last()?.clone(),
+            key_span: child.key_span(),
+            span: child.span(),
+            kind: child.kind()
+        })).collect())
+    }
+}
+
+/// Element of an array or member of an object, as listed by [`children`]
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Child {
+    /// Array index or object key of the child
+    pub index: Index,
+    /// Span of the key including its quotes, `None` for an array element
+    pub key_span: Option<Range<usize>>,
+    /// Span of the value
+    pub span: Range<usize>,
+    pub kind: Kind
+}
+
+/// Position on a value in a [`DocumentIndex`], for walking the document structurally.
+/// Object keys are skipped, moving onto a member moves onto its value.
+///
+/// # Examples
+///
+/// ```
+/// use jsonposition::DocumentIndex;
+///
+/// let json = r#"{"a": [1, 2], "b": null}"#;
+/// let index = DocumentIndex::new(json).expect("Invalid JSON");
+///
+/// let one = index.cursor_at(json.find("1").unwrap()).unwrap();
+/// assert_eq!(one.path().to_string(), "$.a.0");
+///
+/// let two = one.next_sibling().unwrap();
+/// assert_eq!(&json[two.span()], "2");
+///
+/// let b = two.parent().unwrap().next_sibling().unwrap();
+/// assert_eq!(b.path().to_string(), "$.b");
+/// assert_eq!(b.prev_sibling().unwrap().first_child().unwrap(), one);
+/// ```
+#[derive(Debug, Clone, Copy)]
+pub struct Cursor<'a> {
+    index: &'a DocumentIndex,
+    /// Position of the value in the nodes of the index
+    node: usize
+}
+
+impl PartialEq for Cursor<'_> {
+    fn eq(&self, other: &Self) -> bool {
+        core::ptr::eq(self.index, other.index) && self.node == other.node
+    }
+}
+
+impl Eq for Cursor<'_> {}
+
+impl<'a> Cursor<'a> {
+    fn node(&self) -> &'a Node {
+        &self.index.nodes[self.node]
+    }
+
+    fn at(&self, node: usize) -> Cursor<'a> {
+        Cursor { index: self.index, node }
+    }
+
+    /// Path to the value
+    pub fn path(&self) -> &'a Path {
+        &self.node().path
+    }
+
+    /// Byte range of the value in the indexed text
+    pub fn span(&self) -> Range<usize> {
+        self.node().span.clone()
+    }
+
+    /// Kind of the value
+    pub fn kind(&self) -> Kind {
+        self.node().kind
+    }
+
+    /// Byte range of the key, including its quotes, if the value is an object member
+    pub fn key_span(&self) -> Option<Range<usize>> {
+        let before = self.node.checked_sub(1).map(|i| &self.index.nodes[i])?;
+        (before.kind == Kind::ObjectKey && before.parent == self.node().parent).then(|| before.span.clone())
+    }
+
+    /// The object or array containing the value, `None` for the root
+    pub fn parent(&self) -> Option<Cursor<'a>> {
+        self.node().parent.map(|node| self.at(node))
+    }
+
+    /// The first element or member value of an object or array, `None` if it is empty or not a container
+    pub fn first_child(&self) -> Option<Cursor<'a>> {
+        let nodes = &self.index.nodes;
+        // Children directly follow their container, with a key before each member
+        let first = self.node + 1;
+        let first = if nodes.get(first)?.kind == Kind::ObjectKey { first + 1 } else { first };
+        (nodes.get(first)?.parent == Some(self.node)).then(|| self.at(first))
+    }
+
+    /// Every element or member value of an object or array, in order
+    pub fn children(&self) -> impl Iterator<Item = Cursor<'a>> {
+        core::iter::successors(self.first_child(), Cursor::next_sibling)
+    }
+
+    /// The element or member value after this one in the same container
+    pub fn next_sibling(&self) -> Option<Cursor<'a>> {
+        let nodes = &self.index.nodes;
+        let parent = self.node().parent;
+        // Everything inside the value comes right after it, so the next sibling is the next node to start after it ends
+        let next = self.node + nodes[self.node..].partition_point(|node| node.span.start < self.node().span.end);
+        let next = if nodes.get(next)?.kind == Kind::ObjectKey { next + 1 } else { next };
+        (nodes.get(next)?.parent == parent).then(|| self.at(next))
+    }
+
+    /// The element or member value before this one in the same container
+    pub fn prev_sibling(&self) -> Option<Cursor<'a>> {
+        let nodes = &self.index.nodes;
+        let parent = self.node().parent;
+        // The node before is the key of this member, the parent, or inside the previous sibling
+        let mut i = self.node.checked_sub(1)?;
+        if nodes[i].kind == Kind::ObjectKey && nodes[i].parent == parent {
+            i = i.checked_sub(1)?;
+        }
+        loop {
+            if Some(i) == parent {
+                return None;
+            }
+            if nodes[i].parent == parent {
+                return Some(self.at(i));
+            }
+            i = nodes[i].parent?;
+        }
+    }
+}
+
+/// Every value in a raw json string with its path, span and kind, in the order they start.
+/// Containers come before the values inside them, and object keys are left out.
+///
+/// The whole document is validated before the first value is yielded.
+///
+/// # Examples
+///
+/// ```
+/// use jsonposition::{all_paths, Kind};
+///
+/// let json = r#"{"a": [1, "b"]}"#;
+///
+/// let values: Vec<(String, &str, Kind)> = all_paths(json).expect("Invalid JSON").map(|(path, span, kind)| (path.to_string(), &json[span], kind)).collect();
+/// assert_eq!(values, [
+///     ("$".to_string(), json, Kind::Object),
+///     ("$.a".to_string(), r#"[1, "b"]"#, Kind::Array),
+///     ("$.a.0".to_string(), "1", Kind::Number),
+///     ("$.a.1".to_string(), r#""b""#, Kind::String)
+/// ]);
+/// ```
+///
+/// # Errors
+///
+/// Returns an [`Error`](crate::Error) if the input json is invalid.
+pub fn all_paths(text: &str) -> Result<impl Iterator<Item = (Path, Range<usize>, Kind)>, Error> {
+    let index = DocumentIndex::new(text)?;
+    Ok(index.nodes.into_iter().filter(|node| node.kind != Kind::ObjectKey).map(|node| (node.path, node.span, node.kind)))
+}
+
+/// Elements of the array or members of the object at a path in a raw json string, in order.
+/// Builds a [`DocumentIndex`], use [`DocumentIndex::children`] to list the children of many values.
+///
+/// Lists nothing for a scalar, and returns `None` if the document has no value at the path.
+///
+/// # Examples
+///
+/// ```
+/// use jsonposition::children;
+///
+/// let json = r#"{"a": [1, {"b": 2}]}"#;
+///
+/// let elements = children(json, &"$.a".parse().unwrap()).expect("Invalid JSON").unwrap();
+/// assert_eq!(elements.iter().map(|child| &json[child.span.clone()]).collect::<Vec<_>>(), ["1", r#"{"b": 2}"#]);
+/// assert!(elements[0].key_span.is_none());
+/// ```
+///
+/// # Errors
+///
+/// Returns an [`Error`](crate::Error) if the input json is invalid.
+pub fn children(text: &str, path: &Path) -> Result<Option<Vec<Child>>, Error> {
+    Ok(DocumentIndex::new(text)?.children(path))
+}
+
+/// Span of a value, including its key if it is an object member
+fn member_span(cursor: &Cursor) -> Range<usize> {
+    let span = cursor.span();
+    cursor.key_span().map_or(span.clone(), |key| key.start..span.end)
+}
+
+/// Finds the span of the array element or object member after the innermost value containing a byte offset,
+/// for moving a cursor structurally. A member spans from the start of its key to the end of its value.
+///
+/// Returns `None` if the value is the last in its container, or the offset is outside of the root value.
+///
+/// # Examples
+///
+/// ```
+/// use jsonposition::{next_sibling_span, prev_sibling_span};
+///
+/// let json = r#"{"a": [1, "]"], "b": null}"#;
+///
+/// let next = next_sibling_span(json, json.find("1").unwrap()).expect("Invalid JSON").unwrap();
+/// assert_eq!(&json[next], r#""]""#);
+///
+/// let next = next_sibling_span(json, json.find("1").unwrap() - 1).expect("Invalid JSON").unwrap();
+/// assert_eq!(&json[next], r#""b": null"#);
+///
+/// let prev = prev_sibling_span(json, json.find("null").unwrap()).expect("Invalid JSON").unwrap();
+/// assert_eq!(&json[prev], r#""a": [1, "]"]"#);
+/// ```
+///
+/// # Errors
+///
+/// Returns an [`Error`](crate::Error) if the input json is invalid.
+pub fn next_sibling_span(text: &str, offset: usize) -> Result<Option<Range<usize>>, Error> {
+    let index = DocumentIndex::new(text)?;
+    Ok(index.cursor_at(offset).and_then(|cursor| cursor.next_sibling()).map(|sibling| member_span(&sibling)))
+}
+
+/// Finds the span of the array element or object member before the innermost value containing a byte offset.
+/// See [`next_sibling_span`].
+///
+/// # Errors
+///
+/// Returns an [`Error`](crate::Error) if the input json is invalid.
+pub fn prev_sibling_span(text: &str, offset: usize) -> Result<Option<Range<usize>>, Error> {
+    let index = DocumentIndex::new(text)?;
+    Ok(index.cursor_at(offset).and_then(|cursor| cursor.prev_sibling()).map(|sibling| member_span(&sibling)))
+}
+
+/// Finds the path and span of the deepest value containing both of two byte offsets in a raw json string,
+/// such as the scope of a selection or of several cursors. Object keys count as part of their object.
+///
+/// Returns `None` if either offset is outside of the root value.
+///
+/// # Examples
+///
+/// ```
+/// use jsonposition::common_ancestor;
+///
+/// let json = r#"{"a": [1, {"b": 2}], "c": 3}"#;
+///
+/// let (path, span) = common_ancestor(json, json.find("1").unwrap(), json.find("2").unwrap()).expect("Invalid JSON").unwrap();
+/// assert_eq!(path.to_string(), "$.a");
+/// assert_eq!(&json[span], r#"[1, {"b": 2}]"#);
+/// ```
+///
+/// # Errors
+///
+/// Returns an [`Error`](crate::Error) if the input json is invalid.
+pub fn common_ancestor(text: &str, a: usize, b: usize) -> Result<Option<(Path, Range<usize>)>, Error> {
+    let index = DocumentIndex::new(text)?;
+    let values = |offset| index.nodes_containing(offset).into_iter().filter(|(_, _, kind)| *kind != Kind::ObjectKey);
+
+    // Both lists start at the root, so the last value they share is the deepest
+    Ok(values(a).zip(values(b)).take_while(|(a, b)| a == b).last().map(|((path, span, _), _)| (path, span)))
+}
+
+/// Finds the way from the value at one byte offset in a raw json string to the value at another,
+/// going up to their common ancestor and then down. Offsets are resolved the same as [`path_bytes`](crate::path_bytes),
+/// so an offset in a key or outside of the root value is the value of that member or the root.
+///
+/// # Examples
+///
+/// ```
+/// use jsonposition::relative_path;
+///
+/// let json = r#"{"a": [1, {"b": 2}], "c": 3}"#;
+///
+/// let relative = relative_path(json, json.find("1").unwrap(), json.find("2").unwrap()).expect("Invalid JSON");
+/// assert_eq!(relative.to_string(), "1/1/b");
+/// assert!(relative.is_disjoint());
+/// ```
+///
+/// # Errors
+///
+/// Returns an [`Error`](crate::Error) if the input json is invalid.
+pub fn relative_path(text: &str, from: usize, to: usize) -> Result<RelativePath, Error> {
+    let paths = scan::paths_to(text.as_bytes(), &[from, to], &Options::default())?;
+    Ok(paths[0].relative_to(&paths[1]))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use alloc::format;
+
+    #[test]
+    fn lookups() {
+        let json = r#" [{}, {"é": "}", "b": [[], 1, {"c": null}]}, 9] "#;
+        let index = DocumentIndex::new(json).unwrap();
+
+        for offset in 0..=json.len() + 1 {
+            assert_eq!(index.path_at(offset), crate::path_bytes(json.as_bytes(), offset).unwrap(), "{}", offset);
+        }
+
+        assert!(DocumentIndex::new("[1,]").is_err());
+    }
+
+    #[test]
+    fn progress() {
+        let json = format!("[{}0]", "0, ".repeat(500_000));
+        let mut reports = Vec::new();
+        DocumentIndex::with_progress(&json, &Options::new(), |bytes| reports.push(bytes)).unwrap();
+
+        assert_eq!(reports.len(), 2);
+        assert!(reports[0] >= 1 << 20);
+        assert_eq!(reports[1], json.len());
+    }
+
+    #[test]
+    fn cancel() {
+        use std::sync::atomic::{AtomicBool, Ordering};
+
+        let json = format!("[{}0]", "0, ".repeat(100_000));
+        let cancel = std::sync::Arc::new(AtomicBool::new(false));
+        let options = Options::new().cancel_flag(cancel.clone());
+
+        assert!(DocumentIndex::with_options(&json, &options).is_ok());
+        cancel.store(true, Ordering::Relaxed);
+        assert_eq!(DocumentIndex::with_options(&json, &options).unwrap_err().kind(), crate::ErrorKind::Cancelled);
+    }
+
+    #[test]
+    fn intervals() {
+        let json = r#" [{}, {"a": "}", "b": [[], 1, {"c": null}]}, 9] "#;
+        let index = DocumentIndex::new(json).unwrap();
+        let text = |nodes: Vec<(Path, Range<usize>, Kind)>| nodes.into_iter().map(|(_, span, _)| &json[span]).collect::<Vec<_>>();
+
+        // Tests every containing node matches value_span and locate
+        for offset in 0..json.len() {
+            let innermost = index.nodes_containing(offset).pop();
+            let location = crate::locate(json, offset).unwrap();
+            assert_eq!(innermost.as_ref().map(|(path, _, _)| path), location.as_ref().map(|l| &l.path), "{}", offset);
+            assert_eq!(innermost.map(|(_, span, _)| span), location.map(|l| l.span), "{}", offset);
+        }
+
+        assert_eq!(text(index.nodes_containing(json.find("null").unwrap())), [json.trim(), r#"{"a": "}", "b": [[], 1, {"c": null}]}"#, r#"[[], 1, {"c": null}]"#, r#"{"c": null}"#, "null"]);
+        assert!(index.nodes_containing(0).is_empty());
+
+        let start = json.find("1,").unwrap();
+        assert_eq!(text(index.nodes_intersecting(start..start + 6)), [json.trim(), r#"{"a": "}", "b": [[], 1, {"c": null}]}"#, r#"[[], 1, {"c": null}]"#, "1", r#"{"c": null}"#, r#""c""#]);
+        assert!(index.nodes_intersecting(100..200).is_empty());
+    }
+
+    #[test]
+    fn every_path() {
+        let json = r#"[{}, {"a": "}", "b": [[], 1]}, 9]"#;
+
+        // Tests each value's span leads back to its path
+        let all: Vec<_> = all_paths(json).unwrap().collect();
+        assert_eq!(all.len(), 8);
+        for (path, span, _) in &all {
+            assert_eq!(crate::span_of(json, path).unwrap().as_ref(), Some(span));
+        }
+        assert!(all.windows(2).all(|w| w[0].1.start <= w[1].1.start));
+
+        assert_eq!(all_paths("1").unwrap().count(), 1);
+        assert!(all_paths("[1,]").is_err());
+    }
+
+    #[test]
+    fn cursors() {
+        let json = r#" [{}, {"é": "}", "b": [[], 1, {"c": null}]}, 9] "#;
+        let index = DocumentIndex::new(json).unwrap();
+        let root = index.cursor().unwrap();
+
+        // Tests walking every value in order matches all_paths
+        let mut walked = Vec::new();
+        let mut next = Some(root);
+        while let Some(cursor) = next {
+            walked.push((cursor.path().clone(), cursor.span(), cursor.kind()));
+            next = cursor.first_child().or_else(|| {
+                let mut up = Some(cursor);
+                while let Some(cursor) = up {
+                    if let Some(sibling) = cursor.next_sibling() {
+                        return Some(sibling);
+                    }
+                    up = cursor.parent();
+                }
+                None
+            });
+        }
+        assert_eq!(walked, all_paths(json).unwrap().collect::<Vec<_>>());
+
+        // Tests moving back
+        let nine = index.cursor_at(json.find('9').unwrap()).unwrap();
+        let object = nine.prev_sibling().unwrap();
+        assert_eq!(object.path().to_string(), "$.1");
+        assert_eq!(object.prev_sibling().unwrap().path().to_string(), "$.0");
+        assert_eq!(object.prev_sibling().unwrap().prev_sibling(), None);
+        assert_eq!(object.prev_sibling().unwrap().first_child(), None);
+        assert_eq!(nine.next_sibling(), None);
+        assert_eq!(root.parent(), None);
+
+        // Tests keys
+        let b = index.cursor_at(json.find("\"b").unwrap()).unwrap();
+        assert_eq!(b.path().to_string(), "$.1.b");
+        assert_eq!(&json[b.key_span().unwrap()], "\"b\"");
+        assert_eq!(b.prev_sibling().unwrap().path().to_string(), "$.1.é");
+        assert_eq!(nine.key_span(), None);
+        assert!(index.cursor_at(0).is_none());
+    }
+
+    #[test]
+    fn lenient_paths() {
+        let lenient = Options::new().lenient(true);
+        let paths = |json: &str| -> Vec<String> {
+            let index = DocumentIndex::with_options(json, &lenient).unwrap();
+            index.nodes_intersecting(0..json.len()).into_iter().map(|(path, _, kind)| format!("{} {}", path, kind)).collect()
+        };
+
+        // Tests a key without a value leaves the path once its object ends
+        assert_eq!(paths(r#"[{"a": 1, "b"}, 2]"#), [
+            "$ array", "$.0 object", "$.0.a object key", "$.0.a number", "$.0.b object key", "$.1 number"
+        ]);
+        assert_eq!(paths(r#"{"a": {"b"}, "c": 1}"#), [
+            "$ object", "$.a object key", "$.a object", "$.a.b object key", "$.c object key", "$.c number"
+        ]);
+        let index = DocumentIndex::with_options(r#"{"a": {"b"}}"#, &lenient).unwrap();
+        assert!(index.cursor().unwrap().path().is_root());
+    }
+
+    #[test]
+    fn keys_without_values() {
+        let lenient = Options::new().lenient(true);
+        let truncated = Options::new().allow_truncated(true);
+        // Tests the cursor on a key without a value is on its object
+        for (json, key, options, path) in [
+            (r#"{"a"}"#, "\"a", &lenient, "$"),
+            (r#"[{"a": 1, "b"}]"#, "\"b", &lenient, "$.0"),
+            (r#"{"a": {"b"}, "c": 1}"#, "\"b", &lenient, "$.a"),
+            (r#"{"a""#, "\"a", &truncated, "$"),
+            (r#"[{"a": 1, "b""#, "\"b", &truncated, "$.0")
+        ] {
+            let index = DocumentIndex::with_options(json, options).unwrap();
+            let cursor = index.cursor_at(json.find(key).unwrap()).unwrap();
+            assert_eq!(cursor.path().to_string(), path, "Cursor on {} in {}", key, json);
+            assert_eq!(cursor.kind(), Kind::Object, "Kind at {} in {}", key, json);
+            assert_eq!(&json[cursor.span()][..1], "{", "Span at {} in {}", key, json);
+        }
+    }
+
+    #[test]
+    fn child_lists() {
+        let json = r#" [{}, {"é": "}", "b": [[], 1], "é": 2}, 9] "#;
+        let index = DocumentIndex::new(json).unwrap();
+        let list = |path: &str| index.children(&path.parse().unwrap()).map(|children| {
+            children.into_iter().map(|child| (child.index.to_string(), &json[child.span])).collect::<Vec<_>>()
+        });
+
+        assert_eq!(list("$").unwrap(), [("0".to_string(), "{}"), ("1".to_string(), r#"{"é": "}", "b": [[], 1], "é": 2}"#), ("2".to_string(), "9")]);
+        assert_eq!(list("$.1").unwrap().len(), 3);
+        assert_eq!(list("$.1.b").unwrap()[1], ("1".to_string(), "1"));
+        assert_eq!(list("$.1.é").unwrap(), []);
+        assert_eq!(list("$.0").unwrap(), []);
+        assert_eq!(list("$.2"), Some(Vec::new()));
+
+        // Tests missing paths
+        assert_eq!(list("$.3"), None);
+        assert_eq!(list("$.1.0"), None);
+        assert_eq!(list("$.0.a"), None);
+
+        // Tests the last member is taken if a key is repeated
+        let cursor = index.cursor_to(&"$.1.é".parse().unwrap()).unwrap();
+        assert_eq!(&json[cursor.span()], "2");
+        assert!(children("[1,]", &Path::new()).is_err());
+    }
+
+    #[test]
+    fn siblings() {
+        let json = r#" [9, {"a": "}", "b": [[], 1]}, "x"] "#;
+        let next = |s: &str| next_sibling_span(json, json.find(s).unwrap()).unwrap().map(|span| &json[span]);
+        let prev = |s: &str| prev_sibling_span(json, json.find(s).unwrap()).unwrap().map(|span| &json[span]);
+
+        assert_eq!(next("9"), Some(r#"{"a": "}", "b": [[], 1]}"#));
+        assert_eq!(next("{\"a"), Some(r#""x""#));
+        assert_eq!(next("\"a"), Some(r#""b": [[], 1]"#));
+        assert_eq!(next("[]"), Some("1"));
+        assert_eq!(prev("1]"), Some("[]"));
+        assert_eq!(prev("\"b"), Some(r#""a": "}""#));
+        assert_eq!(prev("\"x"), Some(r#"{"a": "}", "b": [[], 1]}"#));
+
+        // Tests values at either end
+        assert_eq!(next("\"x"), None);
+        assert_eq!(prev("9"), None);
+        assert_eq!(next("[9"), None);
+        assert_eq!(next_sibling_span(json, 0).unwrap(), None);
+    }
+
+    #[test]
+    fn ancestors() {
+        let json = r#" [9, {"a": "}", "b": [[], 1]}, "x"] "#;
+        let ancestor = |a: &str, b: &str| {
+            common_ancestor(json, json.find(a).unwrap(), json.find(b).unwrap()).unwrap().map(|(path, span)| (path.to_string(), &json[span]))
+        };
+
+        assert_eq!(ancestor("[]", "1]"), Some(("$.1.b".to_string(), "[[], 1]")));
+        assert_eq!(ancestor("\"a", "1]"), Some(("$.1".to_string(), r#"{"a": "}", "b": [[], 1]}"#)));
+        assert_eq!(ancestor("9", "\"x"), Some(("$".to_string(), json.trim())));
+        assert_eq!(ancestor("1]", "1]"), Some(("$.1.b.1".to_string(), "1")));
+        assert_eq!(ancestor(" [", "9"), None);
+    }
+
+    #[test]
+    fn relative() {
+        let json = r#" [9, {"a": "}", "b": [[], 1]}, "x"] "#;
+        let relative = |a: &str, b: &str| relative_path(json, json.find(a).unwrap(), json.find(b).unwrap()).unwrap().to_string();
+
+        assert_eq!(relative("[]", "1]"), "1/1");
+        assert_eq!(relative("\"x", "[]"), "1/1/b/0");
+        assert_eq!(relative("{", "\"}"), "0/a");
+        assert_eq!(relative("1]", "9"), "3/0");
+        assert_eq!(relative("1]", "1]"), "0");
+        assert!(relative_path("[1,]", 1, 2).is_err());
+    }
+
+    #[test]
+    fn edits() {
+        let json = r#" {"a": [1, {"b": "]"}], "c": {"d": [[]]}, "e": 2} "#;
+        let edits: [(&str, &str); 7] = [
+            ("1", r#"{"x": [3, 4]}"#),
+            (r#""]""#, "[]"),
+            (r#"[]"#, r#"[5, {"y": 6}]"#),
+            (r#""d": [[]]"#, r#""f": 7, "g": 8"#),
+            (", \"e\": 2", ""),
+            ("2", "\"two\""),
+            (" {", "[")
+        ];
+
+        // Tests edits that split the container around them, and ones only valid on their own
+        let multiple = Options::new().allow_multiple_documents(true);
+        let cases = edits.iter().map(|(old, new)| (json, *old, *new, Options::new()))
+            .chain([("[[1], [2]]", "1", "1], [3", Options::new()), ("[[1, 2]]", "2]", "2] [3]", multiple.clone()), ("[[1, 2]] [3]", "2", "4", multiple)]);
+
+        for (json, old, new, options) in cases {
+            let start = json.find(old).unwrap();
+            let range = start..start + old.len();
+            let edited = format!("{}{}{}", &json[..range.start], new, &json[range.end..]);
+            let mut index = DocumentIndex::with_options(json, &options).unwrap();
+
+            match DocumentIndex::with_options(&edited, &options) {
+                Ok(expected) => {
+                    index.apply_edit(&edited, range, new).unwrap();
+                    assert_eq!(format!("{:?}", index), format!("{:?}", expected), "{}", edited);
+                }
+                Err(expected) => {
+                    let before = format!("{:?}", index);
+                    assert_eq!(index.apply_edit(&edited, range, new).unwrap_err(), expected, "{}", edited);
+                    assert_eq!(format!("{:?}", index), before);
+                }
+            }
+        }
+
+        // Tests the depth limit still counts the containers around the edit
+        let options = Options::new().max_depth(3);
+        let mut index = DocumentIndex::with_options("[[[1]]]", &options).unwrap();
+        assert_eq!(index.apply_edit("[[[[]]]]", 3..4, "[]").unwrap_err().kind(), crate::ErrorKind::DepthExceeded);
+    }
+}
@@ -13,59 +13,98 @@
 //! assert_eq!(dotted, "$.1.fields.2");
 //! ```
 
-extern crate oxidized_json_checker;
+#![cfg_attr(not(feature = "std"), no_std)]
 
-/// Index or key into an array or object
-#[derive(Debug, PartialEq, Eq)]
-pub enum Index {
-    Array(usize),
-    Object(String)
-}
-
-impl Index {
-    fn increment(&mut self) {
-        if let Index::Array(ref mut i) = self {
-            *i += 1;
-        }
-    }
-}
-
-impl std::fmt::Display for Index {
-    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
-        write!(f, "{}", match self {
-            Index::Array(i) => i.to_string(),
-            Index::Object(key) => key.to_owned()
-        })
-    }
-}
-
-#[derive(PartialEq)]
-enum Current {
-    Array,
-    Object,
-    None
-}
+extern crate alloc;
 
-fn end_quote(chars: &Vec<char>, start: usize) -> usize {
-    let mut i = start;
-    while i < chars.len() {
-        if chars[i] == '\\' {
-            i += 2;
-        }
+use alloc::string::{String, ToString};
+use alloc::vec;
+use alloc::vec::Vec;
 
-        if chars[i] == '"' {
-            break;
-        }
-        i += 1;
-    }
-    i
-}
+#[cfg(feature = "tokio")]
+mod async_read;
+mod diff;
+mod edit;
+mod error;
+#[cfg(feature = "ffi")]
+pub mod ffi;
+#[cfg(feature = "memmap2")]
+mod file;
+mod flatten;
+mod format;
+mod hover;
+mod index;
+mod lines;
+mod lint;
+mod locate;
+#[cfg(feature = "lsp")]
+pub mod lsp;
+mod options;
+#[cfg(feature = "rayon")]
+mod parallel;
+mod parser;
+#[cfg(feature = "pyo3")]
+mod python;
+mod query;
+mod path;
+#[cfg(feature = "serde_path_to_error")]
+mod path_to_error;
+mod records;
+mod reformat;
+mod scan;
+mod schema;
+mod search;
+mod span;
+#[cfg(feature = "std")]
+mod split;
+mod spanned;
+mod stream;
+#[cfg(feature = "serde_json")]
+mod typed;
+mod visit;
+#[cfg(feature = "wasm-bindgen")]
+pub mod wasm;
 
-fn substring(str: &Vec<char>, start: usize, end: usize) -> String {
-    str.iter().skip(start).take(end-start).collect()
-}
+#[cfg(feature = "tokio")]
+pub use async_read::{path_from_async_reader, AsyncEvents};
+pub use diff::{diff, merge_patch, to_patch, Change, ChangeKind, DiffError, Patch, PatchOp, PatchOperation};
+pub use edit::{insert_at, remove_at, rename_key, set_raw, sort_keys_at, sort_keys_at_with, Edit};
+pub use error::{Error, ErrorKind};
+#[cfg(feature = "memmap2")]
+pub use file::path_in_file;
+pub use flatten::{flatten, unflatten, Flatten, UnflattenError};
+pub use format::{format_path, PathFormatter, PathOptions, Quoting};
+pub use hover::{hover, Hover};
+pub use index::{all_paths, children, common_ancestor, next_sibling_span, prev_sibling_span, relative_path, Child, Cursor, DocumentIndex};
+pub use lines::{dot_path_at, dot_path_with_unit, path_at, path_with_unit, LineIndex, OffsetUnit, Position};
+pub use lint::{lint, lint_with, Diagnostic, LintOptions, Rule};
+pub use locate::{kind_at, locate, Kind, Location};
+pub use options::{Limits, Options};
+#[cfg(feature = "rayon")]
+pub use parallel::par_paths;
+pub use parser::{ParseEvent, Parser};
+pub use path::{Index, Path, ParsePathError, RelativePath};
+#[cfg(feature = "serde_path_to_error")]
+pub use path_to_error::span_of_serde_path;
+pub use query::{paths_matching, query, ParsePatternError, PathPattern, QueryError};
+pub use records::{path_in_documents, path_in_lines, path_in_sequence};
+pub use reformat::{minify, prettify, SourceMap};
+pub use schema::{spans_of_pointers, KeyCompletion, KeyCompletions, Schema, SchemaLocation};
+pub use search::{duplicate_keys, find_key, find_key_by, find_values, find_values_by, DuplicateKey, KeyMatch, ValueMatch};
+pub use span::{extract, folding_ranges, folding_ranges_with, get_raw, matching_delimiter, path_for_range, selection_ranges, span_of, value_at, value_span};
+#[cfg(feature = "std")]
+pub use split::split_reader;
+pub use spanned::{SpannedMember, SpannedNode, SpannedValue};
+#[cfg(feature = "std")]
+pub use stream::path_from_reader;
+pub use stream::StreamScanner;
+#[cfg(feature = "serde_json")]
+pub use typed::{path_of_error, value_at_offset, value_at_path};
+pub use visit::{visit, visit_with, Visit};
 
 /// Constructs the path to an index in a raw json string.
+/// The returned [`Path`] lists each array index or object key from the root down.
+/// The index is counted in chars, see [`path_bytes`] and [`path_with_unit`] for other units.
 ///
 /// # Examples
 /// 
@@ -80,81 +119,9 @@ fn substring(str: &Vec<char>, start: usize, end: usize) -> String {
 /// 
 /// # Errors
 /// 
-/// Returns [`oxidized_json_checker::Error`] if the input json is invalid.
-/// 
-/// [`oxidized_json_checker::Error`]: https://docs.rs/oxidized-json-checker/0.3.2/oxidized_json_checker/enum.Error.html
-pub fn path(text: &str, offset: usize) -> Result<Vec<Index>, oxidized_json_checker::Error> {
-    oxidized_json_checker::validate_str(&text)?;
-
-    let mut pos = 0;
-    let mut path: Vec<Index> = Vec::new();
-    let mut in_key = false;
-
-    let mut current: Vec<Current> = vec![Current::None];
-    
-    let chars: Vec<char> = text.chars().collect();
-
-    while pos < offset && pos < chars.len() {
-        let start_pos = pos;
-        match chars[pos] {
-            '"' => {
-                let i = end_quote(&chars, pos+1);
-                let key = substring(&chars, pos+1, i);
-
-                match current.last() {
-                    Some(last) => {
-                        if *last == Current::Object && in_key {
-                            path.push(Index::Object(key));
-                            in_key = false;
-                            pos = i;
-                        }
-                    }
-                    None => {}
-                }
-                
-            }
-            '{' => {
-                current.push(Current::Object);
-                in_key = true;
-            }
-            '[' => {
-                path.push(Index::Array(0));
-                current.push(Current::Array);
-            }
-            '}' => {
-                path.pop();
-                current.pop();
-            }
-            ']' => {
-                path.pop();
-                current.pop();
-            }
-            ',' => {
-                match current.last() {
-                    Some(last) => {
-                        match last {
-                            Current::Object => {
-                                path.pop();
-                                in_key = true;
-                            },
-                            Current::Array => {
-                                let last = path.len()-1;
-                                path[last].increment();
-                            },
-                            Current::None => {}
-                        }
-                    }
-                    None => {}
-                }
-            }
-            _ => ()
-        }
-        if pos == start_pos {
-            pos += 1;
-        }
-    }
-
-    Ok(path)
+/// Returns an [`Error`] if the input json is invalid.
+pub fn path(text: &str, offset: usize) -> Result<Path, Error> {
+    path_with(text, offset, &Options::default())
 }
 
 /// Constructs the path of an index in a raw json string. 
@@ -173,26 +140,265 @@ pub fn path(text: &str, offset: usize) -> Result<Vec<Index>, oxidized_json_check
 /// 
 /// # Errors
 /// 
-/// Returns [`oxidized_json_checker::Error`] if the input json is invalid.
+/// Returns an [`Error`] if the input json is invalid.
+pub fn dot_path(text: &str, offset: usize) -> Result<String, Error> {
+    Ok(path(text, offset)?.to_string())
+}
+
+/// Constructs the path of an index in a raw json string.
+/// Returns path as a [JSON Pointer](https://www.rfc-editor.org/rfc/rfc6901).
+///
+/// # Examples
+/// 
+/// ```
+/// use jsonposition::pointer_path;
+///
+/// let json = r#"[null, 9, {"a/b": "c"}]"#;
+/// 
+/// let pointer = pointer_path(json, json.find("c").unwrap()).expect("Invalid JSON");
+/// assert_eq!(pointer, "/2/a~1b");
+/// ```
+/// 
+/// # Errors
+/// 
+/// Returns an [`Error`] if the input json is invalid.
+pub fn pointer_path(text: &str, offset: usize) -> Result<String, Error> {
+    Ok(path(text, offset)?.to_pointer())
+}
+
+/// Constructs the path of an index in a raw json string.
+/// Returns path in JSONPath bracket notation, which can express keys holding dots or spaces.
+///
+/// # Examples
+/// 
+/// ```
+/// use jsonposition::bracket_path;
+///
+/// let json = r#"[null, 9, {"a.b": "c"}]"#;
+/// 
+/// let bracket = bracket_path(json, json.find("c").unwrap()).expect("Invalid JSON");
+/// assert_eq!(bracket, r#"$[2]["a.b"]"#);
+/// ```
+/// 
+/// # Errors
+/// 
+/// Returns an [`Error`] if the input json is invalid.
+pub fn bracket_path(text: &str, offset: usize) -> Result<String, Error> {
+    Ok(path(text, offset)?.to_bracket())
+}
+
+/// Constructs the path of an index in a raw json string.
+/// Returns path in the mixed notation shown by most IDEs, with dots before identifier keys and brackets for everything else.
+///
+/// # Examples
+/// 
+/// ```
+/// use jsonposition::mixed_path;
+///
+/// let json = r#"[null, 9, {"fields": [1, {"a b": "c"}]}]"#;
+/// 
+/// let mixed = mixed_path(json, json.find("c").unwrap()).expect("Invalid JSON");
+/// assert_eq!(mixed, r#"$[2].fields[1]["a b"]"#);
+/// ```
+/// 
+/// # Errors
+/// 
+/// Returns an [`Error`] if the input json is invalid.
+pub fn mixed_path(text: &str, offset: usize) -> Result<String, Error> {
+    Ok(path(text, offset)?.to_mixed())
+}
+
+/// Constructs the path of an index in a raw json string.
+/// Returns path as a [jq](https://jqlang.github.io/jq/) filter that can be pasted into a jq invocation.
+///
+/// # Examples
+/// 
+/// ```
+/// use jsonposition::jq_path;
+///
+/// let json = r#"{"fields": [null, {"a b": "c"}]}"#;
+/// 
+/// let filter = jq_path(json, json.find("c").unwrap()).expect("Invalid JSON");
+/// assert_eq!(filter, r#".fields[1]["a b"]"#);
+/// ```
+/// 
+/// # Errors
+/// 
+/// Returns an [`Error`] if the input json is invalid.
+pub fn jq_path(text: &str, offset: usize) -> Result<String, Error> {
+    Ok(path(text, offset)?.to_jq())
+}
+
+/// Constructs the path of an index in a raw json string.
+/// Returns path as JavaScript property accessors on the variable `object`, ready to paste into devtools.
+///
+/// # Examples
 /// 
-/// [`oxidized_json_checker::Error`]: https://docs.rs/oxidized-json-checker/0.3.2/oxidized_json_checker/enum.Error.html
-pub fn dot_path(text: &str, offset: usize) -> Result<String, oxidized_json_checker::Error> {
-    let p = path(text, offset)?;
-    Ok(dots(&p))
+/// ```
+/// use jsonposition::js_path;
+///
+/// let json = r#"{"fields": [null, {"a b": "c"}]}"#;
+/// 
+/// let accessor = js_path(json, json.find("c").unwrap(), "obj").expect("Invalid JSON");
+/// assert_eq!(accessor, r#"obj.fields[1]["a b"]"#);
+/// ```
+/// 
+/// # Errors
+/// 
+/// Returns an [`Error`] if the input json is invalid.
+pub fn js_path(text: &str, offset: usize, object: &str) -> Result<String, Error> {
+    Ok(path(text, offset)?.to_js(object))
+}
+
+/// Constructs the path of an index in a raw json string.
+/// Returns path as Python subscripts on the variable `object`, ready to paste into Python or pandas code.
+///
+/// # Examples
+/// 
+/// ```
+/// use jsonposition::python_path;
+///
+/// let json = r#"{"fields": [null, {"name": "c"}]}"#;
+/// 
+/// let subscript = python_path(json, json.find("c").unwrap(), "data").expect("Invalid JSON");
+/// assert_eq!(subscript, r#"data["fields"][1]["name"]"#);
+/// ```
+/// 
+/// # Errors
+/// 
+/// Returns an [`Error`] if the input json is invalid.
+pub fn python_path(text: &str, offset: usize, object: &str) -> Result<String, Error> {
+    Ok(path(text, offset)?.to_python(object))
+}
+
+/// Constructs the path to a byte offset in raw json bytes,
+/// without converting the input to a string first.
+///
+/// # Examples
+///
+/// ```
+/// use jsonposition::{path_bytes, Index};
+///
+/// let json = r#"["é", {"a": "b"}]"#.as_bytes();
+///
+/// let vec_path = path_bytes(json, 14).expect("Invalid JSON");
+/// assert_eq!(vec_path, [Index::Array(1), Index::Object(String::from("a"))]);
+/// ```
+///
+/// # Errors
+///
+/// Returns an [`Error`] if the input json is invalid.
+pub fn path_bytes(bytes: &[u8], offset: usize) -> Result<Path, Error> {
+    scan::path_to(bytes, offset, &Options::default())
 }
 
-fn dots(p: &Vec<Index>) -> String {
-    let mut dotted = "$".to_owned();
+/// Constructs the paths to many indices in a raw json string, scanning it only once.
+/// The paths are returned in the same order as `offsets`, which don't need to be sorted.
+/// Indices are counted in chars, the same as [`path`].
+///
+/// # Examples
+///
+/// ```
+/// use jsonposition::paths;
+///
+/// let json = r#"[null, 9, {"a": "b"}]"#;
+///
+/// let found = paths(json, &[json.find("b").unwrap(), json.find("9").unwrap()]).expect("Invalid JSON");
+/// assert_eq!(found[0].to_string(), "$.2.a");
+/// assert_eq!(found[1].to_string(), "$.1");
+/// ```
+///
+/// # Errors
+///
+/// Returns an [`Error`] if the input json is invalid.
+pub fn paths(text: &str, offsets: &[usize]) -> Result<Vec<Path>, Error> {
+    // Convert every offset to bytes in one walk over the text, in sorted order
+    let mut order: Vec<usize> = (0..offsets.len()).collect();
+    order.sort_by_key(|i| offsets[*i]);
 
-    for i in p {
-        dotted += ".";
-        dotted += &match i {
-            Index::Array(i) => i.to_string(),
-            Index::Object(key) => key.to_owned()
+    let mut byte_offsets = vec![text.len(); offsets.len()];
+    let mut chars = text.char_indices().map(|(byte, _)| byte).enumerate().peekable();
+    for i in order {
+        while chars.next_if(|(n, _)| *n < offsets[i]).is_some() {}
+        match chars.peek() {
+            Some((_, byte)) => byte_offsets[i] = *byte,
+            None => break
         }
     }
-    
-    dotted
+    scan::paths_to(text.as_bytes(), &byte_offsets, &Options::default())
+}
+
+/// Constructs the path to an index in a raw json string, scanned with the given [`Options`].
+///
+/// # Examples
+/// 
+/// ```
+/// use jsonposition::{path_with, Options, Index};
+/// 
+/// let json = r#"{"a": [1, 2, {"b": 3}"#;
+/// 
+/// let vec_path = path_with(json, json.find("3").unwrap(), &Options::new().lenient(true)).unwrap();
+/// assert_eq!(vec_path, vec![Index::Object(String::from("a")), Index::Array(2), Index::Object(String::from("b"))]);
+/// ```
+/// 
+/// # Errors
+/// 
+/// Returns an [`Error`] if the input json is invalid for the given options.
+pub fn path_with(text: &str, offset: usize, options: &Options) -> Result<Path, Error> {
+    // Offsets are in chars, walk to the matching byte without collecting the text
+    let byte_offset = OffsetUnit::Chars.to_byte_offset(text, offset);
+    scan::path_to(text.as_bytes(), byte_offset, options)
+}
+
+/// Constructs the path of an index in a raw json string, scanned with the given [`Options`].
+/// Returns path in a human readable format usable by most JsonPath crates.
+/// 
+/// # Errors
+/// 
+/// Returns an [`Error`] if the input json is invalid for the given options.
+pub fn dot_path_with(text: &str, offset: usize, options: &Options) -> Result<String, Error> {
+    Ok(path_with(text, offset, options)?.to_string())
+}
+
+/// Constructs the path to an index in a raw json string without validating it.
+/// Scanning stops at the index, so this is faster than [`path`] for json known to be valid,
+/// such as the output of a serializer.
+///
+/// Malformed json does not panic, but the path is only built up to the first syntax error,
+/// and anything after the index is never looked at.
+///
+/// # Examples
+/// 
+/// ```
+/// use jsonposition::{path_unchecked, Index};
+/// 
+/// let json = r#"[null, 9, {"a": "b"}]"#;
+/// 
+/// let vec_path = path_unchecked(json, json.find("b").unwrap());
+/// assert_eq!(vec_path, vec![Index::Array(2), Index::Object(String::from("a"))]);
+/// ```
+pub fn path_unchecked(text: &str, offset: usize) -> Path {
+    let byte_offset = OffsetUnit::Chars.to_byte_offset(text, offset);
+    scan::path_to_unchecked(text.as_bytes(), byte_offset)
+}
+
+/// Constructs the path of an index in a raw json string without validating it.
+/// Returns path in a human readable format usable by most JsonPath crates.
+///
+/// See [`path_unchecked`] for the behavior on malformed json.
+///
+/// # Examples
+/// 
+/// ```
+/// use jsonposition::dot_path_unchecked;
+///
+/// let json = r#"[null, 9, {"a": "b"}]"#;
+/// 
+/// let path = dot_path_unchecked(json, json.find("b").unwrap());
+/// assert_eq!(path, "$.2.a");
+/// ```
+pub fn dot_path_unchecked(text: &str, offset: usize) -> String {
+    path_unchecked(text, offset).to_string()
 }
 
 #[cfg(test)]
@@ -211,7 +417,107 @@ mod tests {
         let dotted = dot_path(json, json.find("87").unwrap()).expect("Invalid JSON");
         assert_eq!(dotted, "$.1.field2.2");
 
+        // Tests pointer path
+        let pointer = pointer_path(json, json.find("87").unwrap()).expect("Invalid JSON");
+        assert_eq!(pointer, "/1/field2/2");
+
+        // Tests bracket path
+        let bracket = bracket_path(json, json.find("87").unwrap()).expect("Invalid JSON");
+        assert_eq!(bracket, r#"$[1]["field2"][2]"#);
+
+        // Tests mixed path
+        let mixed = mixed_path(json, json.find("87").unwrap()).expect("Invalid JSON");
+        assert_eq!(mixed, "$[1].field2[2]");
+
+        // Tests jq path
+        let filter = jq_path(json, json.find("87").unwrap()).expect("Invalid JSON");
+        assert_eq!(filter, ".[1].field2[2]");
+
+        // Tests JavaScript path
+        let accessor = js_path(json, json.find("87").unwrap(), "obj").expect("Invalid JSON");
+        assert_eq!(accessor, "obj[1].field2[2]");
+
+        // Tests Python path
+        let subscript = python_path(json, json.find("87").unwrap(), "data").expect("Invalid JSON");
+        assert_eq!(subscript, r#"data[1]["field2"][2]"#);
+
         // Tests out of bounds 
         assert_eq!(path(json, 1000).unwrap(), vec![]);
+
+        // Tests structure inside strings and multi-byte chars
+        let json = r#"{"a": "}]", "é": ["[", 5]}"#;
+        let position = json[..json.find('5').unwrap()].chars().count();
+        assert_eq!(dot_path(json, position).unwrap(), "$.é.1");
+
+        // Tests escaped keys are decoded, surrogate pairs included
+        let json = r#"{"a\u002eb": {"\ud83d\ude00\n": [1]}, "\"": 2}"#;
+        assert_eq!(path(json, json.find('1').unwrap()).unwrap(), [Index::from("a.b"), Index::from("😀\n"), Index::Array(0)]);
+        assert_eq!(dot_path(json, json.rfind('2').unwrap()).unwrap(), r#"$["\""]"#);
+    }
+
+    #[test]
+    fn unchecked() {
+        let json = r#"[9, {"field1": "b", "field2": [null, null, 87, 4], "field3": "file.txt"}]"#;
+        let position = json.find("87").unwrap();
+
+        assert_eq!(path_unchecked(json, position), path(json, position).unwrap());
+
+        // Tests malformed json after and before the index
+        assert_eq!(dot_path_unchecked(r#"[1, {"a": 2}, }"#, 11), "$.1.a");
+        assert_eq!(dot_path_unchecked(r#"[1, } {"a": 2}]"#, 11), "$.1");
+    }
+
+    #[test]
+    fn lenient() {
+        let lenient = Options::new().lenient(true);
+        let at = |json: &str, s: &str| dot_path_with(json, json.find(s).unwrap(), &lenient).unwrap();
+
+        // Tests missing commas and colons
+        assert_eq!(at("[1 2, 3]", "3"), "$.2");
+        assert_eq!(at(r#"{"a" 1, "b" 2}"#, "2"), "$.b");
+        assert_eq!(at(r#"{"a": 1 "b": 2}"#, "2"), "$.b");
+
+        // Tests unbalanced braces
+        assert_eq!(at(r#"{"a": {"b": 1, "c": [2, 3}, "d": 4}"#, "4"), "$.d");
+        assert_eq!(at(r#"[1, 2]], 3"#, "3"), "$");
+        assert_eq!(at(r#"{"a": [1, 2"#, "2"), "$.a.1");
+        assert_eq!(dot_path_with(r#"{"a": [1, 2"#, 11, &lenient).unwrap(), "$.a.1");
+
+        // Tests trailing commas and unknown tokens
+        assert_eq!(at("[1, 2,, foo, 3]", "3"), "$.4");
+        assert_eq!(at(r#"{a: [tru, "x]"#, "x"), "$.a.1");
+
+        // Tests strict mode still fails
+        assert!(path_with("[1 2]", 3, &Options::new()).is_err());
+    }
+
+    #[test]
+    fn batch() {
+        let json = r#"[9, {"é": "b", "field2": [null, null, 87, 4]}]"#;
+        let offsets = [1000, 20, 0, 20, 3, json.chars().count(), 7];
+
+        let batch = paths(json, &offsets).unwrap();
+        let single: Vec<Path> = offsets.iter().map(|i| path(json, *i).unwrap()).collect();
+        assert_eq!(batch, single);
+
+        assert!(paths(json, &[]).unwrap().is_empty());
+        assert!(paths("[1,]", &[1]).is_err());
+    }
+
+    #[test]
+    fn bytes() {
+        let json = r#"[{}, {"é": "}", "b": [[], 1]}]"#;
+
+        let at = |s: &str| path_bytes(json.as_bytes(), json.find(s).unwrap()).unwrap().to_string();
+
+        assert_eq!(at("{}"), "$.0");
+        assert_eq!(at(", {"), "$.0");
+        assert_eq!(at(" {"), "$.1");
+        assert_eq!(at(r#""}""#), "$.1.é");
+        assert_eq!(at("1]"), "$.1.b.1");
+        assert_eq!(at("b"), "$.1.b");
+
+        // Tests invalid utf-8 in keys
+        assert_eq!(path_bytes(b"{\"\xff\": 1}", 7).unwrap().to_string(), "$.\u{fffd}");
     }
 }
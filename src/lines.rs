@@ -0,0 +1,325 @@
+use alloc::string::{String, ToString};
+use alloc::vec;
+use alloc::vec::Vec;
+
+use crate::error::{Error, ErrorKind};
+use crate::path::Path;
+
+/// Converts a zero based line and column, counted in chars, to a char offset.
+/// Columns past the end of a line are clamped to the end of that line,
+/// returns `None` for lines past the end of the text.
+pub(crate) fn char_offset(text: &str, line: usize, column: usize) -> Option<usize> {
+    let mut offset = 0;
+    let mut chars = text.chars().peekable();
+
+    for _ in 0..line {
+        loop {
+            match chars.next() {
+                Some('\n') => {
+                    offset += 1;
+                    break;
+                }
+                Some(_) => offset += 1,
+                None => return None
+            }
+        }
+    }
+
+    for _ in 0..column {
+        match chars.peek() {
+            Some('\n') | Some('\r') | None => break,
+            Some(_) => {
+                chars.next();
+                offset += 1;
+            }
+        }
+    }
+
+    Some(offset)
+}
+
+/// Unit an offset into a text is measured in
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum OffsetUnit {
+    /// UTF-8 bytes, as used by `str` indexing
+    Bytes,
+    /// Unicode scalar values, as used by [`path`](crate::path)
+    Chars,
+    /// UTF-16 code units, as used by LSP and JavaScript
+    Utf16
+}
+
+impl OffsetUnit {
+    /// Converts an offset in this unit to a char offset.
+    /// Offsets in the middle of a char map to that char, offsets past the end to the end of the text.
+    pub fn to_char_offset(self, text: &str, offset: usize) -> usize {
+        match self {
+            OffsetUnit::Chars => offset,
+            OffsetUnit::Bytes => text.char_indices().take_while(|(i, c)| i + c.len_utf8() <= offset).count(),
+            OffsetUnit::Utf16 => {
+                let mut units = 0;
+                text.chars().take_while(|c| {
+                    units += c.len_utf16();
+                    units <= offset
+                }).count()
+            }
+        }
+    }
+
+    /// Converts an offset in this unit to a byte offset.
+    /// Offsets in the middle of a char map to the start of that char, offsets past the end to the end of the text.
+    pub fn to_byte_offset(self, text: &str, offset: usize) -> usize {
+        let chars = self.to_char_offset(text, offset);
+        text.char_indices().nth(chars).map_or(text.len(), |(i, _)| i)
+    }
+}
+
+/// Constructs the path to an offset in a raw json string, measured in the given unit.
+///
+/// # Examples
+///
+/// ```
+/// use jsonposition::{path_with_unit, OffsetUnit, Index};
+///
+/// let json = r#"["😀", {"a": "b"}]"#;
+///
+/// // The emoji is two UTF-16 code units, so "b" starts at 14
+/// let vec_path = path_with_unit(json, 14, OffsetUnit::Utf16).expect("Invalid JSON");
+/// assert_eq!(vec_path, [Index::Array(1), Index::Object(String::from("a"))]);
+/// ```
+///
+/// # Errors
+///
+/// Returns an [`Error`](crate::Error) if the input json is invalid.
+pub fn path_with_unit(text: &str, offset: usize, unit: OffsetUnit) -> Result<Path, Error> {
+    crate::path(text, unit.to_char_offset(text, offset))
+}
+
+/// Constructs the path to an offset in a raw json string, measured in the given unit.
+/// Returns path in a human readable format usable by most JsonPath crates.
+///
+/// # Errors
+///
+/// Returns an [`Error`](crate::Error) if the input json is invalid.
+pub fn dot_path_with_unit(text: &str, offset: usize, unit: OffsetUnit) -> Result<String, Error> {
+    Ok(path_with_unit(text, offset, unit)?.to_string())
+}
+
+/// Zero based line and column in a text, with the column counted in chars
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, PartialOrd, Ord)]
+pub struct Position {
+    pub line: usize,
+    pub column: usize
+}
+
+impl Position {
+    pub fn new(line: usize, column: usize) -> Self {
+        Position { line, column }
+    }
+}
+
+/// Index of line starts in a text, for converting between byte offsets and [`Position`]s
+/// without rescanning the text on every query.
+///
+/// Lines are found in `O(log n)`, columns are then counted within the line.
+///
+/// # Examples
+///
+/// ```
+/// use jsonposition::{LineIndex, Position};
+///
+/// let json = "{\n  \"é\": [1, 2]\n}";
+/// let index = LineIndex::new(json);
+///
+/// let offset = json.find("2").unwrap();
+/// assert_eq!(index.offset_to_position(offset), Some(Position::new(1, 11)));
+/// assert_eq!(index.position_to_offset(Position::new(1, 11)), Some(offset));
+/// ```
+#[derive(Debug, Clone)]
+pub struct LineIndex<'a> {
+    text: &'a str,
+    /// Byte offset of the start of each line
+    starts: Vec<usize>
+}
+
+impl<'a> LineIndex<'a> {
+    /// Builds the index in a single pass over the text.
+    pub fn new(text: &'a str) -> Self {
+        let mut starts = vec![0];
+        starts.extend(text.bytes().enumerate().filter(|(_, b)| *b == b'\n').map(|(i, _)| i + 1));
+        LineIndex { text, starts }
+    }
+
+    /// Number of lines in the text, a trailing newline starts an empty last line.
+    pub fn line_count(&self) -> usize {
+        self.starts.len()
+    }
+
+    /// Byte range of a line, excluding its line ending.
+    pub fn line_span(&self, line: usize) -> Option<core::ops::Range<usize>> {
+        let start = *self.starts.get(line)?;
+        let end = self.starts.get(line + 1).map_or(self.text.len(), |next| next - 1);
+        let end = if self.text.as_bytes()[start..end].ends_with(b"\r") { end - 1 } else { end };
+        Some(start..end)
+    }
+
+    /// Converts a byte offset to a line and column.
+    /// Returns `None` if the offset is past the end of the text.
+    pub fn offset_to_position(&self, offset: usize) -> Option<Position> {
+        if offset > self.text.len() {
+            return None;
+        }
+
+        let line = match self.starts.binary_search(&offset) {
+            Ok(line) => line,
+            Err(next) => next - 1
+        };
+        let start = self.starts[line];
+        let column = self.text.as_bytes()[start..offset].iter().filter(|b| !is_continuation(**b)).count();
+
+        Some(Position { line, column })
+    }
+
+    /// Converts a line and column to a byte offset.
+    /// Columns past the end of the line are clamped to the end of the line.
+    /// Returns `None` if the line is past the end of the text.
+    pub fn position_to_offset(&self, position: Position) -> Option<usize> {
+        let span = self.line_span(position.line)?;
+        let line = &self.text[span.clone()];
+        Some(match line.char_indices().nth(position.column) {
+            Some((i, _)) => span.start + i,
+            None => span.end
+        })
+    }
+}
+
+fn is_continuation(b: u8) -> bool {
+    b & 0b1100_0000 == 0b1000_0000
+}
+
+/// Spaces and tabs at the start of a line
+pub(crate) fn indentation(line: &str) -> &str {
+    &line[..line.len() - line.trim_start_matches([' ', '\t']).len()]
+}
+
+/// Indentation of the line containing a byte offset
+pub(crate) fn indent_at(text: &str, offset: usize) -> &str {
+    indentation(&text[text[..offset].rfind('\n').map_or(0, |i| i + 1)..])
+}
+
+/// Constructs the path to a line and column in a raw json string.
+/// Lines and columns are zero based, and columns are counted in chars so multi-byte characters count once.
+///
+/// # Examples
+///
+/// ```
+/// use jsonposition::{path_at, Index};
+///
+/// let json = "[\n  \"é\",\n  {\"a\": \"b\"}\n]";
+///
+/// let vec_path = path_at(json, 2, 8).expect("Invalid JSON");
+/// assert_eq!(vec_path, [Index::Array(1), Index::Object(String::from("a"))]);
+/// ```
+///
+/// # Errors
+///
+/// Returns an [`Error`](crate::Error) if the input json is invalid,
+/// or if the line is past the end of the text. Columns past the end of a line are clamped to the end of that line.
+pub fn path_at(text: &str, line: usize, column: usize) -> Result<Path, Error> {
+    match char_offset(text, line, column) {
+        Some(offset) => crate::path(text, offset),
+        None => Err(Error::new(ErrorKind::OffsetOutOfRange, text.as_bytes(), text.len()))
+    }
+}
+
+/// Constructs the path to a line and column in a raw json string.
+/// Returns path in a human readable format usable by most JsonPath crates.
+/// Lines and columns are zero based, and columns are counted in chars so multi-byte characters count once.
+///
+/// # Examples
+///
+/// ```
+/// use jsonposition::dot_path_at;
+///
+/// let json = "[\n  \"é\",\n  {\"a\": \"b\"}\n]";
+///
+/// let path = dot_path_at(json, 1, 3).expect("Invalid JSON");
+/// assert_eq!(path, "$.0");
+/// ```
+///
+/// # Errors
+///
+/// Returns an [`Error`](crate::Error) if the input json is invalid,
+/// or if the line is past the end of the text.
+pub fn dot_path_at(text: &str, line: usize, column: usize) -> Result<String, Error> {
+    Ok(path_at(text, line, column)?.to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::path;
+
+    #[test]
+    fn offsets() {
+        let text = "ab\r\nçd€\nef";
+
+        assert_eq!(char_offset(text, 0, 0), Some(0));
+        assert_eq!(char_offset(text, 0, 5), Some(2));
+        assert_eq!(char_offset(text, 1, 2), Some(6));
+        assert_eq!(char_offset(text, 2, 1), Some(9));
+
+        // Tests clamping past the end
+        assert_eq!(char_offset(text, 2, 10), Some(10));
+        assert_eq!(char_offset(text, 10, 0), None);
+
+        // Tests lines past the end
+        assert_eq!(path_at("[1]", 1, 0).unwrap_err().kind(), ErrorKind::OffsetOutOfRange);
+    }
+
+    #[test]
+    fn units() {
+        let text = "a😀中b";
+
+        assert_eq!(OffsetUnit::Bytes.to_char_offset(text, 5), 2);
+        assert_eq!(OffsetUnit::Bytes.to_char_offset(text, 3), 1);
+        assert_eq!(OffsetUnit::Utf16.to_char_offset(text, 3), 2);
+        assert_eq!(OffsetUnit::Utf16.to_char_offset(text, 2), 1);
+        assert_eq!(OffsetUnit::Utf16.to_char_offset(text, 4), 3);
+        assert_eq!(OffsetUnit::Chars.to_byte_offset(text, 3), 8);
+        assert_eq!(OffsetUnit::Utf16.to_byte_offset(text, 100), text.len());
+
+        let json = r#"["中😀", {"key": 1}]"#;
+        let position = json.find('1').unwrap();
+        let expected = path(json, json[..position].chars().count()).unwrap();
+
+        assert_eq!(path_with_unit(json, position, OffsetUnit::Bytes).unwrap(), expected);
+        assert_eq!(path_with_unit(json, json[..position].encode_utf16().count(), OffsetUnit::Utf16).unwrap(), expected);
+    }
+
+    #[test]
+    fn line_index() {
+        let text = "ab\r\nçd€\nef\n";
+        let index = LineIndex::new(text);
+
+        assert_eq!(index.line_count(), 4);
+        assert_eq!(index.line_span(0), Some(0..2));
+        assert_eq!(index.line_span(1), Some(4..10));
+        assert_eq!(index.line_span(3), Some(14..14));
+        assert_eq!(index.line_span(4), None);
+
+        for (offset, _) in text.char_indices().filter(|(_, c)| *c != '\r' && *c != '\n') {
+            let position = index.offset_to_position(offset).unwrap();
+            assert_eq!(char_offset(text, position.line, position.column), Some(text[..offset].chars().count()));
+        }
+
+        assert_eq!(index.offset_to_position(text.find('€').unwrap()), Some(Position::new(1, 2)));
+        assert_eq!(index.position_to_offset(Position::new(1, 2)), text.find('€'));
+        assert_eq!(index.offset_to_position(text.len()), Some(Position::new(3, 0)));
+        assert_eq!(index.offset_to_position(text.len() + 1), None);
+
+        // Tests clamping past the end of a line
+        assert_eq!(index.position_to_offset(Position::new(0, 10)), Some(2));
+        assert_eq!(index.position_to_offset(Position::new(4, 0)), None);
+    }
+}
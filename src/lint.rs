@@ -0,0 +1,250 @@
+use core::ops::Range;
+use alloc::format;
+use alloc::string::String;
+use alloc::vec::Vec;
+
+use crate::error::Error;
+use crate::locate::Kind;
+use crate::path::Path;
+use crate::scan::{key_text, Event, Walker};
+use crate::search::duplicate_keys;
+
+/// Check made by [`lint`]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum Rule {
+    /// Key repeated in the same object, see [`duplicate_keys`](crate::duplicate_keys)
+    DuplicateKey,
+    /// Container nested deeper than [`LintOptions::max_depth`]
+    DeepNesting,
+    /// Array with more elements than [`LintOptions::max_array_len`]
+    LargeArray,
+    /// String longer than [`LintOptions::max_string_len`]
+    LongString,
+    /// String holding `NaN` or an infinity, which json numbers can't represent
+    NanLikeString
+}
+
+impl core::fmt::Display for Rule {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        let name = match self {
+            Rule::DuplicateKey => "duplicate-key",
+            Rule::DeepNesting => "deep-nesting",
+            Rule::LargeArray => "large-array",
+            Rule::LongString => "long-string",
+            Rule::NanLikeString => "nan-like-string"
+        };
+        write!(f, "{}", name)
+    }
+}
+
+/// Structural issue found by [`lint`]
+///
+/// Formatting a diagnostic with [`Display`](core::fmt::Display) gives the rule, dot path and message on one line.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Diagnostic {
+    pub rule: Rule,
+    /// Span of the value, or of the key for [`Rule::DuplicateKey`]
+    pub span: Range<usize>,
+    /// Path to the value, or to the value of the repeated member for [`Rule::DuplicateKey`]
+    pub path: Path,
+    pub message: String
+}
+
+impl core::fmt::Display for Diagnostic {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        write!(f, "{} at {}: {}", self.rule, self.path, self.message)
+    }
+}
+
+/// Thresholds for the checks made by [`lint_with`]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct LintOptions {
+    pub(crate) max_depth: usize,
+    pub(crate) max_array_len: usize,
+    pub(crate) max_string_len: usize
+}
+
+impl Default for LintOptions {
+    fn default() -> Self {
+        LintOptions { max_depth: 64, max_array_len: 10_000, max_string_len: 10_000 }
+    }
+}
+
+impl LintOptions {
+    /// Creates the default thresholds, a depth of 64 and 10,000 array elements or string bytes.
+    pub fn new() -> Self {
+        LintOptions::default()
+    }
+
+    /// Reports containers nested more than `depth` levels deep, the root container being one level.
+    /// Only the outermost container past the limit is reported.
+    pub fn max_depth(mut self, depth: usize) -> Self {
+        self.max_depth = depth;
+        self
+    }
+
+    /// Reports arrays with more than `len` elements.
+    pub fn max_array_len(mut self, len: usize) -> Self {
+        self.max_array_len = len;
+        self
+    }
+
+    /// Reports strings longer than `len` bytes, not counting their quotes.
+    pub fn max_string_len(mut self, len: usize) -> Self {
+        self.max_string_len = len;
+        self
+    }
+}
+
+/// Container still being scanned
+struct Frame {
+    /// Position in the diagnostics of a [`Rule::DeepNesting`] for this container, completed once its span is known
+    nesting: Option<usize>,
+    /// Number of elements so far if this is an array
+    elements: Option<usize>
+}
+
+/// Checks a raw json string for structural issues with the default [`LintOptions`].
+///
+/// # Examples
+///
+/// ```
+/// use jsonposition::{lint, Rule};
+///
+/// let json = r#"{"port": 80, "ratio": "NaN", "port": 8080}"#;
+///
+/// let diagnostics = lint(json).expect("Invalid JSON");
+/// assert_eq!(diagnostics.iter().map(|diagnostic| diagnostic.rule).collect::<Vec<_>>(), [Rule::NanLikeString, Rule::DuplicateKey]);
+/// assert_eq!(diagnostics[1].to_string(), "duplicate-key at $.port: key \"port\" is repeated, first at 1");
+/// ```
+///
+/// # Errors
+///
+/// Returns an [`Error`](crate::Error) if the input json is invalid.
+pub fn lint(text: &str) -> Result<Vec<Diagnostic>, Error> {
+    lint_with(text, &LintOptions::default())
+}
+
+/// Checks a raw json string for structural issues with the given thresholds.
+/// Diagnostics are sorted by where their span starts.
+///
+/// # Errors
+///
+/// Returns an [`Error`](crate::Error) if the input json is invalid.
+pub fn lint_with(text: &str, options: &LintOptions) -> Result<Vec<Diagnostic>, Error> {
+    let bytes = text.as_bytes();
+    let mut walker = Walker::new(text);
+    let mut diagnostics = Vec::new();
+    let mut frames: Vec<Frame> = Vec::new();
+
+    while let Some(event) = walker.next() {
+        let event = event?;
+        if matches!(event, Event::StartObject(_) | Event::StartArray(_) | Event::Scalar(_)) {
+            if let Some(Frame { elements: Some(elements), .. }) = frames.last_mut() {
+                *elements += 1;
+            }
+        }
+
+        match event {
+            Event::StartObject(start) | Event::StartArray(start) => {
+                let nesting = (frames.len() == options.max_depth).then(|| {
+                    diagnostics.push(Diagnostic {
+                        rule: Rule::DeepNesting,
+                        span: start..start,
+                        path: walker.path().clone(),
+                        message: format!("nested more than {} levels deep", options.max_depth)
+                    });
+                    diagnostics.len() - 1
+                });
+                let elements = matches!(event, Event::StartArray(_)).then_some(0);
+                frames.push(Frame { nesting, elements });
+            }
+            Event::EndObject(span) | Event::EndArray(span) => {
+                let Some(frame) = frames.pop() else { continue };
+                if let Some(i) = frame.nesting {
+                    diagnostics[i].span = span.clone();
+                }
+                match frame.elements {
+                    Some(elements) if elements > options.max_array_len => diagnostics.push(Diagnostic {
+                        rule: Rule::LargeArray,
+                        span,
+                        path: walker.path().clone(),
+                        message: format!("array has {} elements, more than {}", elements, options.max_array_len)
+                    }),
+                    _ => {}
+                }
+            }
+            Event::Scalar(span) if Kind::of_scalar(&bytes[span.clone()]) == Kind::String => {
+                let content = key_text(bytes, &span, false);
+                if content.len() > options.max_string_len {
+                    diagnostics.push(Diagnostic {
+                        rule: Rule::LongString,
+                        span: span.clone(),
+                        path: walker.path().clone(),
+                        message: format!("string is {} bytes long, more than {}", content.len(), options.max_string_len)
+                    });
+                }
+                if is_nan_like(&content) {
+                    diagnostics.push(Diagnostic {
+                        rule: Rule::NanLikeString,
+                        span,
+                        path: walker.path().clone(),
+                        message: format!("string \"{}\" looks like a number json can't represent", content)
+                    });
+                }
+            }
+            _ => {}
+        }
+    }
+
+    diagnostics.extend(duplicate_keys(text)?.into_iter().map(|duplicate| {
+        let mut path = duplicate.path;
+        path.push(duplicate.key.as_str());
+        Diagnostic {
+            rule: Rule::DuplicateKey,
+            span: duplicate.duplicate,
+            path,
+            message: format!("key \"{}\" is repeated, first at {}", duplicate.key, duplicate.first.start)
+        }
+    }));
+    diagnostics.sort_by_key(|diagnostic| diagnostic.span.start);
+
+    Ok(diagnostics)
+}
+
+/// Whether a string holds `NaN` or an infinity, as written by serializers that emit them as strings
+fn is_nan_like(content: &str) -> bool {
+    let unsigned = content.strip_prefix(['-', '+']).unwrap_or(content);
+    ["nan", "inf", "infinity"].iter().any(|name| unsigned.eq_ignore_ascii_case(name))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn rules() {
+        let json = r#"{"a": [[[1]], [2, 3, 4]], "b": "-Infinity", "c": "abcdef", "a": "nan"}"#;
+        let options = LintOptions::new().max_depth(2).max_array_len(2).max_string_len(5);
+
+        let found: Vec<(Rule, String, &str)> = lint_with(json, &options)
+            .unwrap()
+            .into_iter()
+            .map(|diagnostic| (diagnostic.rule, diagnostic.path.to_string(), &json[diagnostic.span]))
+            .collect();
+        assert_eq!(found, [
+            (Rule::DeepNesting, "$.a.0".to_string(), "[[1]]"),
+            (Rule::DeepNesting, "$.a.1".to_string(), "[2, 3, 4]"),
+            (Rule::LargeArray, "$.a.1".to_string(), "[2, 3, 4]"),
+            (Rule::LongString, "$.b".to_string(), r#""-Infinity""#),
+            (Rule::NanLikeString, "$.b".to_string(), r#""-Infinity""#),
+            (Rule::LongString, "$.c".to_string(), r#""abcdef""#),
+            (Rule::DuplicateKey, "$.a".to_string(), r#""a""#),
+            (Rule::NanLikeString, "$.a".to_string(), r#""nan""#)
+        ]);
+
+        assert!(lint(json).unwrap().iter().all(|diagnostic| matches!(diagnostic.rule, Rule::DuplicateKey | Rule::NanLikeString)));
+        assert_eq!(lint(r#"["", "nano", "+inf", "info"]"#).unwrap().len(), 1);
+        assert!(lint("[1,]").is_err());
+    }
+}
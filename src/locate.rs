@@ -0,0 +1,254 @@
+use core::ops::Range;
+use alloc::vec::Vec;
+
+use crate::error::Error;
+use crate::path::{Index, Path};
+use crate::scan::{Event, Walker};
+
+/// Kind of json value, or an object key
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Kind {
+    Null,
+    Bool,
+    Number,
+    String,
+    ObjectKey,
+    Object,
+    Array
+}
+
+impl Kind {
+    /// Kind of a scalar from its raw text
+    pub(crate) fn of_scalar(raw: &[u8]) -> Kind {
+        match raw.first() {
+            Some(b'"') | Some(b'\'') => Kind::String,
+            Some(b't') | Some(b'f') => Kind::Bool,
+            Some(b'n') => Kind::Null,
+            _ => Kind::Number
+        }
+    }
+}
+
+impl core::fmt::Display for Kind {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        write!(f, "{}", match self {
+            Kind::Null => "null",
+            Kind::Bool => "boolean",
+            Kind::Number => "number",
+            Kind::String => "string",
+            Kind::ObjectKey => "object key",
+            Kind::Object => "object",
+            Kind::Array => "array"
+        })
+    }
+}
+
+/// Everything known about the innermost value or key containing an offset.
+/// Spans are byte ranges into the original text.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Location {
+    /// Path to the value, or to the value of the member for a key
+    pub path: Path,
+    /// Span of the value or key
+    pub span: Range<usize>,
+    /// Kind of the value, [`Kind::ObjectKey`] for a key
+    pub kind: Kind,
+    /// Span of the object or array containing the value or key, `None` for the root value
+    pub parent_span: Option<Range<usize>>,
+    /// Span of the key of the member, `None` unless the parent is an object
+    pub key_span: Option<Range<usize>>
+}
+
+impl Location {
+    /// Returns `true` if the offset is within an object key rather than a value.
+    /// The path then points at the value of that member.
+    pub fn in_key(&self) -> bool {
+        self.kind == Kind::ObjectKey
+    }
+
+    /// Key of the member with its escape sequences decoded, the same as the last segment of the path.
+    /// `None` unless the parent is an object.
+    pub fn key(&self) -> Option<&str> {
+        self.key_span.as_ref()?;
+        match self.path.last() {
+            Some(Index::Object(key)) => Some(key),
+            _ => None
+        }
+    }
+
+    /// Key of the member as it is written in `text`, the text [`Location::key_span`] was found in,
+    /// with its escape sequences but without its quotes. `None` unless the parent is an object.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use jsonposition::locate;
+    ///
+    /// let json = r#"{"caf\u00e9": 1}"#;
+    ///
+    /// let location = locate(json, json.find('1').unwrap()).expect("Invalid JSON").unwrap();
+    /// assert_eq!(location.key(), Some("café"));
+    /// assert_eq!(location.raw_key(json), Some(r"caf\u00e9"));
+    /// assert_eq!(&json[location.key_span.unwrap()], r#""caf\u00e9""#);
+    /// ```
+    pub fn raw_key<'a>(&self, text: &'a str) -> Option<&'a str> {
+        let raw = text.get(self.key_span.clone()?)?;
+        match raw.as_bytes().first() {
+            Some(quote @ (b'"' | b'\'')) => raw.strip_prefix(char::from(*quote))?.strip_suffix(char::from(*quote)),
+            _ => Some(raw)
+        }
+    }
+}
+
+/// Finds the path, span and kind of the innermost value containing a byte offset in a single pass over a raw json string.
+/// Offsets on an object key locate the key, offsets between elements belong to the enclosing object or array.
+///
+/// Returns `None` if the offset is outside of the root value.
+///
+/// # Examples
+///
+/// ```
+/// use jsonposition::{locate, Kind};
+///
+/// let json = r#"[null, 9, {"a": "bcd"}]"#;
+///
+/// let location = locate(json, json.find("c").unwrap()).expect("Invalid JSON").unwrap();
+/// assert_eq!(location.path.to_string(), "$.2.a");
+/// assert_eq!(&json[location.span], r#""bcd""#);
+/// assert_eq!(location.kind, Kind::String);
+/// assert_eq!(&json[location.parent_span.unwrap()], r#"{"a": "bcd"}"#);
+/// ```
+///
+/// # Errors
+///
+/// Returns an [`Error`](crate::Error) if the input json is invalid.
+pub fn locate(text: &str, offset: usize) -> Result<Option<Location>, Error> {
+    let mut walker = Walker::new(text);
+    let mut found: Option<Location> = None;
+    let mut complete = false;
+
+    // Key of the next value, and the keys of all open containers
+    let mut pending_key: Option<Range<usize>> = None;
+    let mut keys: Vec<Option<Range<usize>>> = Vec::new();
+
+    // Inner containers end before outer ones, so the first span to contain
+    // the offset is the value, and the next one is its parent.
+    while let Some(event) = walker.next() {
+        let (span, kind, key_span) = match event? {
+            Event::StartObject(_) | Event::StartArray(_) => {
+                keys.push(pending_key.take());
+                continue;
+            }
+            Event::Scalar(span) => {
+                let kind = Kind::of_scalar(&text.as_bytes()[span.clone()]);
+                (span, kind, pending_key.take())
+            }
+            Event::EndObject(span) => (span, Kind::Object, keys.pop().flatten()),
+            Event::EndArray(span) => (span, Kind::Array, keys.pop().flatten()),
+            Event::Key(span) => {
+                pending_key = Some(span.clone());
+                (span.clone(), Kind::ObjectKey, Some(span))
+            }
+            Event::Comma(_) => continue
+        };
+
+        // Keeps scanning once complete to validate the rest of the document
+        if complete || !span.contains(&offset) {
+            continue;
+        }
+
+        match &mut found {
+            Some(location) => {
+                location.parent_span = Some(span);
+                complete = true;
+            }
+            None => {
+                found = Some(Location { path: walker.path().clone(), span, kind, parent_span: None, key_span });
+            }
+        }
+    }
+
+    Ok(found)
+}
+
+/// Finds the kind of token at a byte offset in a raw json string, along with its path.
+/// Shorthand for the path and kind of [`locate`].
+///
+/// # Examples
+///
+/// ```
+/// use jsonposition::{kind_at, Kind};
+///
+/// let json = r#"{"a": [null, 9]}"#;
+///
+/// let (path, kind) = kind_at(json, json.find("9").unwrap()).expect("Invalid JSON").unwrap();
+/// assert_eq!((path.to_string().as_str(), kind), ("$.a.1", Kind::Number));
+///
+/// let (path, kind) = kind_at(json, json.find("a").unwrap()).expect("Invalid JSON").unwrap();
+/// assert_eq!((path.to_string().as_str(), kind), ("$.a", Kind::ObjectKey));
+/// ```
+///
+/// # Errors
+///
+/// Returns an [`Error`](crate::Error) if the input json is invalid.
+pub fn kind_at(text: &str, offset: usize) -> Result<Option<(Path, Kind)>, Error> {
+    Ok(locate(text, offset)?.map(|location| (location.path, location.kind)))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn locations() {
+        let json = r#"[9, {"field1": true, "field2": [null, null, 87, 4]}]"#;
+
+        let location = locate(json, json.find("87").unwrap()).expect("Invalid JSON").unwrap();
+        assert_eq!(location.path.to_string(), "$.1.field2.2");
+        assert_eq!(&json[location.span], "87");
+        assert_eq!(location.key_span, None);
+        assert_eq!(location.kind, Kind::Number);
+        assert_eq!(&json[location.parent_span.unwrap()], "[null, null, 87, 4]");
+
+        let location = locate(json, json.find("true").unwrap()).expect("Invalid JSON").unwrap();
+        assert_eq!(location.kind, Kind::Bool);
+        assert_eq!(location.path.to_string(), "$.1.field1");
+        assert!(!location.in_key());
+        assert_eq!(&json[location.key_span.unwrap()], r#""field1""#);
+
+        // Tests keys
+        let location = locate(json, json.find("field2").unwrap()).expect("Invalid JSON").unwrap();
+        assert_eq!(location.kind, Kind::ObjectKey);
+        assert!(location.in_key());
+        assert_eq!(location.path.to_string(), "$.1.field2");
+        assert_eq!(location.key_span, Some(location.span.clone()));
+        assert_eq!(&json[location.span], r#""field2""#);
+        assert_eq!(&json[location.parent_span.unwrap()], r#"{"field1": true, "field2": [null, null, 87, 4]}"#);
+
+        // Tests containers
+        let location = locate(json, json.find(", null").unwrap()).expect("Invalid JSON").unwrap();
+        assert_eq!(location.kind, Kind::Array);
+        assert_eq!(location.path.to_string(), "$.1.field2");
+        assert_eq!(&json[location.key_span.unwrap()], r#""field2""#);
+
+        // Tests root
+        let location = locate(json, 0).expect("Invalid JSON").unwrap();
+        assert_eq!(location.span, 0..json.len());
+        assert!(location.path.is_root());
+        assert_eq!(location.parent_span, None);
+
+        // Tests out of bounds
+        assert_eq!(locate(json, 1000).unwrap(), None);
+
+        // Tests raw and decoded keys
+        let json = r#"{"a\"b": [{"\ud83d\ude00": 1}]}"#;
+        let location = locate(json, json.find("[").unwrap()).unwrap().unwrap();
+        assert_eq!((location.key(), location.raw_key(json)), (Some("a\"b"), Some(r#"a\"b"#)));
+        let location = locate(json, json.find("ude00").unwrap()).unwrap().unwrap();
+        assert_eq!((location.key(), location.raw_key(json)), (Some("😀"), Some(r"\ud83d\ude00")));
+        let location = locate(json, json.find("1").unwrap()).unwrap().unwrap();
+        assert_eq!(location.key(), Some("😀"));
+        let location = locate(json, json.len() - 1).unwrap().unwrap();
+        assert_eq!((location.kind, location.key(), location.raw_key(json)), (Kind::Object, None, None));
+    }
+}
@@ -0,0 +1,392 @@
+use core::ops::Range;
+use std::collections::HashMap;
+
+use lsp_server::{Connection, ErrorCode, Message, Notification, Request, Response, ResponseError};
+use lsp_types::notification::{DidChangeTextDocument, DidCloseTextDocument, DidOpenTextDocument, Notification as _};
+use lsp_types::request::{DocumentSymbolRequest, ExecuteCommand, Request as _};
+use lsp_types::{
+    DidChangeTextDocumentParams, DidCloseTextDocumentParams, DidOpenTextDocumentParams, DocumentSymbol, DocumentSymbolParams,
+    DocumentSymbolResponse, ExecuteCommandOptions, ExecuteCommandParams, OneOf, ServerCapabilities, SymbolKind,
+    TextDocumentPositionParams, TextDocumentSyncCapability, TextDocumentSyncKind, Uri
+};
+use serde_json::{json, Value};
+
+use crate::error::Error;
+use crate::index::DocumentIndex;
+use crate::lines::{LineIndex, Position};
+use crate::locate::Kind;
+
+/// Command returning the dot path at a position, for the client to copy.
+/// Takes a `TextDocumentPositionParams` and optionally `"pointer"` to return a JSON Pointer instead.
+pub const COPY_PATH_COMMAND: &str = "jsonPosition.copyPath";
+
+/// Request returning every value containing a position, outermost first, for breadcrumbs.
+/// Takes a `TextDocumentPositionParams`, and each value has its `path`, `kind` and `range`.
+pub const BREADCRUMBS_REQUEST: &str = "jsonPosition/breadcrumbs";
+
+type BoxError = Box<dyn std::error::Error + Send + Sync>;
+
+/// Open document, indexed on every change
+struct Document {
+    text: String,
+    index: Result<DocumentIndex, Error>
+}
+
+impl Document {
+    fn new(text: String) -> Self {
+        let index = DocumentIndex::new(&text);
+        Document { text, index }
+    }
+}
+
+/// Converts a byte offset to an LSP position, with the column in UTF-16 code units
+fn lsp_position(lines: &LineIndex, text: &str, offset: usize) -> lsp_types::Position {
+    let Position { line, .. } = lines.offset_to_position(offset).unwrap_or_default();
+    let start = lines.line_span(line).map_or(0, |span| span.start);
+    let column = text.get(start..offset).map_or(0, |before| before.encode_utf16().count());
+    lsp_types::Position::new(line as u32, column as u32)
+}
+
+fn lsp_range(lines: &LineIndex, text: &str, span: &Range<usize>) -> lsp_types::Range {
+    lsp_types::Range::new(lsp_position(lines, text, span.start), lsp_position(lines, text, span.end))
+}
+
+/// Converts an LSP position to a byte offset, columns past the end of the line are clamped to its end
+fn byte_offset(lines: &LineIndex, text: &str, position: lsp_types::Position) -> Option<usize> {
+    let span = lines.line_span(position.line as usize)?;
+    let mut units = 0;
+    for (i, c) in text[span.clone()].char_indices() {
+        if units >= position.character as usize {
+            return Some(span.start + i);
+        }
+        units += c.len_utf16();
+    }
+    Some(span.end)
+}
+
+fn symbol_kind(kind: Kind) -> SymbolKind {
+    match kind {
+        Kind::Null => SymbolKind::NULL,
+        Kind::Bool => SymbolKind::BOOLEAN,
+        Kind::Number => SymbolKind::NUMBER,
+        Kind::String | Kind::ObjectKey => SymbolKind::STRING,
+        Kind::Object => SymbolKind::OBJECT,
+        Kind::Array => SymbolKind::ARRAY
+    }
+}
+
+/// Builds the nested outline of a raw json string, ready to return from a `textDocument/documentSymbol` handler.
+/// The children of the root value are at the top level, named by their key or array index.
+///
+/// A member ranges from its key to the end of its value and selects its key,
+/// array elements range over and select their value. Positions have columns in UTF-16 code units, as LSP expects.
+///
+/// # Examples
+///
+/// ```
+/// use jsonposition::lsp::document_symbols;
+/// use lsp_types::SymbolKind;
+///
+/// let json = "{\n  \"a\": [1, {\"b\": null}]\n}";
+/// let symbols = document_symbols(json).expect("Invalid JSON");
+///
+/// assert_eq!(symbols[0].name, "a");
+/// assert_eq!(symbols[0].kind, SymbolKind::ARRAY);
+/// assert_eq!((symbols[0].selection_range.start.line, symbols[0].selection_range.start.character), (1, 2));
+///
+/// let children = symbols[0].children.as_ref().unwrap();
+/// assert_eq!(children[1].children.as_ref().unwrap()[0].name, "b");
+/// ```
+///
+/// # Errors
+///
+/// Returns an [`Error`](crate::Error) if the input json is invalid.
+pub fn document_symbols(text: &str) -> Result<Vec<DocumentSymbol>, Error> {
+    Ok(symbols_of(text, &DocumentIndex::new(text)?))
+}
+
+/// Outline of a document that has already been indexed
+fn symbols_of(text: &str, index: &DocumentIndex) -> Vec<DocumentSymbol> {
+    let lines = LineIndex::new(text);
+    let mut symbols = Vec::new();
+    // Symbols still open, along with where they end
+    let mut open: Vec<(usize, DocumentSymbol)> = Vec::new();
+    let mut key: Option<Range<usize>> = None;
+
+    let close = |open: &mut Vec<(usize, DocumentSymbol)>, symbols: &mut Vec<DocumentSymbol>| {
+        if let Some((_, symbol)) = open.pop() {
+            match open.last_mut() {
+                Some((_, parent)) => parent.children.get_or_insert_with(Vec::new).push(symbol),
+                None => symbols.push(symbol)
+            }
+        }
+    };
+
+    for (path, span, kind) in index.nodes() {
+        if kind == Kind::ObjectKey {
+            key = Some(span.clone());
+            continue;
+        }
+        let selection = key.take().unwrap_or_else(|| span.clone());
+        let Some(last) = path.last() else {
+            continue;
+        };
+
+        while open.last().is_some_and(|(end, _)| *end <= span.start) {
+            close(&mut open, &mut symbols);
+        }
+
+        #[allow(deprecated)]
+        let symbol = DocumentSymbol {
+            name: last.to_string(),
+            detail: None,
+            kind: symbol_kind(kind),
+            tags: None,
+            deprecated: None,
+            range: lsp_range(&lines, text, &(selection.start..span.end)),
+            selection_range: lsp_range(&lines, text, &selection),
+            children: None
+        };
+        open.push((span.end, symbol));
+    }
+    while !open.is_empty() {
+        close(&mut open, &mut symbols);
+    }
+
+    symbols
+}
+
+/// Language server state, the text and index of every open document
+#[derive(Default)]
+struct Server {
+    documents: HashMap<Uri, Document>
+}
+
+impl Server {
+    /// Updates the open documents
+    fn notify(&mut self, notification: Notification) {
+        match notification.method.as_str() {
+            DidOpenTextDocument::METHOD => {
+                if let Ok(params) = serde_json::from_value::<DidOpenTextDocumentParams>(notification.params) {
+                    self.documents.insert(params.text_document.uri, Document::new(params.text_document.text));
+                }
+            }
+            DidChangeTextDocument::METHOD => {
+                if let Ok(params) = serde_json::from_value::<DidChangeTextDocumentParams>(notification.params) {
+                    // Only full syncs are advertised, so the last change is the whole text
+                    if let Some(change) = params.content_changes.into_iter().last() {
+                        self.documents.insert(params.text_document.uri, Document::new(change.text));
+                    }
+                }
+            }
+            DidCloseTextDocument::METHOD => {
+                if let Ok(params) = serde_json::from_value::<DidCloseTextDocumentParams>(notification.params) {
+                    self.documents.remove(&params.text_document.uri);
+                }
+            }
+            _ => {}
+        }
+    }
+
+    fn request(&self, request: Request) -> Response {
+        let id = request.id.clone();
+        match self.handle(request) {
+            Ok(result) => Response::new_ok(id, result),
+            Err(error) => Response { id, result: None, error: Some(error) }
+        }
+    }
+
+    fn handle(&self, request: Request) -> Result<Value, ResponseError> {
+        match request.method.as_str() {
+            DocumentSymbolRequest::METHOD => {
+                let params: DocumentSymbolParams = parse_params(request.params)?;
+                let document = self.document(&params.text_document.uri)?;
+                // Invalid documents have no outline rather than failing the request
+                let symbols = document.index.as_ref().map_or_else(|_| Vec::new(), |index| symbols_of(&document.text, index));
+                Ok(serde_json::to_value(DocumentSymbolResponse::Nested(symbols)).unwrap_or_default())
+            }
+            ExecuteCommand::METHOD => {
+                let params: ExecuteCommandParams = parse_params(request.params)?;
+                if params.command != COPY_PATH_COMMAND {
+                    return Err(response_error(ErrorCode::InvalidParams, format!("unknown command '{}'", params.command)));
+                }
+                let mut arguments = params.arguments.into_iter();
+                let position: TextDocumentPositionParams = parse_params(arguments.next().unwrap_or_default())?;
+                let pointer = arguments.next().is_some_and(|format| format == "pointer");
+
+                let (_, index, offset) = self.resolve(&position)?;
+                let path = index.path_at(offset);
+                Ok(Value::String(if pointer { path.to_pointer() } else { path.to_string() }))
+            }
+            BREADCRUMBS_REQUEST => {
+                let position: TextDocumentPositionParams = parse_params(request.params)?;
+                let (document, index, offset) = self.resolve(&position)?;
+                let lines = LineIndex::new(&document.text);
+                let crumbs: Vec<Value> = index.nodes_containing(offset).into_iter()
+                    .filter(|(_, _, kind)| *kind != Kind::ObjectKey)
+                    .map(|(path, span, kind)| json!({
+                        "path": path.to_string(),
+                        "kind": kind.to_string(),
+                        "range": lsp_range(&lines, &document.text, &span)
+                    }))
+                    .collect();
+                Ok(Value::Array(crumbs))
+            }
+            _ => Err(response_error(ErrorCode::MethodNotFound, format!("unknown method '{}'", request.method)))
+        }
+    }
+
+    fn document(&self, uri: &Uri) -> Result<&Document, ResponseError> {
+        self.documents.get(uri).ok_or_else(|| response_error(ErrorCode::InvalidParams, format!("document '{}' is not open", uri.as_str())))
+    }
+
+    /// Finds the document, its index and the byte offset of a position
+    fn resolve(&self, position: &TextDocumentPositionParams) -> Result<(&Document, &DocumentIndex, usize), ResponseError> {
+        let document = self.document(&position.text_document.uri)?;
+        let index = document.index.as_ref().map_err(|err| response_error(ErrorCode::RequestFailed, err.to_string()))?;
+        let lines = LineIndex::new(&document.text);
+        let offset = byte_offset(&lines, &document.text, position.position)
+            .ok_or_else(|| response_error(ErrorCode::InvalidParams, "position is past the end of the document".to_string()))?;
+        Ok((document, index, offset))
+    }
+}
+
+fn parse_params<T: serde::de::DeserializeOwned>(params: Value) -> Result<T, ResponseError> {
+    serde_json::from_value(params).map_err(|err| response_error(ErrorCode::InvalidParams, err.to_string()))
+}
+
+fn response_error(code: ErrorCode, message: String) -> ResponseError {
+    ResponseError { code: code as i32, message, data: None }
+}
+
+/// Runs a language server over a connection until the client shuts it down.
+///
+/// The server offers document symbols, the [`COPY_PATH_COMMAND`] command and the [`BREADCRUMBS_REQUEST`] request.
+/// Documents are synced in full and reindexed on every change.
+///
+/// # Examples
+///
+/// ```no_run
+/// let (connection, io_threads) = lsp_server::Connection::stdio();
+/// jsonposition::lsp::serve(&connection).expect("Language server failed");
+/// io_threads.join().unwrap();
+/// ```
+///
+/// # Errors
+///
+/// Returns an error if the connection fails or the client doesn't initialize it properly.
+pub fn serve(connection: &Connection) -> Result<(), BoxError> {
+    let capabilities = ServerCapabilities {
+        text_document_sync: Some(TextDocumentSyncCapability::Kind(TextDocumentSyncKind::FULL)),
+        document_symbol_provider: Some(OneOf::Left(true)),
+        execute_command_provider: Some(ExecuteCommandOptions { commands: vec![COPY_PATH_COMMAND.to_string()], ..Default::default() }),
+        ..Default::default()
+    };
+    connection.initialize(serde_json::to_value(capabilities)?)?;
+
+    let mut server = Server::default();
+    for message in &connection.receiver {
+        match message {
+            Message::Request(request) => {
+                if connection.handle_shutdown(&request)? {
+                    return Ok(());
+                }
+                connection.sender.send(Message::Response(server.request(request)))?;
+            }
+            Message::Notification(notification) => server.notify(notification),
+            Message::Response(_) => {}
+        }
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use lsp_server::RequestId;
+
+    #[test]
+    fn positions() {
+        let text = "{\n  \"😀\": [1, 2]\n}";
+        let lines = LineIndex::new(text);
+        let two = text.find('2').unwrap();
+
+        assert_eq!(lsp_position(&lines, text, two), lsp_types::Position::new(1, 12));
+        assert_eq!(byte_offset(&lines, text, lsp_types::Position::new(1, 12)), Some(two));
+        assert_eq!(byte_offset(&lines, text, lsp_types::Position::new(1, 100)), Some(text.rfind('\n').unwrap()));
+        assert_eq!(byte_offset(&lines, text, lsp_types::Position::new(3, 0)), None);
+    }
+
+    #[test]
+    fn symbols() {
+        let text = "[\n  {\"é\": 1},\n  \"x\"\n]";
+        let symbols = document_symbols(text).unwrap();
+
+        assert_eq!(symbols.iter().map(|symbol| symbol.name.as_str()).collect::<Vec<_>>(), ["0", "1"]);
+        assert_eq!(symbols[1].kind, SymbolKind::STRING);
+        assert_eq!(symbols[1].range, symbols[1].selection_range);
+        assert!(symbols[1].children.is_none());
+
+        let member = &symbols[0].children.as_ref().unwrap()[0];
+        assert_eq!(member.selection_range, lsp_types::Range::new(lsp_types::Position::new(1, 3), lsp_types::Position::new(1, 6)));
+        assert_eq!(member.range.end, lsp_types::Position::new(1, 9));
+
+        assert!(document_symbols("[").is_err());
+        assert!(document_symbols("1").unwrap().is_empty());
+    }
+
+    #[test]
+    fn server() {
+        let (server, client) = Connection::memory();
+        let thread = std::thread::spawn(move || serve(&server).unwrap());
+
+        let mut next_id = 0;
+        let mut request = |method: &str, params: Value| -> Response {
+            next_id += 1;
+            client.sender.send(Message::Request(Request::new(RequestId::from(next_id), method.to_string(), params))).unwrap();
+            loop {
+                if let Message::Response(response) = client.receiver.recv().unwrap() {
+                    return response;
+                }
+            }
+        };
+        let notify = |method: &str, params: Value| {
+            client.sender.send(Message::Notification(Notification::new(method.to_string(), params))).unwrap();
+        };
+
+        request("initialize", json!({ "capabilities": {} }));
+        notify("initialized", json!({}));
+
+        let uri = "file:///a.json";
+        let text = "{\n  \"a\": [1, {\"b\": null}]\n}";
+        notify(DidOpenTextDocument::METHOD, json!({ "textDocument": { "uri": uri, "languageId": "json", "version": 1, "text": text } }));
+
+        let symbols = request(DocumentSymbolRequest::METHOD, json!({ "textDocument": { "uri": uri } })).result.unwrap();
+        let symbols: Vec<DocumentSymbol> = serde_json::from_value(symbols).unwrap();
+        assert_eq!(symbols.len(), 1);
+        assert_eq!(symbols[0].name, "a");
+        assert_eq!(symbols[0].selection_range, lsp_types::Range::new(lsp_types::Position::new(1, 2), lsp_types::Position::new(1, 5)));
+        let children = symbols[0].children.as_ref().unwrap();
+        assert_eq!(children.iter().map(|child| child.name.as_str()).collect::<Vec<_>>(), ["0", "1"]);
+        assert_eq!(children[1].children.as_ref().unwrap()[0].name, "b");
+
+        let position = json!({ "textDocument": { "uri": uri }, "position": { "line": 1, "character": 17 } });
+        let path = request(ExecuteCommand::METHOD, json!({ "command": COPY_PATH_COMMAND, "arguments": [position.clone()] }));
+        assert_eq!(path.result, Some(json!("$.a.1.b")));
+        let pointer = request(ExecuteCommand::METHOD, json!({ "command": COPY_PATH_COMMAND, "arguments": [position.clone(), "pointer"] }));
+        assert_eq!(pointer.result, Some(json!("/a/1/b")));
+
+        let crumbs = request(BREADCRUMBS_REQUEST, position).result.unwrap();
+        let paths: Vec<&str> = crumbs.as_array().unwrap().iter().map(|crumb| crumb["path"].as_str().unwrap()).collect();
+        assert_eq!(paths, ["$", "$.a", "$.a.1", "$.a.1.b"]);
+
+        // Tests unknown documents and methods
+        assert!(request(DocumentSymbolRequest::METHOD, json!({ "textDocument": { "uri": "file:///b.json" } })).error.is_some());
+        assert!(request("jsonPosition/unknown", json!({})).error.is_some());
+
+        request("shutdown", Value::Null);
+        notify("exit", Value::Null);
+        thread.join().unwrap();
+    }
+}
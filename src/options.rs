@@ -0,0 +1,238 @@
+use alloc::sync::Arc;
+use core::sync::atomic::AtomicBool;
+#[cfg(feature = "std")]
+use core::time::Duration;
+
+/// Options controlling how json is scanned.
+///
+/// # Examples
+///
+/// ```
+/// use jsonposition::{dot_path_with, Options};
+///
+/// // Missing comma between elements
+/// let json = r#"[1 {"a": 2}]"#;
+///
+/// let path = dot_path_with(json, json.find("2").unwrap(), &Options::new().lenient(true)).unwrap();
+/// assert_eq!(path, "$.1.a");
+/// ```
+#[derive(Debug, Clone, Default)]
+pub struct Options {
+    pub(crate) lenient: bool,
+    pub(crate) truncated: bool,
+    pub(crate) comments: bool,
+    pub(crate) trailing_commas: bool,
+    pub(crate) multiple: bool,
+    pub(crate) json5: bool,
+    pub(crate) cancel: Option<Arc<AtomicBool>>,
+    pub(crate) max_depth: Option<usize>,
+    pub(crate) limits: Limits
+}
+
+impl Options {
+    /// Creates options for strict json.
+    pub fn new() -> Self {
+        Options::default()
+    }
+
+    /// Accepts malformed json, making a best guess at the structure instead of returning an error.
+    /// Missing commas and colons are assumed, stray or missing braces are matched up with the closest container,
+    /// and unknown tokens are treated as values.
+    ///
+    /// Paths computed in lenient mode stop scanning at the offset, like [`path_unchecked`](crate::path_unchecked).
+    pub fn lenient(mut self, lenient: bool) -> Self {
+        self.lenient = lenient;
+        self
+    }
+
+    /// Accepts a document that ends early, such as a file still being downloaded.
+    /// Unfinished strings, numbers and literals run to the end of the text and open containers are closed there,
+    /// but the json is otherwise strict.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use jsonposition::{dot_path_with, Options};
+    ///
+    /// let json = r#"[1, {"a": [tru"#;
+    ///
+    /// let path = dot_path_with(json, json.len(), &Options::new().allow_truncated(true)).unwrap();
+    /// assert_eq!(path, "$.1.a.0");
+    /// ```
+    pub fn allow_truncated(mut self, truncated: bool) -> Self {
+        self.truncated = truncated;
+        self
+    }
+
+    /// Skips `//` line comments and `/* */` block comments like whitespace,
+    /// as used by JSONC files such as VS Code settings and `tsconfig.json`.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use jsonposition::{dot_path_with, Options};
+    ///
+    /// let json = "{\n  // \"a\": [\n  \"b\": /* { */ 1\n}";
+    ///
+    /// let path = dot_path_with(json, json.find("1").unwrap(), &Options::new().allow_comments(true)).unwrap();
+    /// assert_eq!(path, "$.b");
+    /// ```
+    pub fn allow_comments(mut self, comments: bool) -> Self {
+        self.comments = comments;
+        self
+    }
+
+    /// Accepts a comma after the last element of an array or object, as in `[1, 2,]`.
+    /// A comma on its own, as in `[,]`, is still an error.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use jsonposition::{dot_path_with, Options};
+    ///
+    /// let json = r#"{"a": [1, 2,], "b": 3,}"#;
+    ///
+    /// let path = dot_path_with(json, json.find("2").unwrap(), &Options::new().allow_trailing_commas(true)).unwrap();
+    /// assert_eq!(path, "$.a.1");
+    /// ```
+    pub fn allow_trailing_commas(mut self, trailing_commas: bool) -> Self {
+        self.trailing_commas = trailing_commas;
+        self
+    }
+
+    /// Accepts any number of root values one after another, as in `{"a": 1}{"b": 2}`,
+    /// including none at all. Paths are relative to the root of the value they fall in,
+    /// see [`path_in_documents`](crate::path_in_documents) to also find which value that is.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use jsonposition::{dot_path_with, Options};
+    ///
+    /// let json = r#"{"a": 1}{"b": [2, 3]} 4"#;
+    ///
+    /// let path = dot_path_with(json, json.find("3").unwrap(), &Options::new().allow_multiple_documents(true)).unwrap();
+    /// assert_eq!(path, "$.b.1");
+    /// ```
+    pub fn allow_multiple_documents(mut self, multiple: bool) -> Self {
+        self.multiple = multiple;
+        self
+    }
+
+    /// Fails with [`ErrorKind::DepthExceeded`](crate::ErrorKind::DepthExceeded) at the opening brace
+    /// of any container nested more than `depth` deep, guarding against pathological input like a million `[`.
+    /// The root container has a depth of 1, and there is no limit by default.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use jsonposition::{path_with, ErrorKind, Options};
+    ///
+    /// let error = path_with("[[[1]]]", 0, &Options::new().max_depth(2)).unwrap_err();
+    /// assert_eq!((error.kind(), error.offset()), (ErrorKind::DepthExceeded, 2));
+    /// ```
+    pub fn max_depth(mut self, depth: usize) -> Self {
+        self.max_depth = Some(depth);
+        self
+    }
+
+    /// Bounds the work done on untrusted input, see [`Limits`].
+    pub fn limits(mut self, limits: Limits) -> Self {
+        self.limits = limits;
+        self
+    }
+
+    /// Stops scanning with [`ErrorKind::Cancelled`](crate::ErrorKind::Cancelled) once `flag` is set,
+    /// so a long scan such as building a [`DocumentIndex`](crate::DocumentIndex) can be abandoned from another thread.
+    /// The flag is checked roughly every 64 kilobytes.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use std::sync::Arc;
+    /// use std::sync::atomic::{AtomicBool, Ordering};
+    /// use jsonposition::{DocumentIndex, ErrorKind, Options};
+    ///
+    /// let cancel = Arc::new(AtomicBool::new(false));
+    /// let options = Options::new().cancel_flag(cancel.clone());
+    ///
+    /// cancel.store(true, Ordering::Relaxed);
+    /// let error = DocumentIndex::with_options("[1, 2]", &options).unwrap_err();
+    /// assert_eq!(error.kind(), ErrorKind::Cancelled);
+    /// ```
+    pub fn cancel_flag(mut self, flag: Arc<AtomicBool>) -> Self {
+        self.cancel = Some(flag);
+        self
+    }
+
+    /// Accepts the [JSON5](https://spec.json5.org) dialect: comments, trailing commas,
+    /// unquoted and single quoted keys and strings, hexadecimal numbers, leading or trailing decimal points,
+    /// explicit plus signs, `Infinity` and `NaN`.
+    ///
+    /// Paths use the key names, so `{a: 1}` and `{'a': 1}` both have the path `$.a` to `1`.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use jsonposition::{dot_path_with, Options};
+    ///
+    /// let json = "{unquoted: 'single', hex: [0xFF, +Infinity, .5,],}";
+    ///
+    /// let path = dot_path_with(json, json.find(".5").unwrap(), &Options::new().json5(true)).unwrap();
+    /// assert_eq!(path, "$.hex.2");
+    /// ```
+    pub fn json5(mut self, json5: bool) -> Self {
+        self.json5 = json5;
+        self
+    }
+}
+
+/// Limits on the work done scanning a document, for handling untrusted input safely.
+/// Nothing is limited by default.
+///
+/// # Examples
+///
+/// ```
+/// use std::time::Duration;
+/// use jsonposition::{path_with, ErrorKind, Limits, Options};
+///
+/// let limits = Limits::new().max_bytes(1 << 20).max_nodes(2).time_budget(Duration::from_millis(100));
+///
+/// let error = path_with("[1, 2]", 0, &Options::new().limits(limits)).unwrap_err();
+/// assert_eq!(error.kind(), ErrorKind::NodesExceeded);
+/// ```
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct Limits {
+    pub(crate) bytes: Option<usize>,
+    pub(crate) nodes: Option<usize>,
+    #[cfg(feature = "std")]
+    pub(crate) time: Option<Duration>
+}
+
+impl Limits {
+    /// Creates limits that allow anything.
+    pub fn new() -> Self {
+        Limits::default()
+    }
+
+    /// Fails with [`ErrorKind::SizeExceeded`](crate::ErrorKind::SizeExceeded) on input longer than `bytes`, before scanning any of it.
+    pub fn max_bytes(mut self, bytes: usize) -> Self {
+        self.bytes = Some(bytes);
+        self
+    }
+
+    /// Fails with [`ErrorKind::NodesExceeded`](crate::ErrorKind::NodesExceeded) at the first value past `nodes` values.
+    /// Objects, arrays and scalars each count as one value, keys don't count.
+    pub fn max_nodes(mut self, nodes: usize) -> Self {
+        self.nodes = Some(nodes);
+        self
+    }
+
+    /// Fails with [`ErrorKind::TimeExceeded`](crate::ErrorKind::TimeExceeded) once scanning has taken `budget`.
+    /// The time is checked roughly every 64 kilobytes, so it can be overrun by the time taken to scan that much.
+    #[cfg(feature = "std")]
+    pub fn time_budget(mut self, budget: Duration) -> Self {
+        self.time = Some(budget);
+        self
+    }
+}
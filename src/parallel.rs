@@ -0,0 +1,43 @@
+use rayon::prelude::*;
+
+use crate::error::Error;
+use crate::path::Path;
+
+/// Resolves the offsets in many documents across threads, with [`paths`](crate::paths) on each document.
+/// Results are returned in the same order as `documents`, and an invalid document doesn't stop the others.
+///
+/// # Examples
+///
+/// ```
+/// use jsonposition::par_paths;
+///
+/// let documents = vec![(r#"{"a": 1}"#, vec![6]), (r#"[1, 2]"#, vec![1, 4]), ("[1,]", vec![0])];
+///
+/// let results = par_paths(documents);
+/// assert_eq!(results[0].as_ref().unwrap()[0].to_string(), "$.a");
+/// assert_eq!(results[1].as_ref().unwrap()[1].to_string(), "$.1");
+/// assert!(results[2].is_err());
+/// ```
+pub fn par_paths<I, D, O>(documents: I) -> Vec<Result<Vec<Path>, Error>>
+where
+    I: IntoParallelIterator<Item = (D, O)>,
+    D: AsRef<str>,
+    O: AsRef<[usize]>
+{
+    documents.into_par_iter().map(|(text, offsets)| crate::paths(text.as_ref(), offsets.as_ref())).collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parallel() {
+        let documents: Vec<(String, Vec<usize>)> = (0..100).map(|i| (format!(r#"[{}, {{"a": [{}]}}]"#, i, i), vec![1, 10, 1000])).collect();
+
+        let results = par_paths(documents.clone());
+        for ((text, offsets), result) in documents.iter().zip(results) {
+            assert_eq!(result.unwrap(), crate::paths(text, offsets).unwrap());
+        }
+    }
+}
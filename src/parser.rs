@@ -0,0 +1,152 @@
+use core::ops::Range;
+
+use crate::error::Error;
+use crate::locate::Kind;
+use crate::options::Options;
+use crate::scan::{Event, Scanner};
+
+/// Event yielded by a [`Parser`], with byte offsets into the parsed text.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ParseEvent {
+    /// Opening `{` at the contained offset
+    StartObject(usize),
+    /// Closing `}`, with the span of the whole object
+    EndObject(Range<usize>),
+    /// Opening `[` at the contained offset
+    StartArray(usize),
+    /// Closing `]`, with the span of the whole array
+    EndArray(Range<usize>),
+    /// Object key, with the span including its quotes
+    Key(Range<usize>),
+    /// String, number, boolean or null, with its span
+    Value(Range<usize>, Kind)
+}
+
+impl ParseEvent {
+    /// Span of the token the event was produced by, a single byte for the brace starting a container
+    /// and the whole container for the brace ending it.
+    pub fn span(&self) -> Range<usize> {
+        match self {
+            ParseEvent::StartObject(i) | ParseEvent::StartArray(i) => *i..*i + 1,
+            ParseEvent::EndObject(span) | ParseEvent::EndArray(span) | ParseEvent::Key(span) | ParseEvent::Value(span, _) => span.clone()
+        }
+    }
+}
+
+/// Pull parser over a raw json string, yielding each [`ParseEvent`] in the order it appears.
+///
+/// The document is validated as it is parsed, and the parser stops after yielding an error.
+/// Commas and colons don't produce events.
+///
+/// # Examples
+///
+/// ```
+/// use jsonposition::{Kind, ParseEvent, Parser};
+///
+/// let json = r#"{"a": [1]}"#;
+/// let events: Vec<ParseEvent> = Parser::new(json).collect::<Result<_, _>>().expect("Invalid JSON");
+///
+/// assert_eq!(events, [
+///     ParseEvent::StartObject(0),
+///     ParseEvent::Key(1..4),
+///     ParseEvent::StartArray(6),
+///     ParseEvent::Value(7..8, Kind::Number),
+///     ParseEvent::EndArray(6..9),
+///     ParseEvent::EndObject(0..10)
+/// ]);
+/// ```
+pub struct Parser<'a> {
+    bytes: &'a [u8],
+    scanner: Scanner<'a>
+}
+
+impl<'a> Parser<'a> {
+    /// Creates a parser over strict json.
+    pub fn new(text: &'a str) -> Self {
+        Parser::with_options(text, &Options::default())
+    }
+
+    /// Creates a parser accepting the input allowed by the given [`Options`].
+    pub fn with_options(text: &'a str, options: &Options) -> Self {
+        Parser { bytes: text.as_bytes(), scanner: Scanner::with_options(text.as_bytes(), options) }
+    }
+
+    /// Calls `progress` with the number of bytes parsed so far roughly every megabyte,
+    /// and once with the whole length after the last event.
+    pub fn on_progress(mut self, progress: impl FnMut(usize) + 'a) -> Self {
+        self.scanner.on_progress(progress);
+        self
+    }
+}
+
+impl Iterator for Parser<'_> {
+    type Item = Result<ParseEvent, Error>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        loop {
+            let event = match self.scanner.next()? {
+                Ok(Event::StartObject(i)) => ParseEvent::StartObject(i),
+                Ok(Event::EndObject(span)) => ParseEvent::EndObject(span),
+                Ok(Event::StartArray(i)) => ParseEvent::StartArray(i),
+                Ok(Event::EndArray(span)) => ParseEvent::EndArray(span),
+                Ok(Event::Key(span)) => ParseEvent::Key(span),
+                Ok(Event::Scalar(span)) => {
+                    let kind = Kind::of_scalar(&self.bytes[span.clone()]);
+                    ParseEvent::Value(span, kind)
+                }
+                Ok(Event::Comma(_)) => continue,
+                Err(e) => return Some(Err(e))
+            };
+            return Some(Ok(event));
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn events() {
+        let json = r#"[true, {"a": null}, "b", -1]"#;
+        let events: Vec<ParseEvent> = Parser::new(json).map(Result::unwrap).collect();
+
+        let kinds: Vec<Kind> = events.iter().filter_map(|e| match e {
+            ParseEvent::Value(_, kind) => Some(*kind),
+            _ => None
+        }).collect();
+        assert_eq!(kinds, [Kind::Bool, Kind::Null, Kind::String, Kind::Number]);
+        assert_eq!(&json[events[2].span()], "{");
+        assert_eq!(&json[events.last().unwrap().span()], json);
+
+        // Tests errors stop the parser
+        let mut parser = Parser::new("[1,]");
+        assert!(matches!(parser.next(), Some(Ok(ParseEvent::StartArray(0)))));
+        assert!(matches!(parser.next(), Some(Ok(ParseEvent::Value(..)))));
+        assert!(parser.next().unwrap().is_err());
+        assert!(parser.next().is_none());
+
+        // Tests progress is reported at the end
+        let mut reports = Vec::new();
+        Parser::new(json).on_progress(|bytes| reports.push(bytes)).for_each(drop);
+        assert_eq!(reports, [json.len()]);
+    }
+
+    #[test]
+    fn cancel() {
+        use std::sync::atomic::{AtomicBool, Ordering};
+
+        let json = format!("[{}0]", "0, ".repeat(100_000));
+        let cancel = std::sync::Arc::new(AtomicBool::new(false));
+        let options = Options::new().cancel_flag(cancel.clone());
+
+        // Tests cancelling partway through
+        let mut parser = Parser::with_options(&json, &options);
+        assert!(parser.by_ref().take(10).all(|event| event.is_ok()));
+        cancel.store(true, Ordering::Relaxed);
+
+        let error = parser.find_map(Result::err).unwrap();
+        assert_eq!(error.kind(), crate::ErrorKind::Cancelled);
+        assert!(error.offset() > 0 && error.offset() < json.len());
+    }
+}
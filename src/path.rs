@@ -0,0 +1,804 @@
+use alloc::borrow::ToOwned;
+use alloc::string::{String, ToString};
+use alloc::vec::Vec;
+
+use crate::edit::escape;
+use crate::format::{format_path, Jq, Js, PathFormatter, PathOptions, Python, Quoting};
+use crate::scan::unescape;
+
+/// Index or key into an array or object.
+///
+/// With the `serde` feature an index is serialized as a number and a key as a string.
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum Index {
+    Array(usize),
+    Object(String)
+}
+
+impl Index {
+    pub(crate) fn increment(&mut self) {
+        if let Index::Array(ref mut i) = self {
+            *i += 1;
+        }
+    }
+}
+
+impl core::fmt::Display for Index {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        write!(f, "{}", match self {
+            Index::Array(i) => i.to_string(),
+            Index::Object(key) => key.to_owned()
+        })
+    }
+}
+
+impl From<usize> for Index {
+    fn from(i: usize) -> Self {
+        Index::Array(i)
+    }
+}
+
+impl From<&str> for Index {
+    fn from(key: &str) -> Self {
+        Index::Object(key.to_owned())
+    }
+}
+
+impl From<String> for Index {
+    fn from(key: String) -> Self {
+        Index::Object(key)
+    }
+}
+
+/// Path from the root of a document to one of its elements.
+///
+/// An empty path is the root of the document itself.
+/// Paths compare segment by segment from the root, and with the `serde` feature are serialized as a sequence of [`Index`].
+/// Formatting a path with [`Display`](core::fmt::Display) produces the dotted form used by [`dot_path`](crate::dot_path),
+/// with keys that are empty, made of digits or hold dots, brackets, quotes or whitespace quoted in brackets instead, as in `$.a["b.c"]`.
+///
+/// # Examples
+///
+/// ```
+/// use jsonposition::{Path, Index};
+///
+/// let mut path = Path::new();
+/// path.push("fields");
+/// path.push(2);
+///
+/// assert_eq!(path.len(), 2);
+/// assert_eq!(path.to_string(), "$.fields.2");
+/// assert_eq!(path.parent().unwrap(), [Index::Object("fields".to_string())]);
+/// ```
+#[derive(Debug, Clone, Default, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct Path {
+    segments: Vec<Index>
+}
+
+impl Path {
+    /// Creates an empty path pointing at the root of a document.
+    pub fn new() -> Self {
+        Path { segments: Vec::new() }
+    }
+
+    /// Returns `true` if the path points at the root of the document.
+    pub fn is_root(&self) -> bool {
+        self.segments.is_empty()
+    }
+
+    /// Number of segments in the path.
+    pub fn len(&self) -> usize {
+        self.segments.len()
+    }
+
+    /// Returns `true` if the path has no segments, same as [`Path::is_root`].
+    pub fn is_empty(&self) -> bool {
+        self.segments.is_empty()
+    }
+
+    /// Path to the element containing this one, or `None` if this is the root.
+    pub fn parent(&self) -> Option<Path> {
+        self.segments.split_last().map(|(_, rest)| Path { segments: rest.to_vec() })
+    }
+
+    /// Last segment of the path, or `None` if this is the root.
+    pub fn last(&self) -> Option<&Index> {
+        self.segments.last()
+    }
+
+    /// Appends a segment to the end of the path.
+    pub fn push<I: Into<Index>>(&mut self, index: I) {
+        self.segments.push(index.into());
+    }
+
+    /// Removes the last segment of the path and returns it.
+    pub fn pop(&mut self) -> Option<Index> {
+        self.segments.pop()
+    }
+
+    /// Creates a new path with the segments of `other` appended to this one.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use jsonposition::Path;
+    ///
+    /// let base: Path = vec![1.into(), "fields".into()].into();
+    /// let rest: Path = vec![2.into()].into();
+    ///
+    /// assert_eq!(base.join(&rest).to_string(), "$.1.fields.2");
+    /// ```
+    pub fn join(&self, other: &Path) -> Path {
+        let mut segments = self.segments.clone();
+        segments.extend(other.segments.iter().cloned());
+        Path { segments }
+    }
+
+    /// Returns `true` if `prefix` is an ancestor of this path, or the path itself.
+    pub fn starts_with(&self, prefix: &Path) -> bool {
+        self.segments.starts_with(&prefix.segments)
+    }
+
+    /// Iterates over the segments of the path from the root.
+    pub fn iter(&self) -> core::slice::Iter<'_, Index> {
+        self.segments.iter()
+    }
+
+    /// Segments of the path as a slice.
+    pub fn as_slice(&self) -> &[Index] {
+        &self.segments
+    }
+
+    /// Consumes the path, returning its segments.
+    pub fn into_vec(self) -> Vec<Index> {
+        self.segments
+    }
+
+    /// Formats the path as a [JSON Pointer](https://www.rfc-editor.org/rfc/rfc6901),
+    /// escaping `~` as `~0` and `/` as `~1` in keys.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use jsonposition::Path;
+    ///
+    /// let path: Path = vec![1.into(), "a/b".into(), 2.into()].into();
+    /// assert_eq!(path.to_pointer(), "/1/a~1b/2");
+    /// ```
+    pub fn to_pointer(&self) -> String {
+        let mut pointer = String::new();
+        for i in &self.segments {
+            pointer.push('/');
+            match i {
+                Index::Array(i) => pointer += &i.to_string(),
+                Index::Object(key) => pointer += &key.replace('~', "~0").replace('/', "~1")
+            }
+        }
+        pointer
+    }
+
+    /// Formats the path with the callbacks of a [`PathFormatter`], such as a [`PathOptions`].
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use jsonposition::{Path, PathOptions};
+    ///
+    /// let path: Path = "$.fields.2".parse().unwrap();
+    /// assert_eq!(path.format(&PathOptions::new().separator("/")), "$/fields/2");
+    /// ```
+    pub fn format(&self, formatter: &impl PathFormatter) -> String {
+        let mut out = String::new();
+        formatter.root(&mut out);
+        for (i, index) in self.segments.iter().enumerate() {
+            match index {
+                Index::Array(index) => formatter.index(&mut out, *index, i == 0),
+                Index::Object(key) => formatter.key(&mut out, key, i == 0)
+            }
+        }
+        out
+    }
+
+    /// Formats the path in JSONPath bracket notation, with each key quoted and escaped like a json string
+    /// so keys holding dots, spaces or brackets stay unambiguous.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use jsonposition::Path;
+    ///
+    /// let path: Path = vec![1.into(), "file.name".into(), 2.into()].into();
+    /// assert_eq!(path.to_bracket(), r#"$[1]["file.name"][2]"#);
+    /// ```
+    pub fn to_bracket(&self) -> String {
+        format_path(self, &PathOptions::new().bracket_indices(true).quoting(Quoting::Always))
+    }
+
+    /// Formats the path in the mixed notation shown by most editors, such as `$.config.items[0].name`,
+    /// with dots before keys that are identifiers and brackets for array indices and any other key.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use jsonposition::Path;
+    ///
+    /// let path: Path = vec![1.into(), "fields".into(), 2.into(), "file name".into()].into();
+    /// assert_eq!(path.to_mixed(), r#"$[1].fields[2]["file name"]"#);
+    /// ```
+    pub fn to_mixed(&self) -> String {
+        format_path(self, &PathOptions::new().bracket_indices(true).quoting(Quoting::WhenNeeded))
+    }
+
+    /// Formats the path as a [jq](https://jqlang.github.io/jq/) filter such as `.config.items[0]`,
+    /// quoting keys that aren't identifiers as in `.["file name"]`. The root is `.`.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use jsonposition::Path;
+    ///
+    /// let path: Path = vec!["fields".into(), 2.into(), "a b".into()].into();
+    /// assert_eq!(path.to_jq(), r#".fields[2]["a b"]"#);
+    /// ```
+    pub fn to_jq(&self) -> String {
+        self.format(&Jq)
+    }
+
+    /// Formats the path as JavaScript property accessors on the variable `object`, such as `obj.fields[2]`,
+    /// so it can be pasted into devtools. Keys that aren't identifiers are accessed with brackets, as in `obj["a b"]`.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use jsonposition::Path;
+    ///
+    /// let path: Path = vec!["fields".into(), 2.into(), "content-type".into()].into();
+    /// assert_eq!(path.to_js("response"), r#"response.fields[2]["content-type"]"#);
+    /// ```
+    pub fn to_js(&self, object: &str) -> String {
+        self.format(&Js(object))
+    }
+
+    /// Formats the path as Python subscripts on the variable `object`, such as `data["fields"][2]`,
+    /// for pasting into Python or pandas code. Every key is subscripted since dicts have no attribute access.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use jsonposition::Path;
+    ///
+    /// let path: Path = vec!["fields".into(), 2.into(), "name".into()].into();
+    /// assert_eq!(path.to_python("data"), r#"data["fields"][2]["name"]"#);
+    /// ```
+    pub fn to_python(&self, object: &str) -> String {
+        self.format(&Python(object))
+    }
+
+    /// Finds the way from this path to `target`, going up to their common ancestor and then down.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use jsonposition::Path;
+    ///
+    /// let from: Path = "$.a.0".parse().unwrap();
+    /// let relative = from.relative_to(&"$.a.1.b".parse().unwrap());
+    ///
+    /// assert_eq!(relative.up, 1);
+    /// assert_eq!(relative.down.to_string(), "$.1.b");
+    /// assert_eq!(relative.to_string(), "1/1/b");
+    /// ```
+    pub fn relative_to(&self, target: &Path) -> RelativePath {
+        let shared = self.segments.iter().zip(&target.segments).take_while(|(a, b)| a == b).count();
+        RelativePath {
+            up: self.segments.len() - shared,
+            down: Path { segments: target.segments[shared..].to_vec() }
+        }
+    }
+
+    pub(crate) fn last_mut(&mut self) -> Option<&mut Index> {
+        self.segments.last_mut()
+    }
+}
+
+/// Whether a key can be written after a dot, starting with an ascii letter or `_` followed by letters, digits or `_`
+pub(crate) fn is_identifier(key: &str) -> bool {
+    key.starts_with(|c: char| c.is_ascii_alphabetic() || c == '_') && key.chars().all(|c| c.is_ascii_alphanumeric() || c == '_')
+}
+
+/// Whether a key has to be quoted in brackets to be read back from dot notation
+fn needs_quotes(key: &str) -> bool {
+    // Keys of only digits would be read back as array indices
+    key.bytes().all(|b| b.is_ascii_digit()) || key.chars().any(|c| matches!(c, '.' | '[' | ']' | '"' | '\\') || c.is_whitespace() || c.is_control())
+}
+
+impl core::fmt::Display for Path {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        write!(f, "$")?;
+        for i in &self.segments {
+            match i {
+                Index::Object(key) if needs_quotes(key) => write!(f, "[\"{}\"]", escape(key))?,
+                i => write!(f, ".{}", i)?
+            }
+        }
+        Ok(())
+    }
+}
+
+impl From<Vec<Index>> for Path {
+    fn from(segments: Vec<Index>) -> Self {
+        Path { segments }
+    }
+}
+
+impl From<Path> for Vec<Index> {
+    fn from(path: Path) -> Self {
+        path.segments
+    }
+}
+
+impl AsRef<[Index]> for Path {
+    fn as_ref(&self) -> &[Index] {
+        &self.segments
+    }
+}
+
+impl core::ops::Index<usize> for Path {
+    type Output = Index;
+
+    fn index(&self, i: usize) -> &Index {
+        &self.segments[i]
+    }
+}
+
+impl FromIterator<Index> for Path {
+    fn from_iter<T: IntoIterator<Item = Index>>(iter: T) -> Self {
+        Path { segments: iter.into_iter().collect() }
+    }
+}
+
+impl Extend<Index> for Path {
+    fn extend<T: IntoIterator<Item = Index>>(&mut self, iter: T) {
+        self.segments.extend(iter)
+    }
+}
+
+impl IntoIterator for Path {
+    type Item = Index;
+    type IntoIter = alloc::vec::IntoIter<Index>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.segments.into_iter()
+    }
+}
+
+impl<'a> IntoIterator for &'a Path {
+    type Item = &'a Index;
+    type IntoIter = core::slice::Iter<'a, Index>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.segments.iter()
+    }
+}
+
+impl PartialEq<Vec<Index>> for Path {
+    fn eq(&self, other: &Vec<Index>) -> bool {
+        self.segments == *other
+    }
+}
+
+impl PartialEq<[Index]> for Path {
+    fn eq(&self, other: &[Index]) -> bool {
+        self.segments == other
+    }
+}
+
+impl<const N: usize> PartialEq<[Index; N]> for Path {
+    fn eq(&self, other: &[Index; N]) -> bool {
+        self.segments == other
+    }
+}
+
+#[cfg(feature = "serde")]
+impl serde::Serialize for Index {
+    fn serialize<S: serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        match self {
+            Index::Array(i) => serializer.serialize_u64(*i as u64),
+            Index::Object(key) => serializer.serialize_str(key)
+        }
+    }
+}
+
+#[cfg(feature = "serde")]
+impl<'de> serde::Deserialize<'de> for Index {
+    fn deserialize<D: serde::Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        struct IndexVisitor;
+
+        impl serde::de::Visitor<'_> for IndexVisitor {
+            type Value = Index;
+
+            fn expecting(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+                write!(f, "an array index or an object key")
+            }
+
+            fn visit_u64<E: serde::de::Error>(self, i: u64) -> Result<Index, E> {
+                usize::try_from(i).map(Index::Array).map_err(|_| E::invalid_value(serde::de::Unexpected::Unsigned(i), &self))
+            }
+
+            fn visit_i64<E: serde::de::Error>(self, i: i64) -> Result<Index, E> {
+                usize::try_from(i).map(Index::Array).map_err(|_| E::invalid_value(serde::de::Unexpected::Signed(i), &self))
+            }
+
+            fn visit_str<E: serde::de::Error>(self, key: &str) -> Result<Index, E> {
+                Ok(Index::Object(key.to_owned()))
+            }
+
+            fn visit_string<E: serde::de::Error>(self, key: String) -> Result<Index, E> {
+                Ok(Index::Object(key))
+            }
+        }
+
+        deserializer.deserialize_any(IndexVisitor)
+    }
+}
+
+#[cfg(feature = "serde")]
+impl serde::Serialize for Path {
+    fn serialize<S: serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.collect_seq(&self.segments)
+    }
+}
+
+#[cfg(feature = "serde")]
+impl<'de> serde::Deserialize<'de> for Path {
+    fn deserialize<D: serde::Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        Vec::<Index>::deserialize(deserializer).map(Path::from)
+    }
+}
+
+/// Way from one value in a document to another, made with [`Path::relative_to`].
+///
+/// Formatting it with [`Display`](core::fmt::Display) gives a
+/// [Relative JSON Pointer](https://datatracker.ietf.org/doc/html/draft-bhutton-relative-json-pointer-00) such as `1/b/0`.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct RelativePath {
+    /// Number of levels to go up to the common ancestor
+    pub up: usize,
+    /// Path from the common ancestor down to the target
+    pub down: Path
+}
+
+impl RelativePath {
+    /// Returns `true` if the target is the start itself or below it.
+    pub fn is_descendant(&self) -> bool {
+        self.up == 0
+    }
+
+    /// Returns `true` if the target is the start itself or above it.
+    pub fn is_ancestor(&self) -> bool {
+        self.down.is_root()
+    }
+
+    /// Returns `true` if neither value contains the other, so they are in disjoint subtrees.
+    pub fn is_disjoint(&self) -> bool {
+        !self.is_descendant() && !self.is_ancestor()
+    }
+}
+
+impl core::fmt::Display for RelativePath {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        write!(f, "{}{}", self.up, self.down.to_pointer())
+    }
+}
+
+/// Error returned when parsing a [`Path`] from a string fails.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ParsePathError {
+    /// The string starts with neither `$` nor `/`.
+    MissingRoot,
+    /// A JSON Pointer contains a `~` not followed by `0` or `1`, or a quoted key an invalid `\\` escape,
+    /// at the contained byte offset.
+    InvalidEscape(usize),
+    /// A segment of dot notation is neither `.key`, `["key"]` nor `[index]`, at the contained byte offset.
+    InvalidSegment(usize)
+}
+
+impl core::fmt::Display for ParsePathError {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        match self {
+            ParsePathError::MissingRoot => write!(f, "path must start with `$` or `/`"),
+            ParsePathError::InvalidEscape(i) => write!(f, "invalid escape sequence at {}", i),
+            ParsePathError::InvalidSegment(i) => write!(f, "invalid path segment at {}", i)
+        }
+    }
+}
+
+#[cfg(feature = "std")]
+impl std::error::Error for ParsePathError {}
+
+fn parse_segment(segment: String) -> Index {
+    if !segment.is_empty() && segment.bytes().all(|b| b.is_ascii_digit()) {
+        if let Ok(i) = segment.parse() {
+            return Index::Array(i);
+        }
+    }
+    Index::Object(segment)
+}
+
+fn unescape_pointer(segment: &str, offset: usize) -> Result<String, ParsePathError> {
+    let mut key = String::with_capacity(segment.len());
+    let mut chars = segment.char_indices();
+    while let Some((i, c)) = chars.next() {
+        if c == '~' {
+            match chars.next() {
+                Some((_, '0')) => key.push('~'),
+                Some((_, '1')) => key.push('/'),
+                _ => return Err(ParsePathError::InvalidEscape(offset + i))
+            }
+        } else {
+            key.push(c);
+        }
+    }
+    Ok(key)
+}
+
+/// Parses the segments of dot notation after the `$`, which starts at `offset`
+fn parse_dotted(s: &str, offset: usize) -> Result<Path, ParsePathError> {
+    let mut path = Path::new();
+    let mut i = 0;
+    while i < s.len() {
+        let rest = &s[i..];
+        let invalid = ParsePathError::InvalidSegment(offset + i);
+        if let Some(segment) = rest.strip_prefix('.') {
+            let len = segment.find(['.', '[']).unwrap_or(segment.len());
+            path.push(parse_segment(segment[..len].to_owned()));
+            i += 1 + len;
+        } else if let Some(quoted) = rest.strip_prefix("[\"") {
+            // The key ends at the first quote that isn't escaped
+            let mut escaped = false;
+            let end = quoted.bytes().position(|b| {
+                let end = b == b'"' && !escaped;
+                escaped = b == b'\\' && !escaped;
+                end
+            });
+            let end = end.filter(|end| quoted[end + 1..].starts_with(']')).ok_or(invalid)?;
+            let key = unescape(&quoted[..end]).map_err(|j| ParsePathError::InvalidEscape(offset + i + 2 + j))?;
+            path.push(Index::Object(key));
+            i += end + 4;
+        } else if let Some(index) = rest.strip_prefix('[') {
+            let len = index.find(']').ok_or_else(|| invalid.clone())?;
+            match parse_segment(index[..len].to_owned()) {
+                Index::Array(index) => path.push(index),
+                Index::Object(_) => return Err(invalid)
+            }
+            i += len + 2;
+        } else if i == 0 {
+            return Err(ParsePathError::MissingRoot);
+        } else {
+            return Err(invalid);
+        }
+    }
+    Ok(path)
+}
+
+impl core::str::FromStr for Path {
+    type Err = ParsePathError;
+
+    /// Parses either dot notation (`$.1.fields.2`) or a JSON Pointer (`/1/fields/2`).
+    /// The empty string is the JSON Pointer to the root.
+    ///
+    /// Dot notation can also hold array indices in brackets and quoted keys, escaped like json strings,
+    /// as written by [`Display`](core::fmt::Display) and [`Path::to_bracket`], such as `$[1]["file.name"]`.
+    /// Other segments made up only of digits are parsed as [`Index::Array`],
+    /// since neither notation tells array indices and object keys apart there.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use jsonposition::{Path, Index};
+    ///
+    /// let dotted: Path = "$.1.fields.2".parse().unwrap();
+    /// let pointer: Path = "/1/fields/2".parse().unwrap();
+    ///
+    /// assert_eq!(dotted, pointer);
+    /// assert_eq!(dotted, [Index::Array(1), Index::Object("fields".to_string()), Index::Array(2)]);
+    /// ```
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        if s.is_empty() {
+            return Ok(Path::new());
+        }
+
+        if let Some(rest) = s.strip_prefix('$') {
+            parse_dotted(rest, 1)
+        } else if let Some(rest) = s.strip_prefix('/') {
+            let mut path = Path::new();
+            let mut offset = 1;
+            for segment in rest.split('/') {
+                path.push(parse_segment(unescape_pointer(segment, offset)?));
+                offset += segment.len() + 1;
+            }
+            Ok(path)
+        } else {
+            Err(ParsePathError::MissingRoot)
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn navigation() {
+        let path: Path = vec![Index::Array(1), Index::Object(String::from("fields")), Index::Array(2)].into();
+        let parent = path.parent().unwrap();
+
+        assert!(path.starts_with(&parent));
+        assert!(!parent.starts_with(&path));
+        assert_eq!(parent.join(&vec![Index::Array(2)].into()), path);
+        assert_eq!(path.iter().count(), 3);
+
+        // Tests root
+        assert!(Path::new().is_root());
+        assert_eq!(Path::new().parent(), None);
+        assert_eq!(Path::new().to_string(), "$");
+        assert_eq!(Path::new().to_pointer(), "");
+    }
+
+    #[test]
+    fn relative() {
+        let path = |s: &str| s.parse::<Path>().unwrap();
+
+        let sibling = path("$.a.0").relative_to(&path("$.a.1.b"));
+        assert_eq!((sibling.up, sibling.to_string()), (1, "1/1/b".to_string()));
+        assert!(sibling.is_disjoint());
+
+        let child = path("$.a").relative_to(&path("$.a.1"));
+        assert!(child.is_descendant() && !child.is_ancestor());
+        assert_eq!(child.to_string(), "0/1");
+
+        let parent = path("$.a.1").relative_to(&Path::new());
+        assert!(parent.is_ancestor() && !parent.is_disjoint());
+        assert_eq!(parent.to_string(), "2");
+
+        assert_eq!(path("$.a").relative_to(&path("$.a")).to_string(), "0");
+    }
+
+    #[test]
+    fn pointer() {
+        let path: Path = vec![Index::Array(1), Index::Object(String::from("fields")), Index::Array(2)].into();
+        assert_eq!(path.to_pointer(), "/1/fields/2");
+
+        // Tests escaping, `~` must be escaped first so `~1` isn't produced from `/`
+        let path: Path = vec![Index::Object(String::from("~/")), Index::Object(String::new())].into();
+        assert_eq!(path.to_pointer(), "/~0~1/");
+    }
+
+    #[test]
+    fn bracket() {
+        let path: Path = vec![Index::Array(1), Index::Object(String::from("fields")), Index::Array(2)].into();
+        assert_eq!(path.to_bracket(), r#"$[1]["fields"][2]"#);
+        assert_eq!(Path::new().to_bracket(), "$");
+
+        // Tests keys that dot notation can't express
+        let path: Path = vec![Index::Object(String::from("a.b c")), Index::Object(String::from("\"]\\\n")), Index::Object(String::new())].into();
+        assert_eq!(path.to_bracket(), r#"$["a.b c"]["\"]\\\n"][""]"#);
+    }
+
+    #[test]
+    fn mixed() {
+        let path: Path = "$.config.items.0.name".parse().unwrap();
+        assert_eq!(path.to_mixed(), "$.config.items[0].name");
+        assert_eq!(Path::from(vec![Index::Array(1), Index::Object(String::from("fields"))]).to_mixed(), "$[1].fields");
+        assert_eq!(Path::new().to_mixed(), "$");
+
+        // Tests keys that aren't identifiers
+        let path: Path = vec![Index::Object(String::from("_a1")), Index::Object(String::from("1a")), Index::Object(String::from("a-b")), Index::Object(String::new())].into();
+        assert_eq!(path.to_mixed(), r#"$._a1["1a"]["a-b"][""]"#);
+        assert_eq!(Path::from(vec![Index::Object(String::from("é\""))]).to_mixed(), r#"$["é\""]"#);
+    }
+
+    #[test]
+    fn jq() {
+        assert_eq!("$.1.fields.2".parse::<Path>().unwrap().to_jq(), ".[1].fields[2]");
+        assert_eq!(Path::new().to_jq(), ".");
+
+        // Tests quoted keys at the start and after other segments
+        let path: Path = vec![Index::Object(String::from("a b")), Index::Object(String::from("c")), Index::Object(String::from("\"0"))].into();
+        assert_eq!(path.to_jq(), r#".["a b"].c["\"0"]"#);
+    }
+
+    #[test]
+    fn js() {
+        assert_eq!("$.1.fields.2".parse::<Path>().unwrap().to_js("obj"), "obj[1].fields[2]");
+        assert_eq!(Path::new().to_js("obj"), "obj");
+
+        let path: Path = vec![Index::Object(String::from("$_a1")), Index::Object(String::from("1a")), Index::Object(String::from("é")), Index::Object(String::from("a\"b"))].into();
+        assert_eq!(path.to_js("obj"), r#"obj.$_a1["1a"]["é"]["a\"b"]"#);
+    }
+
+    #[test]
+    fn python() {
+        assert_eq!("$.1.fields.2".parse::<Path>().unwrap().to_python("data"), r#"data[1]["fields"][2]"#);
+        assert_eq!(Path::new().to_python("data"), "data");
+
+        let path: Path = vec![Index::Object(String::from("a.b")), Index::Object(String::from("\"\\\n"))].into();
+        assert_eq!(path.to_python("df"), r#"df["a.b"]["\"\\\n"]"#);
+    }
+
+    #[test]
+    fn parse() {
+        let path: Path = vec![Index::Array(1), Index::Object(String::from("fields")), Index::Array(2)].into();
+        assert_eq!("$.1.fields.2".parse::<Path>().unwrap(), path);
+        assert_eq!("/1/fields/2".parse::<Path>().unwrap(), path);
+
+        // Tests round trip of escaped pointer
+        let escaped: Path = vec![Index::Object(String::from("~/")), Index::Object(String::new())].into();
+        assert_eq!(escaped.to_pointer().parse::<Path>().unwrap(), escaped);
+
+        // Tests root
+        assert!("$".parse::<Path>().unwrap().is_root());
+        assert!("".parse::<Path>().unwrap().is_root());
+
+        // Tests errors
+        assert_eq!("fields".parse::<Path>(), Err(ParsePathError::MissingRoot));
+        assert_eq!("$fields".parse::<Path>(), Err(ParsePathError::MissingRoot));
+        assert_eq!("/a/b~2".parse::<Path>(), Err(ParsePathError::InvalidEscape(4)));
+    }
+
+    #[test]
+    fn quoted_keys() {
+        let key = |key: &str| Path::from(vec![Index::Object(String::from("x")), Index::Object(String::from(key))]);
+
+        // Tests keys that would be ambiguous in dot notation
+        assert_eq!(key("a.b").to_string(), r#"$.x["a.b"]"#);
+        assert_eq!(key("a\"b").to_string(), r#"$.x["a\"b"]"#);
+        assert_eq!(key("[0]").to_string(), r#"$.x["[0]"]"#);
+        assert_eq!(key("a b").to_string(), r#"$.x["a b"]"#);
+        assert_eq!(key("\t\\").to_string(), r#"$.x["\t\\"]"#);
+        assert_eq!(key("").to_string(), r#"$.x[""]"#);
+        assert_eq!(key("12").to_string(), r#"$.x["12"]"#);
+        assert_eq!(key("é-1_$").to_string(), "$.x.é-1_$");
+
+        // Tests every form round trips
+        for k in ["a.b", "a\"b", "[0]", "a b", "\t\\", "", "é-1_$", "]\"]", "\u{1}", "0", "12"] {
+            let path = key(k);
+            assert_eq!(path.to_string().parse::<Path>().unwrap(), path, "{}", k);
+            assert_eq!(path.to_bracket().parse::<Path>().unwrap(), path, "{}", k);
+        }
+        assert_eq!(r#"$[1]["0"].a["é😀"]"#.parse::<Path>().unwrap(), [Index::Array(1), Index::from("0"), Index::from("a"), Index::from("é😀")]);
+        assert_eq!("$.a[2][3]".parse::<Path>().unwrap(), [Index::from("a"), Index::Array(2), Index::Array(3)]);
+
+        // Tests errors
+        assert_eq!(r#"$["a"#.parse::<Path>(), Err(ParsePathError::InvalidSegment(1)));
+        assert_eq!(r#"$.a["b"c"]"#.parse::<Path>(), Err(ParsePathError::InvalidSegment(3)));
+        assert_eq!(r#"$["a"]b"#.parse::<Path>(), Err(ParsePathError::InvalidSegment(6)));
+        assert_eq!("$[a]".parse::<Path>(), Err(ParsePathError::InvalidSegment(1)));
+        assert_eq!("$[1".parse::<Path>(), Err(ParsePathError::InvalidSegment(1)));
+        assert_eq!(r#"$["\x"]"#.parse::<Path>(), Err(ParsePathError::InvalidEscape(3)));
+    }
+
+    #[test]
+    fn ordering() {
+        let path = |s: &str| s.parse::<Path>().unwrap();
+        let mut paths = vec![path("$.b"), path("$.a.1"), path("$.0"), path("$.a"), path("$.a.0.c"), Path::new()];
+        paths.sort();
+        assert_eq!(paths, [Path::new(), path("$.0"), path("$.a"), path("$.a.0.c"), path("$.a.1"), path("$.b")]);
+
+        let counts: alloc::collections::BTreeMap<Path, usize> = paths.into_iter().map(|path| (path, 1)).collect();
+        assert_eq!(counts.len(), 6);
+    }
+
+    #[cfg(feature = "serde")]
+    #[test]
+    fn serde() {
+        let path: Path = vec![Index::Array(1), Index::Object(String::from("fields")), Index::Object(String::from("2"))].into();
+        let json = serde_json::to_string(&path).unwrap();
+        assert_eq!(json, r#"[1,"fields","2"]"#);
+        assert_eq!(serde_json::from_str::<Path>(&json).unwrap(), path);
+
+        assert_eq!(serde_json::from_str::<Index>("7").unwrap(), Index::Array(7));
+        assert!(serde_json::from_str::<Index>("-1").is_err());
+        assert!(serde_json::from_str::<Path>(r#"[1, null]"#).is_err());
+    }
+}
@@ -0,0 +1,91 @@
+use core::ops::Range;
+
+use serde_path_to_error::Segment;
+
+use crate::error::Error;
+use crate::path::{Index, Path};
+use crate::span::span_of;
+
+/// Converts a segment of a [`serde_path_to_error::Path`].
+/// An enum variant is the key it is tagged with in json.
+///
+/// Fails with the segment itself if it is [`Segment::Unknown`], where the deserializer couldn't tell what was entered.
+impl<'a> TryFrom<&'a Segment> for Index {
+    type Error = &'a Segment;
+
+    fn try_from(segment: &'a Segment) -> Result<Self, Self::Error> {
+        match segment {
+            Segment::Seq { index } => Ok(Index::Array(*index)),
+            Segment::Map { key } | Segment::Enum { variant: key } => Ok(Index::Object(key.clone())),
+            Segment::Unknown => Err(segment)
+        }
+    }
+}
+
+/// Converts the path reported by [`serde_path_to_error`].
+/// The path stops before the first [`Segment::Unknown`], giving the deepest value that is known.
+///
+/// # Examples
+///
+/// ```
+/// use std::collections::HashMap;
+/// use jsonposition::Path;
+///
+/// let json = r#"{"users": [{"age": 30}, {"age": "x"}]}"#;
+///
+/// let deserializer = &mut serde_json::Deserializer::from_str(json);
+/// let err = serde_path_to_error::deserialize::<_, HashMap<String, Vec<HashMap<String, u32>>>>(deserializer).unwrap_err();
+/// assert_eq!(Path::from(err.path()).to_string(), "$.users.1.age");
+/// ```
+impl From<&serde_path_to_error::Path> for Path {
+    fn from(path: &serde_path_to_error::Path) -> Self {
+        path.iter().map_while(|segment| Index::try_from(segment).ok()).collect()
+    }
+}
+
+/// Finds the span in a raw json string of the value at a [`serde_path_to_error::Path`],
+/// such as the value a deserialization error was found in.
+///
+/// The path is converted with [`Path::from`], so it stops before any segment the deserializer couldn't track.
+/// Returns `None` if the document has no value at the path.
+///
+/// # Examples
+///
+/// ```
+/// use std::collections::HashMap;
+/// use jsonposition::span_of_serde_path;
+///
+/// let json = r#"{"users": [{"age": 30}, {"age": "x"}]}"#;
+///
+/// let deserializer = &mut serde_json::Deserializer::from_str(json);
+/// let err = serde_path_to_error::deserialize::<_, HashMap<String, Vec<HashMap<String, u32>>>>(deserializer).unwrap_err();
+///
+/// let span = span_of_serde_path(json, err.path()).expect("Invalid JSON").unwrap();
+/// assert_eq!(&json[span], r#""x""#);
+/// ```
+///
+/// # Errors
+///
+/// Returns an [`Error`](crate::Error) if the input json is invalid.
+pub fn span_of_serde_path(text: &str, path: &serde_path_to_error::Path) -> Result<Option<Range<usize>>, Error> {
+    span_of(text, &Path::from(path))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::collections::BTreeMap;
+
+    #[test]
+    fn paths() {
+        let json = r#"[{"a": {"b": [1, 2]}}, {"c": {"d": [3, "e"]}}]"#;
+
+        let deserializer = &mut serde_json::Deserializer::from_str(json);
+        let err = serde_path_to_error::deserialize::<_, Vec<BTreeMap<String, BTreeMap<String, Vec<u8>>>>>(deserializer).unwrap_err();
+        assert_eq!(Path::from(err.path()).to_string(), "$.1.c.d.1");
+        assert_eq!(span_of_serde_path(json, err.path()).unwrap().map(|span| &json[span]), Some(r#""e""#));
+
+        assert_eq!(Index::try_from(&Segment::Enum { variant: "V".to_string() }).ok(), Some(Index::Object("V".to_string())));
+        assert!(matches!(Index::try_from(&Segment::Unknown), Err(Segment::Unknown)));
+    }
+}
@@ -0,0 +1,162 @@
+use core::ops::Range;
+
+use pyo3::exceptions::PyValueError;
+use pyo3::prelude::*;
+use pyo3::types::PyList;
+
+use crate::lines::OffsetUnit;
+use crate::locate::Kind;
+use crate::path::{Index, Path};
+
+// Offsets and spans are in chars, the same as Python string indices.
+
+fn value_error(err: crate::Error) -> PyErr {
+    PyValueError::new_err(err.to_string())
+}
+
+/// Converts a path to a list of ints and strs
+fn segments<'py>(py: Python<'py>, path: &Path) -> PyResult<Bound<'py, PyList>> {
+    let list = PyList::empty(py);
+    for index in path.iter() {
+        match index {
+            Index::Array(i) => list.append(i)?,
+            Index::Object(key) => list.append(key)?
+        }
+    }
+    Ok(list)
+}
+
+/// Converts a byte span on char boundaries to chars
+fn char_span(text: &str, span: &Range<usize>) -> (usize, usize) {
+    let start = text[..span.start].chars().count();
+    (start, start + text[span.clone()].chars().count())
+}
+
+/// Constructs the path to a char offset in a raw json string, as a list of ints and strs
+#[pyfunction]
+fn path<'py>(py: Python<'py>, json: &str, offset: usize) -> PyResult<Bound<'py, PyList>> {
+    segments(py, &crate::path(json, offset).map_err(value_error)?)
+}
+
+/// Constructs the dot path to a char offset in a raw json string, such as `$.2.a`
+#[pyfunction]
+fn dot_path(json: &str, offset: usize) -> PyResult<String> {
+    crate::dot_path(json, offset).map_err(value_error)
+}
+
+/// Finds the innermost value or key containing a char offset in a raw json string,
+/// `None` if the offset is outside of the root value
+#[pyfunction]
+fn locate(json: &str, offset: usize) -> PyResult<Option<Location>> {
+    let byte_offset = OffsetUnit::Chars.to_byte_offset(json, offset);
+    Ok(crate::locate(json, byte_offset).map_err(value_error)?.map(|location| Location {
+        span: char_span(json, &location.span),
+        path: location.path,
+        kind: location.kind
+    }))
+}
+
+/// Innermost value or key containing an offset, as returned by `locate`
+#[pyclass(frozen, module = "jsonposition")]
+struct Location {
+    path: Path,
+    span: (usize, usize),
+    kind: Kind
+}
+
+#[pymethods]
+impl Location {
+    /// Path to the value, or to the value of the member for a key
+    #[getter]
+    fn path<'py>(&self, py: Python<'py>) -> PyResult<Bound<'py, PyList>> {
+        segments(py, &self.path)
+    }
+
+    /// Dot path to the value, or to the value of the member for a key
+    #[getter]
+    fn dot_path(&self) -> String {
+        self.path.to_string()
+    }
+
+    /// Start and end char offsets of the value or key
+    #[getter]
+    fn span(&self) -> (usize, usize) {
+        self.span
+    }
+
+    /// Kind of the value, such as `number` or `object key`
+    #[getter]
+    fn kind(&self) -> String {
+        self.kind.to_string()
+    }
+
+    /// Whether the offset is within an object key rather than a value
+    #[getter]
+    fn in_key(&self) -> bool {
+        self.kind == Kind::ObjectKey
+    }
+
+    fn __repr__(&self) -> String {
+        format!("Location(path='{}', span={:?}, kind='{}')", self.path, self.span, self.kind)
+    }
+}
+
+/// Index over a json document for looking up many char offsets quickly
+#[pyclass(frozen, name = "DocumentIndex", module = "jsonposition")]
+struct PyDocumentIndex {
+    text: String,
+    index: crate::DocumentIndex
+}
+
+#[pymethods]
+impl PyDocumentIndex {
+    #[new]
+    fn new(json: String) -> PyResult<Self> {
+        let index = crate::DocumentIndex::new(&json).map_err(value_error)?;
+        Ok(PyDocumentIndex { text: json, index })
+    }
+
+    /// Path to a char offset, as a list of ints and strs
+    fn path_at<'py>(&self, py: Python<'py>, offset: usize) -> PyResult<Bound<'py, PyList>> {
+        segments(py, &self.index.path_at(OffsetUnit::Chars.to_byte_offset(&self.text, offset)))
+    }
+
+    /// Dot path to a char offset, such as `$.2.a`
+    fn dot_path_at(&self, offset: usize) -> String {
+        self.index.path_at(OffsetUnit::Chars.to_byte_offset(&self.text, offset)).to_string()
+    }
+
+    /// Dot path, char span and kind of every value or key containing a char offset, outermost first
+    fn nodes_containing(&self, offset: usize) -> Vec<(String, (usize, usize), String)> {
+        self.index.nodes_containing(OffsetUnit::Chars.to_byte_offset(&self.text, offset))
+            .into_iter()
+            .map(|(path, span, kind)| (path.to_string(), char_span(&self.text, &span), kind.to_string()))
+            .collect()
+    }
+}
+
+/// Python module, imported as `jsonposition`
+#[pymodule]
+#[pyo3(name = "jsonposition")]
+fn python_module(m: &Bound<'_, PyModule>) -> PyResult<()> {
+    m.add_function(wrap_pyfunction!(path, m)?)?;
+    m.add_function(wrap_pyfunction!(dot_path, m)?)?;
+    m.add_function(wrap_pyfunction!(locate, m)?)?;
+    m.add_class::<Location>()?;
+    m.add_class::<PyDocumentIndex>()?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn spans() {
+        let json = r#"{"é": [null, 9]}"#;
+        let nine = json.find('9').unwrap();
+
+        assert_eq!(char_span(json, &(nine..nine + 1)), (nine - 1, nine));
+        assert_eq!(char_span(json, &(1..json.find(':').unwrap())), (1, 4));
+    }
+}
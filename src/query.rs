@@ -0,0 +1,357 @@
+use core::ops::Range;
+use alloc::string::String;
+use alloc::vec::Vec;
+
+use crate::error::Error;
+use crate::path::{Index, Path};
+use crate::scan::{Event, Walker};
+
+/// Error returned by [`query`]
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum QueryError {
+    /// The query isn't a supported JSONPath expression, failing at the contained byte offset into it.
+    InvalidQuery(usize),
+    /// The input json is invalid.
+    Json(Error)
+}
+
+impl core::fmt::Display for QueryError {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        match self {
+            QueryError::InvalidQuery(i) => write!(f, "invalid query at {}", i),
+            QueryError::Json(err) => write!(f, "{}", err)
+        }
+    }
+}
+
+#[cfg(feature = "std")]
+impl std::error::Error for QueryError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            QueryError::InvalidQuery(_) => None,
+            QueryError::Json(err) => Some(err)
+        }
+    }
+}
+
+impl From<Error> for QueryError {
+    fn from(err: Error) -> Self {
+        QueryError::Json(err)
+    }
+}
+
+impl From<ParsePatternError> for QueryError {
+    fn from(err: ParsePatternError) -> Self {
+        QueryError::InvalidQuery(err.offset)
+    }
+}
+
+/// Error returned when parsing a [`PathPattern`] from a string fails.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ParsePatternError {
+    /// Byte offset into the pattern where parsing failed
+    pub offset: usize
+}
+
+impl core::fmt::Display for ParsePatternError {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        write!(f, "invalid pattern at {}", self.offset)
+    }
+}
+
+#[cfg(feature = "std")]
+impl std::error::Error for ParsePatternError {}
+
+/// What a step of a query accepts
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum Selector {
+    /// Object key, or an array index written out in digits the way a dot path does
+    Name(String),
+    Index(usize),
+    Wildcard
+}
+
+impl Selector {
+    fn matches(&self, index: &Index) -> bool {
+        match (self, index) {
+            (Selector::Wildcard, _) => true,
+            (Selector::Index(i), Index::Array(j)) => i == j,
+            (Selector::Name(name), Index::Object(key)) => name == key,
+            (Selector::Name(name), Index::Array(i)) => name.parse() == Ok(*i),
+            _ => false
+        }
+    }
+}
+
+/// Step of a query, selecting from the children of the values before it or from all of their descendants
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum Step {
+    Child(Selector),
+    Descendant(Selector)
+}
+
+/// Pattern matching paths, with wildcards and recursive descent, parsed from the JSONPath syntax [`query`] takes.
+///
+/// A pattern can filter the values of [`all_paths`](crate::all_paths) or the path of a [`StreamScanner`](crate::StreamScanner)
+/// as it goes, and [`paths_matching`] finds every value it matches in a single scan.
+///
+/// # Examples
+///
+/// ```
+/// use jsonposition::{all_paths, PathPattern};
+///
+/// let pattern: PathPattern = "$.items.*.id".parse().unwrap();
+/// assert!(pattern.matches(&"$.items.3.id".parse().unwrap()));
+/// assert!(!pattern.matches(&"$.items.3.name".parse().unwrap()));
+///
+/// let names: PathPattern = "$..name".parse().unwrap();
+/// let json = r#"{"name": "a", "items": [{"name": "b"}]}"#;
+/// let found: Vec<&str> = all_paths(json).expect("Invalid JSON").filter(|(path, ..)| names.matches(path)).map(|(_, span, _)| &json[span]).collect();
+/// assert_eq!(found, [r#""a""#, r#""b""#]);
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PathPattern {
+    steps: Vec<Step>
+}
+
+impl PathPattern {
+    /// Returns `true` if the pattern selects the value at `path`.
+    pub fn matches(&self, path: &Path) -> bool {
+        matches_steps(&self.steps, path.as_slice())
+    }
+}
+
+impl core::str::FromStr for PathPattern {
+    type Err = ParsePatternError;
+
+    fn from_str(expression: &str) -> Result<Self, Self::Err> {
+        let bytes = expression.as_bytes();
+        if bytes.first() != Some(&b'$') {
+            return Err(ParsePatternError { offset: 0 });
+        }
+
+        let mut steps = Vec::new();
+        let mut i = 1;
+        while i < bytes.len() {
+            let descendant = bytes[i..].starts_with(b"..");
+            let selector = if descendant || bytes[i] == b'.' {
+                i += if descendant { 2 } else { 1 };
+                match bytes.get(i) {
+                    Some(b'[') if descendant => parse_bracket(expression, &mut i)?,
+                    Some(b'*') => {
+                        i += 1;
+                        Selector::Wildcard
+                    }
+                    _ => {
+                        let end = bytes[i..].iter().position(|b| matches!(b, b'.' | b'[')).map_or(bytes.len(), |n| i + n);
+                        if end == i {
+                            return Err(ParsePatternError { offset: i });
+                        }
+                        let name = &expression[i..end];
+                        i = end;
+                        Selector::Name(String::from(name))
+                    }
+                }
+            } else if bytes[i] == b'[' {
+                parse_bracket(expression, &mut i)?
+            } else {
+                return Err(ParsePatternError { offset: i });
+            };
+
+            steps.push(if descendant { Step::Descendant(selector) } else { Step::Child(selector) });
+        }
+
+        Ok(PathPattern { steps })
+    }
+}
+
+fn matches_steps(steps: &[Step], path: &[Index]) -> bool {
+    match steps.split_first() {
+        None => path.is_empty(),
+        Some((Step::Child(selector), rest)) => match path.split_first() {
+            Some((index, path)) => selector.matches(index) && matches_steps(rest, path),
+            None => false
+        },
+        Some((Step::Descendant(selector), rest)) => {
+            (0..path.len()).any(|skip| selector.matches(&path[skip]) && matches_steps(rest, &path[skip + 1..]))
+        }
+    }
+}
+
+/// Parses `[*]`, `[0]`, `['key']` or `["key"]` starting at the `[` at `i`, leaving `i` after the `]`
+fn parse_bracket(expression: &str, i: &mut usize) -> Result<Selector, ParsePatternError> {
+    let bytes = expression.as_bytes();
+    let start = *i + 1;
+
+    let (selector, end) = match bytes.get(start) {
+        Some(b'*') => (Selector::Wildcard, start + 1),
+        Some(quote @ (b'\'' | b'"')) => {
+            let mut name = String::new();
+            let mut chars = expression[start + 1..].char_indices();
+            loop {
+                match chars.next() {
+                    Some((n, c)) if c == char::from(*quote) => break (Selector::Name(name), start + 2 + n),
+                    Some((_, '\\')) => match chars.next() {
+                        Some((_, c)) => name.push(c),
+                        None => return Err(ParsePatternError { offset: bytes.len() })
+                    },
+                    Some((_, c)) => name.push(c),
+                    None => return Err(ParsePatternError { offset: bytes.len() })
+                }
+            }
+        }
+        _ => {
+            let end = bytes[start..].iter().position(|b| !b.is_ascii_digit()).map_or(bytes.len(), |n| start + n);
+            match expression[start..end].parse() {
+                Ok(index) => (Selector::Index(index), end),
+                Err(_) => return Err(ParsePatternError { offset: start })
+            }
+        }
+    };
+
+    if bytes.get(end) != Some(&b']') {
+        return Err(ParsePatternError { offset: end });
+    }
+    *i = end + 1;
+    Ok(selector)
+}
+
+/// Evaluates a JSONPath query against a raw json string in a single scan, without building a tree,
+/// returning the path and span of every value it selects in the order they start.
+///
+/// Supports the root `$`, child names as `.name` or `['name']`, array indices as `[0]`,
+/// wildcards as `.*` or `[*]` and recursive descent as `..name`, `..*` or `..[0]`.
+/// An index written as `.0`, the way a dot path writes it, also selects an array element.
+///
+/// # Examples
+///
+/// ```
+/// use jsonposition::query;
+///
+/// let json = r#"{"store": {"book": [{"title": "a"}, {"title": "b", "isbn": "c"}]}}"#;
+///
+/// let found = query(json, "$.store.book[*].title").unwrap();
+/// assert_eq!(found.iter().map(|(path, _)| path.to_string()).collect::<Vec<_>>(), ["$.store.book.0.title", "$.store.book.1.title"]);
+///
+/// let (_, span) = &query(json, "$..isbn").unwrap()[0];
+/// assert_eq!(&json[span.clone()], r#""c""#);
+/// ```
+///
+/// # Errors
+///
+/// Returns [`QueryError::InvalidQuery`] if the query can't be parsed
+/// and [`QueryError::Json`] if the input json is invalid.
+pub fn query(text: &str, expression: &str) -> Result<Vec<(Path, Range<usize>)>, QueryError> {
+    let pattern: PathPattern = expression.parse()?;
+    Ok(paths_matching(text, &pattern)?)
+}
+
+/// Finds the path and span of every value a [`PathPattern`] matches in a raw json string in a single scan,
+/// in the order they start. The same as [`query`] with a pattern parsed ahead of time.
+///
+/// # Errors
+///
+/// Returns an [`Error`](crate::Error) if the input json is invalid.
+pub fn paths_matching(text: &str, pattern: &PathPattern) -> Result<Vec<(Path, Range<usize>)>, Error> {
+    let mut walker = Walker::new(text);
+    let mut found: Vec<(Path, Range<usize>)> = Vec::new();
+    // Position in `found` of each open container that was selected, so its span can be completed when it ends
+    let mut open: Vec<Option<usize>> = Vec::new();
+
+    while let Some(event) = walker.next() {
+        match event? {
+            Event::StartObject(start) | Event::StartArray(start) => {
+                let selected = pattern.matches(walker.path());
+                open.push(selected.then_some(found.len()));
+                if selected {
+                    found.push((walker.path().clone(), start..start));
+                }
+            }
+            Event::EndObject(span) | Event::EndArray(span) => {
+                if let Some(Some(i)) = open.pop() {
+                    found[i].1 = span;
+                }
+            }
+            Event::Scalar(span) => {
+                if pattern.matches(walker.path()) {
+                    found.push((walker.path().clone(), span));
+                }
+            }
+            Event::Key(_) | Event::Comma(_) => {}
+        }
+    }
+
+    Ok(found)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parse() {
+        let steps = r#"$.a[0]..*['b.c']["d\"e"][*]..[1]..f.2"#.parse::<PathPattern>().unwrap().steps;
+        assert_eq!(steps, [
+            Step::Child(Selector::Name("a".into())),
+            Step::Child(Selector::Index(0)),
+            Step::Descendant(Selector::Wildcard),
+            Step::Child(Selector::Name("b.c".into())),
+            Step::Child(Selector::Name("d\"e".into())),
+            Step::Child(Selector::Wildcard),
+            Step::Descendant(Selector::Index(1)),
+            Step::Descendant(Selector::Name("f".into())),
+            Step::Child(Selector::Name("2".into()))
+        ]);
+        assert!("$".parse::<PathPattern>().unwrap().steps.is_empty());
+
+        // Tests errors
+        assert_eq!("a".parse::<PathPattern>(), Err(ParsePatternError { offset: 0 }));
+        assert_eq!("$a".parse::<PathPattern>(), Err(ParsePatternError { offset: 1 }));
+        assert_eq!("$.a.".parse::<PathPattern>(), Err(ParsePatternError { offset: 4 }));
+        assert_eq!("$[x]".parse::<PathPattern>(), Err(ParsePatternError { offset: 2 }));
+        assert_eq!("$[0".parse::<PathPattern>(), Err(ParsePatternError { offset: 3 }));
+        assert_eq!("$['a]".parse::<PathPattern>(), Err(ParsePatternError { offset: 5 }));
+    }
+
+    #[test]
+    fn evaluate() {
+        let json = r#" {"a": [{"b": 1}, {"c": {"b": [2, 3]}}], "b": "x"} "#;
+        let select = |expression: &str| {
+            query(json, expression).unwrap().into_iter().map(|(path, span)| (path.to_string(), &json[span])).collect::<Vec<_>>()
+        };
+        let paths = |expression: &str| select(expression).into_iter().map(|(path, _)| path).collect::<Vec<_>>();
+
+        assert_eq!(select("$"), [("$".to_string(), json.trim())]);
+        assert_eq!(select("$.a[1].c"), [("$.a.1.c".to_string(), r#"{"b": [2, 3]}"#)]);
+        assert_eq!(paths("$..b"), ["$.a.0.b", "$.a.1.c.b", "$.b"]);
+        assert_eq!(paths("$..b[*]"), ["$.a.1.c.b.0", "$.a.1.c.b.1"]);
+        assert_eq!(paths("$.a.*"), ["$.a.0", "$.a.1"]);
+        assert_eq!(paths("$.a.0.b"), ["$.a.0.b"]);
+        assert_eq!(select("$..[1]"), [("$.a.1".to_string(), r#"{"c": {"b": [2, 3]}}"#), ("$.a.1.c.b.1".to_string(), "3")]);
+        assert_eq!(paths("$..*").len(), 9);
+        assert!(paths("$.c").is_empty());
+
+        assert!(matches!(query("[1,]", "$"), Err(QueryError::Json(_))));
+        assert!(matches!(query(json, "$["), Err(QueryError::InvalidQuery(_))));
+    }
+
+    #[test]
+    fn patterns() {
+        let path = |s: &str| s.parse::<Path>().unwrap();
+        let pattern: PathPattern = "$..a[*]..b".parse().unwrap();
+
+        assert!(pattern.matches(&path("$.a.0.b")));
+        assert!(pattern.matches(&path("$.x.a.y.z.b")));
+        assert!(!pattern.matches(&path("$.a.b")));
+        assert!(!pattern.matches(&path("$.a.0.b.c")));
+        assert!("$".parse::<PathPattern>().unwrap().matches(&Path::new()));
+
+        let json = r#"[{"a": [{"b": 1}]}, {"a": {"c": {"b": 2}}}]"#;
+        let found = paths_matching(json, &pattern).unwrap();
+        assert_eq!(found, query(json, "$..a[*]..b").unwrap());
+        assert_eq!(found.iter().map(|(path, span)| (path.to_string(), &json[span.clone()])).collect::<Vec<_>>(), [
+            ("$.0.a.0.b".to_string(), "1"),
+            ("$.1.a.c.b".to_string(), "2")
+        ]);
+    }
+}
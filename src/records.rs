@@ -0,0 +1,213 @@
+use crate::error::Error;
+use crate::lines::OffsetUnit;
+use crate::options::Options;
+use crate::path::Path;
+use crate::scan::{self, Event, PathTracker, Scanner};
+
+/// Whether a record holds nothing but whitespace, and can be skipped
+fn is_blank(record: &[u8]) -> bool {
+    record.iter().all(|b| matches!(b, b' ' | b'\t' | b'\r' | b'\n'))
+}
+
+/// Constructs the path to a byte offset in text made of records split by `separator`,
+/// returning the index of the record the offset falls in and the path within it.
+fn path_in_records(bytes: &[u8], offset: usize, separator: u8) -> Result<(usize, Path), Error> {
+    let mut found = None;
+    let mut start = 0;
+    for (i, record) in bytes.split(|b| *b == separator).enumerate() {
+        let end = start + record.len();
+        let contains = found.is_none() && offset <= end;
+
+        let path = if is_blank(record) {
+            Path::new()
+        } else {
+            // Records not containing the offset are still scanned to validate them
+            let record_offset = if contains { offset - start } else { record.len() };
+            scan::path_to(record, record_offset, &Options::default()).map_err(|e| e.offset_by(bytes, start))?
+        };
+
+        if contains {
+            found = Some((i, path));
+        }
+        start = end + 1;
+    }
+
+    Ok(found.unwrap_or_default())
+}
+
+/// Constructs the path to an index in [newline delimited json](https://github.com/ndjson/ndjson-spec),
+/// where each line of the text is a separate document.
+/// Returns the zero based line of the record the index falls in, along with the path within that record.
+/// The index is counted in chars, the same as [`path`](crate::path).
+///
+/// Blank lines are skipped, an index on one has the root path.
+///
+/// # Examples
+///
+/// ```
+/// use jsonposition::path_in_lines;
+///
+/// let json = "{\"a\": 1}\n{\"a\": [2, 3]}\n";
+///
+/// let (line, path) = path_in_lines(json, json.find('3').unwrap()).expect("Invalid JSON");
+/// assert_eq!(line, 1);
+/// assert_eq!(path.to_string(), "$.a.1");
+/// ```
+///
+/// # Errors
+///
+/// Returns an [`Error`](crate::Error) if any record is invalid json,
+/// with its offset and position in the whole text.
+pub fn path_in_lines(text: &str, offset: usize) -> Result<(usize, Path), Error> {
+    let byte_offset = OffsetUnit::Chars.to_byte_offset(text, offset);
+    path_in_records(text.as_bytes(), byte_offset, b'\n')
+}
+
+/// Constructs the path to an index in a [json text sequence](https://www.rfc-editor.org/rfc/rfc7464),
+/// where each record starts with an ASCII record separator (`0x1E`), as served with `application/json-seq`.
+/// Returns the zero based index of the record the index falls in, along with the path within that record.
+/// The index is counted in chars, the same as [`path`](crate::path).
+///
+/// Records are usually ended with a line feed, but any whitespace is accepted around them,
+/// and empty records are skipped like blank lines in [`path_in_lines`].
+///
+/// # Examples
+///
+/// ```
+/// use jsonposition::path_in_sequence;
+///
+/// let json = "\x1e{\"a\": 1}\n\x1e{\"a\": [2, 3]}\n";
+///
+/// let (record, path) = path_in_sequence(json, json.find('3').unwrap()).expect("Invalid JSON");
+/// assert_eq!(record, 1);
+/// assert_eq!(path.to_string(), "$.a.1");
+/// ```
+///
+/// # Errors
+///
+/// Returns an [`Error`](crate::Error) if any record is invalid json,
+/// with its offset and position in the whole text.
+pub fn path_in_sequence(text: &str, offset: usize) -> Result<(usize, Path), Error> {
+    let byte_offset = OffsetUnit::Chars.to_byte_offset(text, offset);
+    let (record, path) = path_in_records(text.as_bytes(), byte_offset, 0x1e)?;
+
+    // Each record starts with a separator, so what comes before the first one isn't a record of its own
+    let leading = text.find('\x1e').is_some_and(|i| is_blank(&text.as_bytes()[..i]));
+    Ok((if leading { record.saturating_sub(1) } else { record }, path))
+}
+
+/// Constructs the path to a byte offset in a stream of root values,
+/// returning the zero based index of the value the offset falls in and the path within it.
+fn path_in_stream(bytes: &[u8], offset: usize, options: &Options) -> Result<(usize, Path), Error> {
+    let mut tracker = PathTracker::with_options(options);
+    let mut document = None;
+    let mut depth = 0usize;
+    for event in Scanner::with_options(bytes, options) {
+        let event = event?;
+        if event.start() > offset {
+            continue;
+        }
+
+        // A document starting right at the offset is the one it falls in, even though its path is not entered yet
+        if let Event::StartObject(_) | Event::StartArray(_) | Event::Scalar(_) = event {
+            if depth == 0 {
+                document = Some(document.map_or(0, |i| i + 1));
+            }
+        }
+        if event.start() == offset {
+            continue;
+        }
+        match event {
+            Event::StartObject(_) | Event::StartArray(_) => depth += 1,
+            Event::EndObject(_) | Event::EndArray(_) => depth -= 1,
+            _ => {}
+        }
+        tracker.update(bytes, &event);
+    }
+    Ok((document.unwrap_or_default(), tracker.into_path()))
+}
+
+/// Constructs the path to an index in a stream of json documents written one after another,
+/// such as `{"a": 1}{"b": 2}`, with or without whitespace between them.
+/// Returns the zero based index of the document the index falls in, along with the path within that document.
+/// The index is counted in chars, the same as [`path`](crate::path).
+///
+/// Between two documents the index belongs to the earlier one, with the root path.
+///
+/// # Examples
+///
+/// ```
+/// use jsonposition::path_in_documents;
+///
+/// let json = r#"{"a": 1}{"a": [2, 3]}"#;
+///
+/// let (document, path) = path_in_documents(json, json.find('3').unwrap()).expect("Invalid JSON");
+/// assert_eq!(document, 1);
+/// assert_eq!(path.to_string(), "$.a.1");
+/// ```
+///
+/// # Errors
+///
+/// Returns an [`Error`](crate::Error) if any document is invalid json.
+pub fn path_in_documents(text: &str, offset: usize) -> Result<(usize, Path), Error> {
+    let byte_offset = OffsetUnit::Chars.to_byte_offset(text, offset);
+    path_in_stream(text.as_bytes(), byte_offset, &Options::new().allow_multiple_documents(true))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn lines() {
+        let json = "{\"a\": 1}\r\n\n[1, {\"b\": \"c\"}]\n";
+        let at = |s: &str| path_in_lines(json, json.find(s).unwrap()).map(|(line, path)| (line, path.to_string())).unwrap();
+
+        assert_eq!(at("1}"), (0, "$.a".to_string()));
+        assert_eq!(at("\r"), (0, "$".to_string()));
+        assert_eq!(at("\n["), (1, "$".to_string()));
+        assert_eq!(at("\"c"), (2, "$.1.b".to_string()));
+        assert_eq!(path_in_lines(json, 1000).unwrap(), (3, Path::new()));
+
+        // Tests errors are placed in the whole text
+        let error = path_in_lines("[1]\n[2,]\n", 0).unwrap_err();
+        assert_eq!((error.offset(), error.line(), error.column()), (7, 1, 3));
+
+        // Tests a record can't span lines
+        assert!(path_in_lines("[1,\n2]", 0).is_err());
+    }
+
+    #[test]
+    fn sequence() {
+        let json = "\x1e[1, 2]\n\x1e\n\x1e{\"a\": \"b\"}\n";
+        let at = |s: &str| path_in_sequence(json, json.find(s).unwrap()).map(|(i, path)| (i, path.to_string())).unwrap();
+
+        assert_eq!(at("\x1e["), (0, "$".to_string()));
+        assert_eq!(at("2"), (0, "$.1".to_string()));
+        assert_eq!(at("\"b"), (2, "$.a".to_string()));
+        assert_eq!(path_in_sequence("[1]", 2).unwrap(), (0, "$.0".parse().unwrap()));
+
+        // Tests errors are placed in the whole text
+        let error = path_in_sequence("\x1e[1]\n\x1e[2,]\n", 0).unwrap_err();
+        assert_eq!((error.offset(), error.line(), error.column()), (9, 1, 4));
+        assert!(path_in_sequence("\x1e1 2\n", 0).is_err());
+    }
+
+    #[test]
+    fn documents() {
+        let json = r#"{"a": 1}[2, {"b": 3}] "c" 4"#;
+        let at = |s: &str| path_in_documents(json, json.find(s).unwrap()).map(|(i, path)| (i, path.to_string())).unwrap();
+
+        assert_eq!(at("{"), (0, "$".to_string()));
+        assert_eq!(at("1"), (0, "$.a".to_string()));
+        assert_eq!(at("3"), (1, "$.1.b".to_string()));
+        assert_eq!(at(" \"c"), (1, "$".to_string()));
+        assert_eq!(at("4"), (3, "$".to_string()));
+
+        // Tests empty streams and errors
+        assert_eq!(path_in_documents(" ", 0).unwrap(), (0, Path::new()));
+        assert_eq!(path_in_documents("[1] ]", 0).unwrap_err().kind(), crate::ErrorKind::OrphanSquareBrace);
+        assert!(path_in_documents("[1] [", 0).is_err());
+        assert!(path_in_documents("truefalse", 0).is_err());
+    }
+}
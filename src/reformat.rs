@@ -0,0 +1,220 @@
+use core::ops::Range;
+use alloc::string::String;
+use alloc::vec::Vec;
+
+use crate::error::Error;
+use crate::scan::{is_whitespace, Event, Scanner};
+
+/// Run of text copied unchanged from the original text to the output
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+struct Segment {
+    original: usize,
+    output: usize,
+    len: usize
+}
+
+/// Translates byte offsets between a document and a reformatted copy of it, as made by [`minify`] or [`prettify`].
+///
+/// Every token is copied unchanged and only the whitespace between tokens differs, so an offset in a token
+/// maps to the same place in the token on the other side and a path computed there is the same.
+/// An offset in whitespace that only one side has maps to the end of the token before it, which is inside the same container.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct SourceMap {
+    /// Segments in the order they appear on both sides
+    segments: Vec<Segment>
+}
+
+impl SourceMap {
+    /// Records that `len` bytes at `original` were copied to `output`, joining the segment before if they follow on from it.
+    fn push(&mut self, original: usize, output: usize, len: usize) {
+        if let Some(last) = self.segments.last_mut() {
+            if last.original + last.len == original && last.output + last.len == output {
+                last.len += len;
+                return;
+            }
+        }
+        self.segments.push(Segment { original, output, len });
+    }
+
+    /// Maps an offset from one side to the other, `from` and `to` picking the sides out of a segment
+    fn translate(&self, offset: usize, from: fn(&Segment) -> usize, to: fn(&Segment) -> usize) -> usize {
+        let i = self.segments.partition_point(|segment| from(segment) <= offset);
+        match i.checked_sub(1).map(|i| &self.segments[i]) {
+            Some(segment) => to(segment) + (offset - from(segment)).min(segment.len),
+            None => 0
+        }
+    }
+
+    /// Maps a byte offset in the original text to the reformatted text.
+    pub fn to_output(&self, offset: usize) -> usize {
+        self.translate(offset, |segment| segment.original, |segment| segment.output)
+    }
+
+    /// Maps a byte offset in the reformatted text back to the original text.
+    pub fn to_original(&self, offset: usize) -> usize {
+        self.translate(offset, |segment| segment.output, |segment| segment.original)
+    }
+}
+
+/// Span of every token of a raw json string in order, colons included
+fn tokens(text: &str) -> Result<Vec<Range<usize>>, Error> {
+    let bytes = text.as_bytes();
+    let mut tokens = Vec::new();
+    for event in Scanner::new(bytes) {
+        match event? {
+            Event::StartObject(i) | Event::StartArray(i) | Event::Comma(i) => tokens.push(i..i + 1),
+            Event::EndObject(span) | Event::EndArray(span) => tokens.push(span.end - 1..span.end),
+            Event::Scalar(span) => tokens.push(span),
+            Event::Key(span) => {
+                let colon = span.end + bytes[span.end..].iter().take_while(|b| is_whitespace(**b)).count();
+                tokens.push(span);
+                tokens.push(colon..colon + 1);
+            }
+        }
+    }
+    Ok(tokens)
+}
+
+/// Removes all whitespace between the tokens of a raw json string,
+/// returning the minified text and a [`SourceMap`] between it and the original.
+///
+/// # Examples
+///
+/// ```
+/// use jsonposition::{dot_path, minify};
+///
+/// let json = "{\n  \"a\": [1, 2],\n  \"b\": \"x y\"\n}";
+///
+/// let (minified, map) = minify(json).expect("Invalid JSON");
+/// assert_eq!(minified, r#"{"a":[1,2],"b":"x y"}"#);
+///
+/// // An offset reported against the minified text, mapped back to the original
+/// let offset = map.to_original(minified.find('2').unwrap());
+/// assert_eq!(offset, json.find('2').unwrap());
+/// assert_eq!(dot_path(json, offset).unwrap(), "$.a.1");
+/// ```
+///
+/// # Errors
+///
+/// Returns an [`Error`](crate::Error) if the input json is invalid.
+pub fn minify(text: &str) -> Result<(String, SourceMap), Error> {
+    let mut minified = String::with_capacity(text.len());
+    let mut map = SourceMap::default();
+    for token in tokens(text)? {
+        map.push(token.start, minified.len(), token.len());
+        minified.push_str(&text[token]);
+    }
+    Ok((minified, map))
+}
+
+/// Lays a raw json string out with each element and member on its own line, indented by `indent` for each level of nesting,
+/// returning the formatted text and a [`SourceMap`] between it and the original.
+/// Empty objects and arrays are kept on one line as `{}` and `[]`.
+///
+/// # Examples
+///
+/// ```
+/// use jsonposition::{dot_path, prettify};
+///
+/// let json = r#"{"a":[1,2],"b":{}}"#;
+///
+/// let (pretty, map) = prettify(json, "  ").expect("Invalid JSON");
+/// assert_eq!(pretty, "{\n  \"a\": [\n    1,\n    2\n  ],\n  \"b\": {}\n}");
+///
+/// // An error offset reported against the compact text, mapped into the formatted view
+/// let offset = map.to_output(json.find('2').unwrap());
+/// assert_eq!(offset, pretty.find('2').unwrap());
+/// assert_eq!(dot_path(&pretty, offset).unwrap(), "$.a.1");
+/// ```
+///
+/// # Errors
+///
+/// Returns an [`Error`](crate::Error) if the input json is invalid.
+pub fn prettify(text: &str, indent: &str) -> Result<(String, SourceMap), Error> {
+    let bytes = text.as_bytes();
+    let tokens = tokens(text)?;
+    let mut pretty = String::with_capacity(text.len() * 2);
+    let mut map = SourceMap::default();
+    let mut depth = 0;
+    let new_line = |pretty: &mut String, depth: usize| {
+        pretty.push('\n');
+        (0..depth).for_each(|_| pretty.push_str(indent));
+    };
+
+    for (i, token) in tokens.iter().enumerate() {
+        let b = bytes[token.start];
+        let empty = matches!(b, b'{' | b'[') && tokens.get(i + 1).is_some_and(|next| matches!(bytes[next.start], b'}' | b']'));
+        if matches!(b, b'}' | b']') && !matches!(bytes[tokens[i - 1].start], b'{' | b'[') {
+            depth -= 1;
+            new_line(&mut pretty, depth);
+        }
+
+        map.push(token.start, pretty.len(), token.len());
+        pretty.push_str(&text[token.clone()]);
+
+        match b {
+            b'{' | b'[' if !empty => {
+                depth += 1;
+                new_line(&mut pretty, depth);
+            }
+            b',' => new_line(&mut pretty, depth),
+            b':' => pretty.push(' '),
+            _ => {}
+        }
+    }
+    Ok((pretty, map))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn minified() {
+        let json = " {\"a\" :\t[1, {\"b c\": \"d, e\"}, [ ]],\r\n \"f\": -2.5e3 } ";
+        let (minified, map) = minify(json).unwrap();
+        assert_eq!(minified, r#"{"a":[1,{"b c":"d, e"},[]],"f":-2.5e3}"#);
+
+        // Tests paths agree on both sides for every offset in a token
+        for (offset, b) in json.bytes().enumerate() {
+            if !is_whitespace(b) {
+                let mapped = map.to_output(offset);
+                assert_eq!(minified.as_bytes()[mapped], b, "{}", offset);
+                assert_eq!(crate::path(json, offset).unwrap(), crate::path(&minified, mapped).unwrap(), "{}", offset);
+            }
+        }
+        for offset in 0..=minified.len() {
+            assert_eq!(map.to_output(map.to_original(offset)), offset);
+        }
+
+        // Tests whitespace maps to the end of the token before
+        assert_eq!(map.to_output(0), 0);
+        assert_eq!(map.to_output(json.find(" :").unwrap()), 4);
+        assert_eq!(map.to_output(json.len()), minified.len());
+        assert_eq!(map.to_original(minified.len()), json.len() - 1);
+
+        assert!(minify("[1,]").is_err());
+        assert_eq!(minify("").unwrap_err().kind(), crate::ErrorKind::UnexpectedEnd);
+    }
+
+    #[test]
+    fn prettified() {
+        let json = r#" {"a" :[1, {"b c": "d, e"}, [ ], {}],"f": -2.5e3} "#;
+        let (pretty, map) = prettify(json, "\t").unwrap();
+        assert_eq!(pretty, "{\n\t\"a\": [\n\t\t1,\n\t\t{\n\t\t\t\"b c\": \"d, e\"\n\t\t},\n\t\t[],\n\t\t{}\n\t],\n\t\"f\": -2.5e3\n}");
+
+        for (offset, b) in json.bytes().enumerate() {
+            if !is_whitespace(b) {
+                let mapped = map.to_output(offset);
+                assert_eq!(pretty.as_bytes()[mapped], b, "{}", offset);
+                assert_eq!(crate::path(json, offset).unwrap(), crate::path(&pretty, mapped).unwrap(), "{}", offset);
+            }
+        }
+        // Tests formatting round trips through minify
+        let (minified, _) = minify(&pretty).unwrap();
+        assert_eq!(prettify(&minified, "\t").unwrap().0, pretty);
+
+        assert_eq!(prettify("1", "  ").unwrap().0, "1");
+        assert!(prettify("{", "  ").is_err());
+    }
+}
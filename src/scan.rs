@@ -0,0 +1,1226 @@
+use core::ops::Range;
+use alloc::boxed::Box;
+use alloc::string::String;
+use alloc::sync::Arc;
+use alloc::vec;
+use alloc::vec::Vec;
+use core::sync::atomic::{AtomicBool, Ordering};
+#[cfg(feature = "std")]
+use std::time::Instant;
+
+use crate::error::{Error, ErrorKind};
+use crate::options::{Limits, Options};
+
+use crate::path::{Index, Path};
+
+/// Structural event produced while scanning a json document.
+/// Offsets are byte offsets into the scanned text.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub(crate) enum Event {
+    /// Opening `{` at the contained offset
+    StartObject(usize),
+    /// Closing `}`, with the span of the whole object
+    EndObject(Range<usize>),
+    /// Opening `[` at the contained offset
+    StartArray(usize),
+    /// Closing `]`, with the span of the whole array
+    EndArray(Range<usize>),
+    /// Object key, with the span including its quotes
+    Key(Range<usize>),
+    /// String, number, boolean or null
+    Scalar(Range<usize>),
+    /// Comma separating elements or members, at the contained offset
+    Comma(usize)
+}
+
+impl Event {
+    /// Offset of the first byte of the event
+    pub(crate) fn start(&self) -> usize {
+        match self {
+            Event::StartObject(i) | Event::StartArray(i) | Event::Comma(i) => *i,
+            Event::EndObject(span) | Event::EndArray(span) => span.end - 1,
+            Event::Key(span) | Event::Scalar(span) => span.start
+        }
+    }
+}
+
+/// Text of a key from its span, without quotes, decoded by [`decode_key`]
+pub(crate) fn key_text(bytes: &[u8], span: &Range<usize>, json5: bool) -> String {
+    let mut raw = &bytes[span.clone()];
+    if let Some(quote @ (b'"' | b'\'')) = raw.first() {
+        raw = &raw[1..];
+        raw = raw.strip_suffix(&[*quote]).unwrap_or(raw);
+    }
+    decode_key(raw, json5)
+}
+
+/// Text of a key from the bytes between its quotes, with its escape sequences decoded, including those of json5 if `json5` is set.
+/// A key that can't be decoded, as can happen in lenient mode, is kept as it is written.
+pub(crate) fn decode_key(raw: &[u8], json5: bool) -> String {
+    let text = String::from_utf8_lossy(raw);
+    if !text.contains('\\') {
+        return text.into_owned();
+    }
+    unescape_with(&text, json5).unwrap_or_else(|_| text.into_owned())
+}
+
+/// Value of the `len` hex digits at a byte offset, as in a `\u` or `\x` escape
+fn hex_digits(raw: &str, at: usize, len: usize) -> Option<u32> {
+    let digits = raw.get(at..at + len)?;
+    if !digits.bytes().all(|b| b.is_ascii_hexdigit()) {
+        return None;
+    }
+    u32::from_str_radix(digits, 16).ok()
+}
+
+/// Decodes the escape sequences in the text between the quotes of a json string,
+/// or returns the offset of the first invalid one. Surrogates that aren't paired are replaced with U+FFFD.
+pub(crate) fn unescape(raw: &str) -> Result<String, usize> {
+    unescape_with(raw, false)
+}
+
+/// Decodes the escape sequences of a json string like [`unescape`], along with those json5 adds if `json5` is set:
+/// `\v`, `\0`, `\xHH`, a backslash before a line break continuing the string on the next line, and any other character escaping itself.
+pub(crate) fn unescape_with(raw: &str, json5: bool) -> Result<String, usize> {
+    let mut decoded = String::with_capacity(raw.len());
+    let mut i = 0;
+    while let Some(escape) = raw[i..].find('\\').map(|j| i + j) {
+        decoded.push_str(&raw[i..escape]);
+        i = escape + 2;
+        let c = match raw.as_bytes().get(escape + 1) {
+            Some(b'"') => '"',
+            // Only written in the single quoted strings of json5
+            Some(b'\'') => '\'',
+            Some(b'\\') => '\\',
+            Some(b'/') => '/',
+            Some(b'b') => '\u{8}',
+            Some(b'f') => '\u{c}',
+            Some(b'n') => '\n',
+            Some(b'r') => '\r',
+            Some(b't') => '\t',
+            Some(b'u') => {
+                let unit = hex_digits(raw, i, 4).ok_or(escape)?;
+                i += 4;
+                let low = raw[i..].strip_prefix("\\u").and_then(|_| hex_digits(raw, i + 2, 4)).filter(|low| (0xDC00..0xE000).contains(low));
+                match low {
+                    Some(low) if (0xD800..0xDC00).contains(&unit) => {
+                        i += 6;
+                        char::from_u32(0x10000 + ((unit - 0xD800) << 10) + (low - 0xDC00)).unwrap_or('\u{fffd}')
+                    }
+                    _ => char::from_u32(unit).unwrap_or('\u{fffd}')
+                }
+            }
+            Some(b'v') if json5 => '\u{b}',
+            Some(b'0') if json5 => '\0',
+            Some(b'x') if json5 => {
+                let byte = hex_digits(raw, i, 2).ok_or(escape)?;
+                i += 2;
+                char::from_u32(byte).unwrap_or('\u{fffd}')
+            }
+            Some(b'1'..=b'9') => return Err(escape),
+            Some(b'\r') if json5 => {
+                i += usize::from(raw[i..].starts_with('\n'));
+                continue;
+            }
+            Some(b'\n') if json5 => continue,
+            Some(_) if json5 => {
+                let c = raw[escape + 1..].chars().next().ok_or(escape)?;
+                i = escape + 1 + c.len_utf8();
+                if matches!(c, '\u{2028}' | '\u{2029}') {
+                    continue;
+                }
+                c
+            }
+            _ => return Err(escape)
+        };
+        decoded.push(c);
+    }
+    decoded.push_str(&raw[i..]);
+    Ok(decoded)
+}
+
+/// What the scanner accepts next
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum Expect {
+    /// Any value, at the root or after a colon or comma
+    Value,
+    /// A value or `]`, right after `[`
+    FirstValue,
+    /// A key, after a comma in an object
+    Key,
+    /// A key or `}`, right after `{`
+    FirstKey,
+    Colon,
+    /// A comma or the end of the current container
+    CommaOrEnd,
+    /// Nothing but whitespace after the root value
+    Done
+}
+
+/// Byte based tokenizer over a json document.
+///
+/// Validates the document as it goes, so a single pass both checks and scans it.
+/// After yielding an error the scanner stops.
+pub(crate) struct Scanner<'a> {
+    bytes: &'a [u8],
+    pos: usize,
+    /// Start offset of each open container, and whether it is an object
+    stack: Vec<(usize, bool)>,
+    expect: Expect,
+    failed: bool,
+    /// Whether the last event closed a container without a closing brace
+    implicit: bool,
+    lenient: bool,
+    truncated: bool,
+    comments: bool,
+    trailing_commas: bool,
+    multiple: bool,
+    json5: bool,
+    /// Called with the number of bytes scanned so far, see [`Scanner::on_progress`]
+    progress: Option<Box<dyn FnMut(usize) + 'a>>,
+    next_report: usize,
+    cancel: Option<Arc<AtomicBool>>,
+    next_check: usize,
+    max_depth: Option<usize>,
+    limits: Limits,
+    /// When scanning started, if there is a time budget
+    #[cfg(feature = "std")]
+    started: Option<Instant>,
+    /// Number of values scanned so far
+    nodes: usize
+}
+
+/// Bytes scanned between calls to a progress hook
+const PROGRESS_INTERVAL: usize = 1 << 20;
+
+/// Bytes scanned between checks of the cancel flag and time budget
+const CHECK_INTERVAL: usize = 1 << 16;
+
+/// How the scanner recovers from malformed input in lenient mode
+enum Repair {
+    /// Yield this event in place of an error
+    Event(Event),
+    /// The state or position changed, scan again
+    Rescan,
+    /// Nothing to repair
+    Valid
+}
+
+impl<'a> Scanner<'a> {
+    pub(crate) fn new(bytes: &'a [u8]) -> Self {
+        Scanner::with_options(bytes, &Options::default())
+    }
+
+    pub(crate) fn with_options(bytes: &'a [u8], options: &Options) -> Self {
+        Scanner {
+            bytes,
+            pos: 0,
+            stack: Vec::new(),
+            // Multiple documents may be empty, so start as if one has already been scanned
+            expect: if options.multiple { Expect::Done } else { Expect::Value },
+            failed: false,
+            implicit: false,
+            lenient: options.lenient,
+            truncated: options.truncated,
+            comments: options.comments || options.json5,
+            trailing_commas: options.trailing_commas || options.json5,
+            multiple: options.multiple,
+            json5: options.json5,
+            progress: None,
+            next_report: PROGRESS_INTERVAL,
+            cancel: options.cancel.clone(),
+            next_check: 0,
+            max_depth: options.max_depth,
+            limits: options.limits,
+            #[cfg(feature = "std")]
+            started: options.limits.time.map(|_| Instant::now()),
+            nodes: 0
+        }
+    }
+
+    /// Calls `progress` with the number of bytes scanned roughly every megabyte, and once with the whole length at the end.
+    pub(crate) fn on_progress(&mut self, progress: impl FnMut(usize) + 'a) {
+        self.progress = Some(Box::new(progress));
+    }
+
+    /// Checks the input size, and the cancel flag and time budget every [`CHECK_INTERVAL`] bytes
+    fn check_interrupts(&mut self) -> Result<(), Error> {
+        if let Some(max) = self.limits.bytes.filter(|max| self.bytes.len() > *max) {
+            return Err(self.error(ErrorKind::SizeExceeded, max));
+        }
+        if self.pos < self.next_check {
+            return Ok(());
+        }
+        if self.cancel.as_ref().is_some_and(|cancel| cancel.load(Ordering::Relaxed)) {
+            return Err(self.error(ErrorKind::Cancelled, self.pos));
+        }
+        #[cfg(feature = "std")]
+        if let (Some(started), Some(budget)) = (self.started, self.limits.time) {
+            if started.elapsed() >= budget {
+                return Err(self.error(ErrorKind::TimeExceeded, self.pos));
+            }
+        }
+        self.next_check = self.pos + CHECK_INTERVAL;
+        Ok(())
+    }
+
+    /// Counts values against the node limit
+    fn count_node(&mut self, event: &Event) -> Result<(), Error> {
+        if let Event::StartObject(start) | Event::StartArray(start) | Event::Scalar(Range { start, .. }) = event {
+            self.nodes += 1;
+            if self.limits.nodes.is_some_and(|max| self.nodes > max) {
+                return Err(self.error(ErrorKind::NodesExceeded, *start));
+            }
+        }
+        Ok(())
+    }
+
+    fn report(&mut self, finished: bool) {
+        if finished {
+            if let Some(mut progress) = self.progress.take() {
+                progress(self.bytes.len());
+            }
+        } else if self.pos >= self.next_report {
+            if let Some(progress) = &mut self.progress {
+                progress(self.pos);
+            }
+            self.next_report = self.pos + PROGRESS_INTERVAL;
+        }
+    }
+
+    /// Start of an event, placing containers closed without a brace
+    /// at the byte that closed them rather than on their last byte.
+    pub(crate) fn start_of(&self, event: &Event) -> usize {
+        match event {
+            Event::EndObject(span) | Event::EndArray(span) if self.implicit => span.end,
+            event => event.start()
+        }
+    }
+
+    /// Moves past whitespace, and comments if they are allowed
+    fn skip_whitespace(&mut self) -> Result<(), Error> {
+        loop {
+            loop {
+                match self.bytes.get(self.pos) {
+                    Some(b) if is_whitespace(*b) => self.pos += 1,
+                    Some(_) if self.json5 => match json5_whitespace(&self.bytes[self.pos..]) {
+                        0 => break,
+                        len => self.pos += len
+                    },
+                    _ => break
+                }
+            }
+
+            if !self.comments || self.bytes.get(self.pos) != Some(&b'/') {
+                return Ok(());
+            }
+
+            match self.bytes.get(self.pos + 1) {
+                Some(b'/') => {
+                    self.pos = self.bytes[self.pos..].iter().position(|b| *b == b'\n').map_or(self.bytes.len(), |i| self.pos + i);
+                }
+                Some(b'*') => {
+                    let body = self.pos + 2;
+                    match self.bytes[body.min(self.bytes.len())..].windows(2).position(|w| w == b"*/") {
+                        Some(i) => self.pos = body + i + 2,
+                        None if self.lenient || self.truncated => self.pos = self.bytes.len(),
+                        None => return Err(self.unexpected_end())
+                    }
+                }
+                Some(_) if self.lenient => return Ok(()),
+                Some(_) => return Err(self.error(ErrorKind::InvalidCharacter, self.pos)),
+                None if self.lenient || self.truncated => {
+                    self.pos = self.bytes.len();
+                    return Ok(());
+                }
+                None => return Err(self.unexpected_end())
+            }
+        }
+    }
+
+    fn is_delimiter(&self, b: u8) -> bool {
+        is_delimiter(b) || (self.comments && b == b'/')
+    }
+
+    fn in_object(&self) -> bool {
+        matches!(self.stack.last(), Some((_, true)))
+    }
+
+    fn expects_value(&self) -> bool {
+        matches!(self.expect, Expect::Value | Expect::FirstValue)
+    }
+
+    fn after_value(&mut self) {
+        self.expect = if self.stack.is_empty() { Expect::Done } else { Expect::CommaOrEnd };
+    }
+
+    fn error(&self, kind: ErrorKind, offset: usize) -> Error {
+        Error::new(kind, self.bytes, offset)
+    }
+
+    fn unexpected_end(&self) -> Error {
+        self.error(ErrorKind::UnexpectedEnd, self.bytes.len())
+    }
+
+    /// Lets a token run to the end of the text when truncated documents are allowed
+    fn allow_end(&self, end: Result<usize, Error>) -> Result<usize, Error> {
+        match end {
+            Err(e) if self.truncated && e.kind() == ErrorKind::UnexpectedEnd => Ok(self.bytes.len()),
+            end => end
+        }
+    }
+
+    /// Finds the end of the string starting with the quote at `start`
+    fn end_quote(&self, start: usize) -> Result<usize, Error> {
+        let quote = self.bytes[start];
+        if self.lenient {
+            return Ok(self.end_quote_lenient(start + 1, quote));
+        }
+
+        let mut i = start + 1;
+        while i < self.bytes.len() {
+            match self.bytes[i] {
+                b if b == quote => return Ok(i + 1),
+                b'\\' => match self.bytes.get(i + 1) {
+                    Some(b'"' | b'\\' | b'/' | b'b' | b'f' | b'n' | b'r' | b't') => i += 2,
+                    Some(b'u') => i = self.hex_escape(i + 2, 4)?,
+                    Some(b'x') if self.json5 => i = self.hex_escape(i + 2, 2)?,
+                    Some(b'1'..=b'9') if self.json5 => return Err(self.error(ErrorKind::InvalidCharacter, i + 1)),
+                    // JSON5 allows escaping any character, including line breaks
+                    Some(_) if self.json5 => i += 2,
+                    Some(_) => return Err(self.error(ErrorKind::InvalidCharacter, i + 1)),
+                    None => return Err(self.unexpected_end())
+                },
+                0..=0x1f => return Err(self.error(ErrorKind::InvalidCharacter, i)),
+                _ => i += 1
+            }
+        }
+        Err(self.unexpected_end())
+    }
+
+    fn hex_escape(&self, start: usize, len: usize) -> Result<usize, Error> {
+        match self.bytes.get(start..start + len) {
+            Some(hex) => match hex.iter().position(|b| !b.is_ascii_hexdigit()) {
+                Some(i) => Err(self.error(ErrorKind::InvalidCharacter, start + i)),
+                None => Ok(start + len)
+            },
+            None => Err(self.unexpected_end())
+        }
+    }
+
+    /// Finds the end of a JSON5 unquoted key
+    fn end_identifier(&self, start: usize) -> Result<usize, Error> {
+        let mut i = start;
+        while let Some(b) = self.bytes.get(i) {
+            match b {
+                b'\\' => match self.bytes.get(i + 1) {
+                    Some(b'u') => i = self.hex_escape(i + 2, 4)?,
+                    Some(_) => return Err(self.error(ErrorKind::InvalidCharacter, i + 1)),
+                    None => return Err(self.unexpected_end())
+                },
+                b if is_identifier_start(*b) || b.is_ascii_digit() => i += 1,
+                _ => break
+            }
+        }
+        Ok(i)
+    }
+
+    /// Finds the end of a string, running to the end of the text if it is never closed
+    fn end_quote_lenient(&self, start: usize, quote: u8) -> usize {
+        let mut i = start;
+        while i < self.bytes.len() {
+            match self.bytes[i] {
+                b if b == quote => return i + 1,
+                b'\\' => i += 2,
+                _ => i += 1
+            }
+        }
+        self.bytes.len()
+    }
+
+    /// Finds the end of an unknown token
+    fn end_junk(&self, start: usize) -> usize {
+        let mut i = start + 1;
+        while i < self.bytes.len() && !self.is_delimiter(self.bytes[i]) {
+            i += 1;
+        }
+        i
+    }
+
+    fn repair(&mut self, start: usize) -> Repair {
+        let b = self.bytes[start];
+        match (b, self.expect) {
+            (b'}' | b']', _) => {
+                let object = b == b'}';
+                match self.stack.iter().rposition(|(_, o)| *o == object) {
+                    // Stray closing brace
+                    None => {
+                        self.pos += 1;
+                        Repair::Rescan
+                    }
+                    // Close containers left open inside the one this brace closes
+                    Some(i) if i + 1 < self.stack.len() => {
+                        let (open, inner) = self.stack.pop().unwrap_or_default();
+                        self.after_value();
+                        self.implicit = true;
+                        Repair::Event(if inner { Event::EndObject(open..start) } else { Event::EndArray(open..start) })
+                    }
+                    // Trailing comma or missing value
+                    Some(_) => {
+                        self.expect = Expect::CommaOrEnd;
+                        Repair::Valid
+                    }
+                }
+            }
+            // Another root value
+            (_, Expect::Done) => {
+                self.expect = Expect::Value;
+                Repair::Rescan
+            }
+            (b',', Expect::CommaOrEnd) | (b':', Expect::Colon) => Repair::Valid,
+            // Missing value before a comma
+            (b',', _) if !self.stack.is_empty() => {
+                self.expect = Expect::CommaOrEnd;
+                Repair::Valid
+            }
+            (b',' | b':', _) => {
+                self.pos += 1;
+                Repair::Rescan
+            }
+            (_, Expect::Colon) => {
+                self.expect = Expect::Value;
+                Repair::Rescan
+            }
+            (_, Expect::CommaOrEnd) => {
+                self.expect = if self.in_object() { Expect::Key } else { Expect::Value };
+                Repair::Event(Event::Comma(start))
+            }
+            (b'"', _) => Repair::Valid,
+            (b'\'', _) if self.json5 => Repair::Valid,
+            // Missing key
+            (b'{' | b'[', Expect::Key | Expect::FirstKey) => {
+                self.expect = Expect::Value;
+                Repair::Rescan
+            }
+            // Unquoted key
+            (_, Expect::Key | Expect::FirstKey) => {
+                self.pos = self.end_junk(start);
+                self.expect = Expect::Colon;
+                Repair::Event(Event::Key(start..self.pos))
+            }
+            (b'{' | b'[', _) => Repair::Valid,
+            // Unknown or malformed scalar
+            _ => match self.end_scalar(start) {
+                Ok(_) => Repair::Valid,
+                Err(_) => {
+                    self.pos = self.end_junk(start);
+                    self.after_value();
+                    Repair::Event(Event::Scalar(start..self.pos))
+                }
+            }
+        }
+    }
+
+    fn digits(&self, start: usize) -> usize {
+        let mut i = start;
+        while i < self.bytes.len() && self.bytes[i].is_ascii_digit() {
+            i += 1;
+        }
+        i
+    }
+
+    fn end_number(&self, start: usize) -> Result<usize, Error> {
+        if self.json5 {
+            return self.end_number_json5(start);
+        }
+
+        let mut i = start;
+        if self.bytes[i] == b'-' {
+            i += 1;
+        }
+        match self.bytes.get(i) {
+            Some(b'0') => i += 1,
+            Some(b'1'..=b'9') => i = self.digits(i),
+            Some(_) => return Err(self.error(ErrorKind::InvalidCharacter, i)),
+            None => return Err(self.unexpected_end())
+        }
+        if self.bytes.get(i) == Some(&b'.') {
+            i = self.required_digits(i + 1)?;
+        }
+        if let Some(b'e' | b'E') = self.bytes.get(i) {
+            i += 1;
+            if let Some(b'+' | b'-') = self.bytes.get(i) {
+                i += 1;
+            }
+            i = self.required_digits(i)?;
+        }
+        Ok(i)
+    }
+
+    /// Finds the end of a JSON5 number, which may be hexadecimal, `Infinity` or `NaN`,
+    /// have a leading plus sign, or a leading or trailing decimal point.
+    fn end_number_json5(&self, start: usize) -> Result<usize, Error> {
+        let mut i = start;
+        if let Some(b'+' | b'-') = self.bytes.get(i) {
+            i += 1;
+        }
+
+        for word in [&b"Infinity"[..], b"NaN"] {
+            match self.bytes.get(i..i + word.len()) {
+                Some(raw) if raw == word => return Ok(i + word.len()),
+                None if !self.bytes[i..].is_empty() && word.starts_with(&self.bytes[i..]) => return Err(self.unexpected_end()),
+                _ => {}
+            }
+        }
+
+        if let Some(b'x' | b'X') = self.bytes.get(i + 1) {
+            if self.bytes[i] == b'0' {
+                i += 2;
+                return match self.bytes.get(i) {
+                    Some(b) if b.is_ascii_hexdigit() => {
+                        while self.bytes.get(i).is_some_and(u8::is_ascii_hexdigit) {
+                            i += 1;
+                        }
+                        Ok(i)
+                    }
+                    Some(_) => Err(self.error(ErrorKind::InvalidCharacter, i)),
+                    None => Err(self.unexpected_end())
+                };
+            }
+        }
+
+        let integer = self.digits(i);
+        let mut end = integer;
+        if self.bytes.get(end) == Some(&b'.') {
+            end = self.digits(end + 1);
+        }
+        // At least one digit on either side of the decimal point
+        if integer == i && end <= integer + 1 {
+            return match self.bytes.get(end) {
+                Some(_) => Err(self.error(ErrorKind::InvalidCharacter, end)),
+                None => Err(self.unexpected_end())
+            };
+        }
+        if let Some(b'e' | b'E') = self.bytes.get(end) {
+            end += 1;
+            if let Some(b'+' | b'-') = self.bytes.get(end) {
+                end += 1;
+            }
+            end = self.required_digits(end)?;
+        }
+        Ok(end)
+    }
+
+    fn required_digits(&self, start: usize) -> Result<usize, Error> {
+        match self.bytes.get(start) {
+            Some(b) if b.is_ascii_digit() => Ok(self.digits(start)),
+            Some(_) => Err(self.error(ErrorKind::InvalidCharacter, start)),
+            None => Err(self.unexpected_end())
+        }
+    }
+
+    fn end_literal(&self, start: usize) -> Result<usize, Error> {
+        for literal in [&b"true"[..], b"false", b"null"] {
+            let end = start + literal.len();
+            match self.bytes.get(start..end) {
+                Some(raw) if raw == literal => return Ok(end),
+                // Only a truncated document can end partway through a literal
+                None if literal.starts_with(&self.bytes[start..]) => return Err(self.unexpected_end()),
+                _ => {}
+            }
+        }
+        Err(self.error(ErrorKind::InvalidCharacter, start))
+    }
+
+    fn end_scalar(&self, start: usize) -> Result<usize, Error> {
+        let end = match self.bytes[start] {
+            b'-' | b'0'..=b'9' => self.end_number(start)?,
+            b'+' | b'.' | b'I' | b'N' if self.json5 => self.end_number(start)?,
+            _ => self.end_literal(start)?
+        };
+        match self.bytes.get(end) {
+            Some(b) if !self.is_delimiter(*b) => Err(self.error(ErrorKind::InvalidCharacter, end)),
+            _ => Ok(end)
+        }
+    }
+
+    fn scan(&mut self) -> Result<Option<Event>, Error> {
+        loop {
+            self.skip_whitespace()?;
+
+            if self.pos == self.bytes.len() {
+                if (self.lenient || self.truncated) && self.expect != Expect::Done {
+                    // Close any containers left open
+                    return Ok(self.stack.pop().map(|(open, object)| {
+                        self.after_value();
+                        self.implicit = true;
+                        match object {
+                            true => Event::EndObject(open..self.bytes.len()),
+                            false => Event::EndArray(open..self.bytes.len())
+                        }
+                    }));
+                }
+                return match self.expect {
+                    Expect::Done => Ok(None),
+                    _ => Err(self.unexpected_end())
+                };
+            }
+
+            let start = self.pos;
+            if self.lenient {
+                match self.repair(start) {
+                    Repair::Event(event) => return Ok(Some(event)),
+                    Repair::Rescan => continue,
+                    Repair::Valid => {}
+                }
+            }
+
+            if self.multiple && self.expect == Expect::Done {
+                self.expect = Expect::Value;
+            }
+
+            let event = match self.bytes[start] {
+                b'{' | b'[' if self.expects_value() => {
+                    let object = self.bytes[start] == b'{';
+                    self.pos += 1;
+                    if self.max_depth.is_some_and(|max| self.stack.len() >= max) {
+                        return Err(self.error(ErrorKind::DepthExceeded, start));
+                    }
+                    self.stack.push((start, object));
+                    if object {
+                        self.expect = Expect::FirstKey;
+                        Event::StartObject(start)
+                    } else {
+                        self.expect = Expect::FirstValue;
+                        Event::StartArray(start)
+                    }
+                }
+                b'}' if self.in_object() && (matches!(self.expect, Expect::FirstKey | Expect::CommaOrEnd) || (self.trailing_commas && self.expect == Expect::Key)) => {
+                    self.pos += 1;
+                    let (open, _) = self.stack.pop().unwrap_or_default();
+                    self.after_value();
+                    Event::EndObject(open..self.pos)
+                }
+                b']' if !self.in_object() && (matches!(self.expect, Expect::FirstValue | Expect::CommaOrEnd) || (self.trailing_commas && self.expect == Expect::Value && !self.stack.is_empty())) => {
+                    self.pos += 1;
+                    let (open, _) = self.stack.pop().unwrap_or_default();
+                    self.after_value();
+                    Event::EndArray(open..self.pos)
+                }
+                b'}' => return Err(self.error(ErrorKind::OrphanCurlyBrace, start)),
+                b']' => return Err(self.error(ErrorKind::OrphanSquareBrace, start)),
+                b',' if self.expect == Expect::CommaOrEnd => {
+                    self.pos += 1;
+                    self.expect = if self.in_object() { Expect::Key } else { Expect::Value };
+                    Event::Comma(start)
+                }
+                b',' => return Err(self.error(ErrorKind::InvalidComma, start)),
+                b':' if self.expect == Expect::Colon => {
+                    self.pos += 1;
+                    self.expect = Expect::Value;
+                    continue;
+                }
+                b':' => return Err(self.error(ErrorKind::InvalidColon, start)),
+                b'"' if matches!(self.expect, Expect::Key | Expect::FirstKey) => {
+                    self.pos = self.allow_end(self.end_quote(start))?;
+                    self.expect = Expect::Colon;
+                    Event::Key(start..self.pos)
+                }
+                b'"' if self.expects_value() => {
+                    self.pos = self.allow_end(self.end_quote(start))?;
+                    self.after_value();
+                    Event::Scalar(start..self.pos)
+                }
+                b'\'' if self.json5 && matches!(self.expect, Expect::Key | Expect::FirstKey) => {
+                    self.pos = self.allow_end(self.end_quote(start))?;
+                    self.expect = Expect::Colon;
+                    Event::Key(start..self.pos)
+                }
+                b'\'' if self.json5 && self.expects_value() => {
+                    self.pos = self.allow_end(self.end_quote(start))?;
+                    self.after_value();
+                    Event::Scalar(start..self.pos)
+                }
+                b'"' | b'\'' => return Err(self.error(ErrorKind::InvalidQuote, start)),
+                b if self.json5 && is_identifier_start(b) && matches!(self.expect, Expect::Key | Expect::FirstKey) => {
+                    self.pos = self.allow_end(self.end_identifier(start))?;
+                    self.expect = Expect::Colon;
+                    Event::Key(start..self.pos)
+                }
+                b'+' | b'.' | b'I' | b'N' if self.json5 && self.expects_value() => {
+                    self.pos = self.allow_end(self.end_scalar(start))?;
+                    self.after_value();
+                    Event::Scalar(start..self.pos)
+                }
+                b'-' | b'0'..=b'9' | b't' | b'f' | b'n' if self.expects_value() => {
+                    self.pos = self.allow_end(self.end_scalar(start))?;
+                    self.after_value();
+                    Event::Scalar(start..self.pos)
+                }
+                _ => return Err(self.error(ErrorKind::InvalidCharacter, start))
+            };
+            return Ok(Some(event));
+        }
+    }
+}
+
+/// Length of the extra whitespace JSON5 allows at the start of `bytes`, or 0 if there is none.
+/// Covers vertical tab, form feed, no-break space, byte order mark, and the line and paragraph separators.
+fn json5_whitespace(bytes: &[u8]) -> usize {
+    match bytes {
+        [0x0b | 0x0c, ..] => 1,
+        [0xc2, 0xa0, ..] => 2,
+        [0xef, 0xbb, 0xbf, ..] | [0xe2, 0x80, 0xa8 | 0xa9, ..] => 3,
+        _ => 0
+    }
+}
+
+/// Whether a byte can start an unquoted JSON5 key.
+/// Any non-ASCII byte is accepted rather than checking for unicode letters.
+fn is_identifier_start(b: u8) -> bool {
+    b.is_ascii_alphabetic() || matches!(b, b'$' | b'_' | b'\\') || b >= 0x80
+}
+
+pub(crate) fn is_whitespace(b: u8) -> bool {
+    matches!(b, b' ' | b'\t' | b'\n' | b'\r')
+}
+
+pub(crate) fn is_delimiter(b: u8) -> bool {
+    is_whitespace(b) || matches!(b, b',' | b':' | b'[' | b']' | b'{' | b'}' | b'"')
+}
+
+impl Iterator for Scanner<'_> {
+    type Item = Result<Event, Error>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.failed {
+            return None;
+        }
+        self.implicit = false;
+        let result = self.check_interrupts().and_then(|_| self.scan()).and_then(|event| match event {
+            Some(event) => self.count_node(&event).map(|_| Some(event)),
+            None => Ok(None)
+        });
+        if let Ok(event) = &result {
+            self.report(event.is_none());
+        }
+        match result {
+            Ok(event) => event.map(Ok),
+            Err(e) => {
+                self.failed = true;
+                Some(Err(e))
+            }
+        }
+    }
+}
+
+/// Builds the path to a position from the events before it.
+///
+/// Offsets between elements point at the next element once its comma has been passed,
+/// and an object key is part of the path as soon as the offset passes its opening quote.
+#[derive(Debug, Clone, Default)]
+pub(crate) struct PathTracker {
+    path: Path,
+    /// Each open container, and for objects whether a key is on the path
+    frames: Vec<Option<bool>>,
+    /// Whether keys are decoded with the escapes of json5
+    json5: bool
+}
+
+impl PathTracker {
+    pub(crate) fn with_options(options: &Options) -> Self {
+        PathTracker { json5: options.json5, ..PathTracker::default() }
+    }
+
+    pub(crate) fn update(&mut self, bytes: &[u8], event: &Event) {
+        match event {
+            Event::StartObject(_) => self.frames.push(Some(false)),
+            Event::StartArray(_) => {
+                self.path.push(Index::Array(0));
+                self.frames.push(None);
+            }
+            Event::EndObject(_) | Event::EndArray(_) => {
+                if self.frames.pop() != Some(Some(false)) {
+                    self.path.pop();
+                }
+            }
+            Event::Key(span) => self.key(key_text(bytes, span, self.json5)),
+            Event::Comma(_) => match self.frames.last_mut() {
+                Some(Some(has_key)) => {
+                    if *has_key {
+                        self.path.pop();
+                    }
+                    *has_key = false;
+                }
+                Some(None) => {
+                    if let Some(last) = self.path.last_mut() {
+                        last.increment();
+                    }
+                }
+                None => {}
+            },
+            Event::Scalar(_) => {}
+        }
+    }
+
+    /// Enters the value of an object member, the same as a [`Event::Key`] with this text
+    pub(crate) fn key(&mut self, key: String) {
+        self.path.push(Index::Object(key));
+        if let Some(frame) = self.frames.last_mut() {
+            *frame = Some(true);
+        }
+    }
+
+    pub(crate) fn path(&self) -> &Path {
+        &self.path
+    }
+
+    pub(crate) fn into_path(self) -> Path {
+        self.path
+    }
+}
+
+/// Constructs the path to a byte offset, counting every event that starts before it.
+/// The rest of the document is still scanned to validate it, unless scanning leniently.
+pub(crate) fn path_to(bytes: &[u8], offset: usize, options: &Options) -> Result<Path, Error> {
+    let mut tracker = PathTracker::with_options(options);
+    let mut scanner = Scanner::with_options(bytes, options);
+    while let Some(event) = scanner.next() {
+        let event = event?;
+        if scanner.start_of(&event) < offset {
+            tracker.update(bytes, &event);
+        } else if options.lenient {
+            break;
+        }
+    }
+    Ok(tracker.into_path())
+}
+
+/// Constructs the paths to many byte offsets in a single scan, returned in the order of `offsets`.
+pub(crate) fn paths_to(bytes: &[u8], offsets: &[usize], options: &Options) -> Result<Vec<Path>, Error> {
+    let mut order: Vec<usize> = (0..offsets.len()).collect();
+    order.sort_by_key(|i| offsets[*i]);
+    let mut pending = order.into_iter().peekable();
+
+    let mut paths = vec![Path::new(); offsets.len()];
+    let mut tracker = PathTracker::with_options(options);
+    let mut scanner = Scanner::with_options(bytes, options);
+    while let Some(event) = scanner.next() {
+        let event = event?;
+        let start = scanner.start_of(&event);
+        while let Some(i) = pending.next_if(|i| offsets[*i] <= start) {
+            paths[i] = tracker.path().clone();
+        }
+        if pending.peek().is_none() && options.lenient {
+            break;
+        }
+        tracker.update(bytes, &event);
+    }
+    for i in pending {
+        paths[i] = tracker.path().clone();
+    }
+    Ok(paths)
+}
+
+/// Constructs the path to a byte offset without scanning past it.
+/// Stops at the first syntax error, returning the path up to that point.
+pub(crate) fn path_to_unchecked(bytes: &[u8], offset: usize) -> Path {
+    let mut tracker = PathTracker::default();
+    for event in Scanner::new(bytes) {
+        match event {
+            Ok(event) if event.start() < offset => tracker.update(bytes, &event),
+            _ => break
+        }
+    }
+    tracker.into_path()
+}
+
+enum Frame {
+    /// Array with the index of the next element
+    Array(usize),
+    /// Object, whose keys are pushed as they are scanned, and whether a key is on the path without its value
+    Object(bool)
+}
+
+/// Wraps a [`Scanner`] and keeps track of the path to each event.
+///
+/// After each event [`Walker::path`] points at the value the event belongs to,
+/// keys included, as the path of a key is the path of its value.
+pub(crate) struct Walker<'a> {
+    text: &'a str,
+    scanner: Scanner<'a>,
+    path: Path,
+    frames: Vec<Frame>,
+    finished: bool
+}
+
+impl<'a> Walker<'a> {
+    pub(crate) fn new(text: &'a str) -> Self {
+        Walker::with_options(text, &Options::default())
+    }
+
+    pub(crate) fn with_options(text: &'a str, options: &Options) -> Self {
+        Walker {
+            text,
+            scanner: Scanner::with_options(text.as_bytes(), options),
+            path: Path::new(),
+            frames: Vec::new(),
+            finished: false
+        }
+    }
+
+    pub(crate) fn text(&self) -> &'a str {
+        self.text
+    }
+
+    /// Path to the value of the most recent event
+    pub(crate) fn path(&self) -> &Path {
+        &self.path
+    }
+
+    /// See [`Scanner::start_of`]
+    pub(crate) fn start_of(&self, event: &Event) -> usize {
+        self.scanner.start_of(event)
+    }
+
+    /// See [`Scanner::on_progress`]
+    pub(crate) fn on_progress(&mut self, progress: impl FnMut(usize) + 'a) {
+        self.scanner.on_progress(progress);
+    }
+
+    fn enter_value(&mut self) {
+        match self.frames.last_mut() {
+            Some(Frame::Array(i)) => {
+                self.path.push(Index::Array(*i));
+                *i += 1;
+            }
+            Some(Frame::Object(pending)) => *pending = false,
+            None => {}
+        }
+    }
+
+    /// Takes a key off the path whose value never came, as lenient and truncated scans allow
+    fn drop_pending_key(&mut self) {
+        if let Some(Frame::Object(pending)) = self.frames.last_mut() {
+            if *pending {
+                *pending = false;
+                self.path.pop();
+            }
+        }
+    }
+}
+
+impl Iterator for Walker<'_> {
+    type Item = Result<Event, Error>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.finished {
+            // The previous event completed a value, so it is no longer part of the path
+            if !self.frames.is_empty() {
+                self.path.pop();
+            }
+            self.finished = false;
+        }
+
+        let event = match self.scanner.next()? {
+            Ok(event) => event,
+            Err(e) => return Some(Err(e))
+        };
+        match &event {
+            Event::StartObject(_) => {
+                self.enter_value();
+                self.frames.push(Frame::Object(false));
+            }
+            Event::StartArray(_) => {
+                self.enter_value();
+                self.frames.push(Frame::Array(0));
+            }
+            Event::EndObject(_) | Event::EndArray(_) => {
+                self.drop_pending_key();
+                self.frames.pop();
+                self.finished = true;
+            }
+            Event::Key(span) => {
+                self.drop_pending_key();
+                self.path.push(Index::Object(key_text(self.text.as_bytes(), span, self.scanner.json5)));
+                if let Some(Frame::Object(pending)) = self.frames.last_mut() {
+                    *pending = true;
+                }
+            }
+            Event::Scalar(_) => {
+                self.enter_value();
+                self.finished = true;
+            }
+            Event::Comma(_) => self.drop_pending_key()
+        }
+        Some(Ok(event))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn validate(text: &str) -> Result<(), Error> {
+        Scanner::new(text.as_bytes()).try_for_each(|event| event.map(|_| ()))
+    }
+
+    fn error(text: &str) -> (ErrorKind, usize) {
+        let error = validate(text).unwrap_err();
+        (error.kind(), error.offset())
+    }
+
+    #[test]
+    fn validation() {
+        for valid in ["0", " -0.5e+3 ", r#""é\n""#, "[]", "{}", r#"[{"a": [true, false, null]}, "}"]"#] {
+            assert!(validate(valid).is_ok(), "{}", valid);
+        }
+
+        for invalid in ["", "01", "1.", "[1,]", r#"{"a": 1,}"#, r#"{"a" 1}"#, r#"{1: 2}"#, "[1 2]", "[", "]", "{]", "nul", "truex", r#""\x""#, "\"\u{1}\"", "1 2"] {
+            assert!(validate(invalid).is_err(), "{}", invalid);
+        }
+
+        // Tests error kinds and offsets
+        assert_eq!(error("[1,]"), (ErrorKind::OrphanSquareBrace, 3));
+        assert_eq!(error(r#"{"a" 1}"#), (ErrorKind::InvalidCharacter, 5));
+        assert_eq!(error(r#"["a\x"]"#), (ErrorKind::InvalidCharacter, 4));
+        assert_eq!(error("[1, 2"), (ErrorKind::UnexpectedEnd, 5));
+        assert_eq!(error("[1 2]"), (ErrorKind::InvalidCharacter, 3));
+        assert_eq!(error("[1] ,"), (ErrorKind::InvalidComma, 4));
+    }
+
+    #[test]
+    fn max_depth() {
+        let options = Options::new().max_depth(2);
+        let validate = |text: &str| Scanner::with_options(text.as_bytes(), &options).try_for_each(|event| event.map(|_| ()));
+
+        assert!(validate(r#"[{"a": 1}, [2], 3]"#).is_ok());
+        let error = validate(r#"[{"a": [1]}]"#).unwrap_err();
+        assert_eq!((error.kind(), error.offset()), (ErrorKind::DepthExceeded, 7));
+
+        // Tests the limit also applies when scanning leniently
+        let lenient = Options::new().max_depth(1).lenient(true);
+        assert!(Scanner::with_options(b"[[[[", &lenient).any(|event| event.is_err()));
+        assert_eq!(validate(&"[".repeat(100_000)).unwrap_err().offset(), 2);
+    }
+
+    #[test]
+    fn limits() {
+        let scan = |text: &str, limits: Limits| Scanner::with_options(text.as_bytes(), &Options::new().limits(limits)).try_for_each(|event| event.map(|_| ()));
+
+        assert!(scan("[1, [2]]", Limits::new().max_bytes(8).max_nodes(4)).is_ok());
+        assert_eq!(scan("[1, [2]]", Limits::new().max_bytes(7)).unwrap_err().kind(), ErrorKind::SizeExceeded);
+
+        let error = scan("[1, [2]]", Limits::new().max_nodes(3)).unwrap_err();
+        assert_eq!((error.kind(), error.offset()), (ErrorKind::NodesExceeded, 5));
+
+        let error = scan("[1, [2]]", Limits::new().time_budget(std::time::Duration::ZERO)).unwrap_err();
+        assert_eq!((error.kind(), error.offset()), (ErrorKind::TimeExceeded, 0));
+    }
+
+    #[test]
+    fn truncated() {
+        let truncated = Options::new().allow_truncated(true);
+        let at_end = |json: &str| path_to(json.as_bytes(), json.len(), &truncated).map(|p| p.to_string());
+
+        assert_eq!(at_end("").unwrap(), "$");
+        assert_eq!(at_end(r#"[1, {"a": [tru"#).unwrap(), "$.1.a.0");
+        assert_eq!(at_end(r#"[1, {"a": [true, -"#).unwrap(), "$.1.a.1");
+        assert_eq!(at_end(r#"{"a": "b\"#).unwrap(), "$.a");
+        assert_eq!(at_end(r#"{"a": {"b"#).unwrap(), "$.a.b");
+        assert_eq!(at_end(r#"{"a": [1, "#).unwrap(), "$.a.1");
+
+        // Tests errors other than the early end are still reported
+        assert_eq!(at_end("[1, 2}").unwrap_err().kind(), ErrorKind::OrphanCurlyBrace);
+        assert_eq!(at_end("[1, tx").unwrap_err().kind(), ErrorKind::InvalidCharacter);
+    }
+
+    #[test]
+    fn comments() {
+        let comments = Options::new().allow_comments(true);
+        let validate = |text: &str| Scanner::with_options(text.as_bytes(), &comments).try_for_each(|event| event.map(|_| ()));
+
+        assert!(validate("// leading\n[1, /* ] */ 2] // trailing").is_ok());
+        assert!(validate("[1/**/,2//]\n]").is_ok());
+        assert!(validate("[1] /* unterminated").is_err());
+        assert!(validate("[1, / 2]").is_err());
+        assert!(validate("[1, // 2]").is_err());
+
+        // Tests comments are rejected by default
+        assert!(validate("[1]").is_ok());
+        assert!(Scanner::new(b"[1] // c").try_for_each(|event| event.map(|_| ())).is_err());
+    }
+
+    #[test]
+    fn json5() {
+        let json5 = Options::new().json5(true);
+        let validate = |text: &str| Scanner::with_options(text.as_bytes(), &json5).try_for_each(|event| event.map(|_| ()));
+
+        for valid in ["[0xFF, -0x1a, +1, .5, 5., 1e3, Infinity, -Infinity, NaN,]", "{a: 1, $b_2: 'c', 'd': \"e\",}", "'a\\\nb'", "'\\x41\\''", "\u{feff}[1,\u{a0}2] // c"] {
+            assert!(validate(valid).is_ok(), "{}", valid);
+        }
+
+        for invalid in ["[,]", "{,}", "[1,,]", ".", "0x", "+", "Inf", "{1a: 2}", "'a", "'\\x4'", "[1]]"] {
+            assert!(validate(invalid).is_err(), "{}", invalid);
+        }
+
+        // Tests key names in paths
+        let json = "{unquoted: {'single': [1, 2,],},}";
+        assert_eq!(path_to(json.as_bytes(), json.find('2').unwrap(), &json5).unwrap().to_string(), "$.unquoted.single.1");
+
+        // Tests json5 is rejected by default
+        assert!(Scanner::new(b"{a: 1}").try_for_each(|event| event.map(|_| ())).is_err());
+    }
+
+    #[test]
+    fn trailing_commas() {
+        let trailing = Options::new().allow_trailing_commas(true);
+        let validate = |text: &str| Scanner::with_options(text.as_bytes(), &trailing).try_for_each(|event| event.map(|_| ()));
+
+        assert!(validate("[1, [2,],]").is_ok());
+        assert!(validate(r#"{"a": {"b": 1,},}"#).is_ok());
+        for invalid in ["[,]", "{,}", "[1,,]", "1,", "{a: 1,}", "[1,] // c"] {
+            assert!(validate(invalid).is_err(), "{}", invalid);
+        }
+    }
+
+    #[test]
+    fn events() {
+        let json = r#"{"a": [1, "b"]}"#;
+        let events: Vec<Event> = Scanner::new(json.as_bytes()).map(Result::unwrap).collect();
+
+        assert_eq!(events, [
+            Event::StartObject(0),
+            Event::Key(1..4),
+            Event::StartArray(6),
+            Event::Scalar(7..8),
+            Event::Comma(8),
+            Event::Scalar(10..13),
+            Event::EndArray(6..14),
+            Event::EndObject(0..15)
+        ]);
+    }
+
+    #[test]
+    fn unescaped() {
+        assert_eq!(unescape(r#"a\"b\\c\/\n\té"#), Ok(String::from("a\"b\\c/\n\té")));
+        assert_eq!(unescape(r"\ud83d\ude00\u00e9"), Ok(String::from("😀é")));
+        assert_eq!(unescape("é"), Ok(String::from("é")));
+
+        // Tests unpaired surrogates and invalid escapes
+        assert_eq!(unescape(r"\ud83dx\ude00"), Ok(String::from("\u{fffd}x\u{fffd}")));
+        assert_eq!(unescape(r"\ud83dA"), Ok(String::from("\u{fffd}A")));
+        assert_eq!(unescape(r"ab\x"), Err(2));
+        assert_eq!(unescape(r"\u12"), Err(0));
+        assert_eq!(unescape(r"\u+123"), Err(0));
+        assert_eq!(unescape("a\\"), Err(1));
+
+        // Tests the escapes json5 adds
+        assert_eq!(unescape_with(r"\x41\v\0\'\é\q", true), Ok(String::from("A\u{b}\0'éq")));
+        assert_eq!(unescape_with("a\\\nb\\\r\nc\\\u{2028}d", true), Ok(String::from("abcd")));
+        assert_eq!(unescape_with(r"\x4", true), Err(0));
+        assert_eq!(unescape_with(r"\1", true), Err(0));
+        assert_eq!(unescape(r"\x41"), Err(0));
+        assert_eq!(path_to(br#"{"\x41": 1}"#, 8, &Options::new().json5(true)).unwrap().to_string(), "$.A");
+    }
+}
@@ -0,0 +1,410 @@
+use core::ops::Range;
+use alloc::vec::Vec;
+
+use crate::error::Error;
+use crate::index::{Cursor, DocumentIndex};
+use crate::locate::Kind;
+use crate::options::Options;
+use crate::path::{Index, Path};
+use crate::spanned::{SpannedNode, SpannedValue};
+
+/// Keywords whose subschemas all apply to the same value as the schema containing them
+const APPLICATORS: [&str; 3] = ["allOf", "anyOf", "oneOf"];
+
+/// Subschema of a [`Schema`] that applies to a value in a document
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SchemaLocation<'a> {
+    /// Path to the subschema within the schema document
+    pub path: Path,
+    /// Span of the subschema in the schema text
+    pub span: Range<usize>,
+    /// Raw text of the `title` keyword without its quotes, if the subschema has one
+    pub title: Option<&'a str>,
+    /// Raw text of the `description` keyword without its quotes, if the subschema has one
+    pub description: Option<&'a str>
+}
+
+/// Key a [`Schema`] allows in an object, offered by [`Schema::complete_keys`]
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct KeyCompletion<'a> {
+    /// Key without its quotes, as written in `properties`
+    pub key: &'a str,
+    /// Raw text of the `description` of the key's subschema without its quotes, if it has one
+    pub description: Option<&'a str>
+}
+
+/// Keys to offer at an offset in an object, found by [`Schema::complete_keys`]
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct KeyCompletions<'a> {
+    /// Span to replace with the chosen key, the key being typed or an empty span at the offset
+    pub span: Range<usize>,
+    /// Allowed keys not yet in the object, in the order the schema lists them
+    pub keys: Vec<KeyCompletion<'a>>
+}
+
+/// JSON Schema parsed with the span of every subschema, for finding which parts of it apply to a value in a document.
+///
+/// Follows `properties`, `additionalProperties`, `items`, `prefixItems` and `additionalItems` down into a document,
+/// expanding local `$ref`s such as `#/$defs/name` and the subschemas of `allOf`, `anyOf` and `oneOf`.
+/// Remote references and `patternProperties` are not followed.
+///
+/// # Examples
+///
+/// ```
+/// use jsonposition::Schema;
+///
+/// let schema = Schema::parse(r##"{
+///     "properties": {"users": {"items": {"$ref": "#/$defs/user"}}},
+///     "$defs": {"user": {"properties": {"email": {"description": "Where to send mail"}}}}
+/// }"##).expect("Invalid schema");
+///
+/// let json = r#"{"users": [{"email": "a@example.com"}]}"#;
+///
+/// let locations = schema.locations_at(json, json.find("a@").unwrap()).expect("Invalid JSON");
+/// assert_eq!(locations[0].path.to_pointer(), "/$defs/user/properties/email");
+/// assert_eq!(locations[0].description, Some("Where to send mail"));
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Schema<'a> {
+    text: &'a str,
+    root: SpannedValue<'a>
+}
+
+impl<'a> Schema<'a> {
+    /// Parses the raw text of a schema.
+    ///
+    /// # Errors
+    ///
+    /// Returns an [`Error`](crate::Error) if the schema isn't valid json.
+    pub fn parse(text: &'a str) -> Result<Self, Error> {
+        Ok(Schema { text, root: SpannedValue::parse(text)? })
+    }
+
+    /// Finds the subschemas that apply to the value at a path in a document, in the order they are reached.
+    /// Subschemas reached by a `$ref` or an applicator come after the subschema they are reached from.
+    ///
+    /// Returns nothing if the schema doesn't describe the path.
+    pub fn locations_for(&self, path: &Path) -> Vec<SchemaLocation<'a>> {
+        let mut current = Vec::new();
+        self.expand(Path::new(), &mut current);
+
+        for index in path {
+            let mut next = Vec::new();
+            for schema in &current {
+                for child in self.step(schema, index) {
+                    self.expand(child, &mut next);
+                }
+            }
+            current = next;
+        }
+
+        current.into_iter().filter_map(|path| self.location(path)).collect()
+    }
+
+    /// Finds the subschemas that apply to the innermost value containing a byte offset in a raw json string.
+    /// An offset in a key gets the subschemas of the member's value. See [`Schema::locations_for`].
+    ///
+    /// # Errors
+    ///
+    /// Returns an [`Error`](crate::Error) if the input json is invalid.
+    pub fn locations_at(&self, text: &str, offset: usize) -> Result<Vec<SchemaLocation<'a>>, Error> {
+        Ok(self.locations_for(&crate::path_bytes(text.as_bytes(), offset)?))
+    }
+
+    /// Finds the keys the schema allows in the object at a byte offset in a raw json string that it doesn't already have,
+    /// for completion in an editor. Keys come from the `properties` of every subschema applying to the object.
+    ///
+    /// The document is scanned leniently since it is usually being edited. If the offset is in or right after a key,
+    /// that key is taken as being typed, so it is replaced and isn't counted as present.
+    /// An offset at the end of an object with no closing `}` is in that object.
+    /// Returns `None` if the offset isn't in an object or a key.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use jsonposition::Schema;
+    ///
+    /// let schema = Schema::parse(r#"{"properties": {"name": {"description": "Full name"}, "email": {}, "age": {}}}"#).expect("Invalid schema");
+    ///
+    /// let json = r#"{"email": "a@example.com", "na"#;
+    /// let completions = schema.complete_keys(json, json.len()).expect("Invalid JSON").unwrap();
+    ///
+    /// assert_eq!(&json[completions.span.clone()], r#""na"#);
+    /// assert_eq!(completions.keys.iter().map(|completion| completion.key).collect::<Vec<_>>(), ["name", "age"]);
+    /// assert_eq!(completions.keys[0].description, Some("Full name"));
+    /// ```
+    ///
+    /// # Errors
+    ///
+    /// Returns an [`Error`](crate::Error) if the input json can't be scanned even leniently, such as when it is too deep.
+    pub fn complete_keys(&self, text: &str, offset: usize) -> Result<Option<KeyCompletions<'a>>, Error> {
+        let index = DocumentIndex::with_options(text, &Options::new().lenient(true))?;
+        let typed_key = |offset: usize| index.nodes_containing(offset).pop().filter(|(_, _, kind)| *kind == Kind::ObjectKey);
+        // An object still being written is closed where the text ends, so the values ending there are all unclosed but the innermost
+        let unclosed = |offset: usize| {
+            let mut ending: Vec<_> = index.nodes_containing(offset.checked_sub(1)?).into_iter().filter(|(_, span, _)| span.end == offset).collect();
+            let (_, span, kind) = ending.last()?;
+            let last = text.as_bytes()[offset - 1];
+            match kind {
+                Kind::Object if last != b'}' => ending.pop(),
+                Kind::String if span.len() < 2 || last != b'"' => None,
+                _ => ending.into_iter().rev().skip(1).find(|(_, _, kind)| *kind == Kind::Object)
+            }
+        };
+
+        let (path, span) = match typed_key(offset).or_else(|| typed_key(offset.checked_sub(1)?).filter(|(_, span, _)| span.end == offset)) {
+            // The path of a key is the path of its value
+            Some((path, span, _)) => (path.parent().unwrap_or_default(), span),
+            None => match index.nodes_containing(offset).pop().or_else(|| unclosed(offset)) {
+                Some((path, _, Kind::Object)) => (path, offset..offset),
+                _ => return Ok(None)
+            }
+        };
+
+        let present: Vec<Index> = index
+            .children(&path)
+            .unwrap_or_default()
+            .into_iter()
+            .filter(|child| child.key_span.as_ref() != Some(&span))
+            .map(|child| child.index)
+            .collect();
+
+        let mut keys: Vec<KeyCompletion<'a>> = Vec::new();
+        for location in self.locations_for(&path) {
+            let Some(SpannedNode::Object(properties)) = self.get(&location.path).and_then(|schema| keyword(schema, "properties")).map(|value| &value.node) else { continue };
+            for property in properties {
+                let index = Index::Object(property.key.clone());
+                if present.contains(&index) || keys.iter().any(|completion| completion.key == property.key) {
+                    continue;
+                }
+                let mut path = path.clone();
+                path.push(index);
+                let description = self.locations_for(&path).into_iter().find_map(|location| location.description);
+                let key = &self.text[property.key_span.start + 1..property.key_span.end - 1];
+                keys.push(KeyCompletion { key, description });
+            }
+        }
+
+        Ok(Some(KeyCompletions { span, keys }))
+    }
+
+    /// Subschema at a path, looking up array indices as keys as well since a JSON Pointer can't tell them apart
+    fn get(&self, path: &Path) -> Option<&SpannedValue<'a>> {
+        path.iter().try_fold(&self.root, |value, index| match (&value.node, index) {
+            (SpannedNode::Object(members), Index::Array(i)) => {
+                members.iter().rev().find(|member| member.key.parse() == Ok(*i)).map(|member| &member.value)
+            }
+            _ => value.child(index)
+        })
+    }
+
+    /// Adds a subschema to `found` with everything it refers to, skipping subschemas already found
+    fn expand(&self, path: Path, found: &mut Vec<Path>) {
+        let Some(schema) = self.get(&path) else { return };
+        if found.contains(&path) {
+            return;
+        }
+        found.push(path.clone());
+
+        if let Some(target) = keyword(schema, "$ref").and_then(|reference| reference.raw.strip_prefix("\"#")?.strip_suffix('"')?.parse::<Path>().ok()) {
+            self.expand(target, found);
+        }
+        for applicator in APPLICATORS {
+            if let Some(SpannedNode::Array(schemas)) = keyword(schema, applicator).map(|value| &value.node) {
+                for i in 0..schemas.len() {
+                    let mut path = path.clone();
+                    path.push(applicator);
+                    path.push(i);
+                    self.expand(path, found);
+                }
+            }
+        }
+    }
+
+    /// Subschemas applying to a child of the values a subschema applies to
+    fn step(&self, path: &Path, index: &Index) -> Vec<Path> {
+        let Some(schema) = self.get(path) else { return Vec::new() };
+        let child = |keys: &[Index]| {
+            let mut child = path.clone();
+            child.extend(keys.iter().cloned());
+            child
+        };
+
+        match index {
+            Index::Object(key) => {
+                let named = keyword(schema, "properties").is_some_and(|properties| properties.child(index).is_some());
+                if named {
+                    Vec::from([child(&["properties".into(), key.as_str().into()])])
+                } else if keyword(schema, "additionalProperties").is_some() {
+                    Vec::from([child(&["additionalProperties".into()])])
+                } else {
+                    Vec::new()
+                }
+            }
+            Index::Array(i) => {
+                let positional = |name: &str| match keyword(schema, name).map(|value| &value.node) {
+                    Some(SpannedNode::Array(schemas)) => Some(schemas.len()),
+                    _ => None
+                };
+                match (positional("prefixItems"), positional("items")) {
+                    (Some(len), _) if *i < len => Vec::from([child(&["prefixItems".into(), (*i).into()])]),
+                    (_, Some(len)) if *i < len => Vec::from([child(&["items".into(), (*i).into()])]),
+                    (_, Some(_)) if keyword(schema, "additionalItems").is_some() => Vec::from([child(&["additionalItems".into()])]),
+                    (_, None) if keyword(schema, "items").is_some() => Vec::from([child(&["items".into()])]),
+                    _ => Vec::new()
+                }
+            }
+        }
+    }
+
+    fn location(&self, path: Path) -> Option<SchemaLocation<'a>> {
+        let schema = self.get(&path)?;
+        let text = |name: &str| keyword(schema, name).filter(|value| value.kind() == Kind::String).map(|value| &value.raw[1..value.raw.len() - 1]);
+        Some(SchemaLocation { span: schema.span.clone(), title: text("title"), description: text("description"), path })
+    }
+}
+
+/// Value of a keyword in a subschema, `None` for a boolean schema
+fn keyword<'s, 'a>(schema: &'s SpannedValue<'a>, name: &str) -> Option<&'s SpannedValue<'a>> {
+    schema.child(&Index::Object(name.into()))
+}
+
+/// Value at a JSON Pointer, looking up a key made of digits when a pointer segment is taken as an array index on an object,
+/// and taking the last member if a key is repeated
+fn cursor_to<'i>(index: &'i DocumentIndex, path: &Path) -> Option<Cursor<'i>> {
+    path.iter().try_fold(index.cursor()?, |cursor, segment| match (cursor.kind(), segment) {
+        (Kind::Array, Index::Array(i)) => cursor.children().nth(*i),
+        (Kind::Object, Index::Array(i)) => cursor.children().filter(|child| matches!(child.path().last(), Some(Index::Object(key)) if key.parse() == Ok(*i))).last(),
+        (Kind::Object, Index::Object(_)) => cursor.children().filter(|child| child.path().last() == Some(segment)).last(),
+        _ => None
+    })
+}
+
+/// Finds the spans in a raw json string of the values at the instance locations reported by a JSON Schema validator,
+/// so validation errors can be underlined in the source. The spans are returned in the same order as the pointers,
+/// scanning the document only once.
+///
+/// Pointers are JSON Pointers such as `/users/3/email`, optionally written as a URI fragment starting with `#`.
+/// A pointer that can't be parsed or has no value in the document gets `None`.
+///
+/// # Examples
+///
+/// ```
+/// use jsonposition::spans_of_pointers;
+///
+/// let json = r#"{"users": [{"email": 3}], "count": "x"}"#;
+///
+/// let spans = spans_of_pointers(json, &["/users/0/email", "#/count", "/missing"]).expect("Invalid JSON");
+/// assert_eq!(spans.iter().map(|span| span.clone().map(|span| &json[span])).collect::<Vec<_>>(), [Some("3"), Some(r#""x""#), None]);
+/// ```
+///
+/// # Errors
+///
+/// Returns an [`Error`](crate::Error) if the input json is invalid.
+pub fn spans_of_pointers(text: &str, pointers: &[&str]) -> Result<Vec<Option<Range<usize>>>, Error> {
+    let index = DocumentIndex::new(text)?;
+    Ok(pointers
+        .iter()
+        .map(|pointer| {
+            let pointer = pointer.strip_prefix('#').unwrap_or(pointer);
+            // Only JSON Pointers are accepted, not dot paths
+            let path: Path = match pointer {
+                "" => Path::new(),
+                _ if pointer.starts_with('/') => pointer.parse().ok()?,
+                _ => return None
+            };
+            cursor_to(&index, &path).map(|cursor| cursor.span())
+        })
+        .collect())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn locations() {
+        let schema = Schema::parse(r##"{
+            "title": "Root",
+            "properties": {
+                "a": {"type": "array", "prefixItems": [{"title": "First"}], "items": {"$ref": "#/$defs/item"}},
+                "b": {"allOf": [{"$ref": "#"}, {"title": "B"}]}
+            },
+            "additionalProperties": {"title": "Other"},
+            "$defs": {"item": {"title": "Item", "anyOf": [{"$ref": "#/$defs/item"}]}}
+        }"##).unwrap();
+        let pointers = |path: &str| schema.locations_for(&path.parse().unwrap()).into_iter().map(|location| location.path.to_pointer()).collect::<Vec<_>>();
+
+        assert_eq!(pointers("$"), [""]);
+        assert_eq!(pointers("$.a.0"), ["/properties/a/prefixItems/0"]);
+        assert_eq!(pointers("$.a.3"), ["/properties/a/items", "/$defs/item", "/$defs/item/anyOf/0"]);
+        assert_eq!(pointers("$.b"), ["/properties/b", "/properties/b/allOf/0", "", "/properties/b/allOf/1"]);
+        assert_eq!(pointers("$.b.a.0"), ["/properties/a/prefixItems/0"]);
+        assert_eq!(pointers("$.c"), ["/additionalProperties"]);
+        assert!(pointers("$.c.d").is_empty());
+
+        let titles = schema.locations_for(&"$.b".parse().unwrap()).into_iter().map(|location| location.title).collect::<Vec<_>>();
+        assert_eq!(titles, [None, None, Some("Root"), Some("B")]);
+
+        // Tests draft 7 tuples and offsets
+        let schema = Schema::parse(r#"{"items": [{"title": "x"}], "additionalItems": {"title": "y"}}"#).unwrap();
+        let json = "[1, 2]";
+        assert_eq!(schema.locations_at(json, 1).unwrap()[0].title, Some("x"));
+        assert_eq!(schema.locations_at(json, 4).unwrap()[0].title, Some("y"));
+        assert!(schema.locations_at("[1,]", 1).is_err());
+        assert!(Schema::parse("{").is_err());
+    }
+
+    #[test]
+    fn pointers() {
+        let json = r#" {"a": [1, {"0": "x"}], "b~/": null} "#;
+
+        let spans = spans_of_pointers(json, &["", "#", "/a/1/0", "/b~0~1", "/a/2", "/a/1/0/0", "$.a", "/a~2"]).unwrap();
+        assert_eq!(spans.into_iter().map(|span| span.map(|span| &json[span])).collect::<Vec<_>>(), [
+            Some(json.trim()),
+            Some(json.trim()),
+            Some(r#""x""#),
+            Some("null"),
+            None,
+            None,
+            None,
+            None
+        ]);
+        assert!(spans_of_pointers("[1,]", &[""]).is_err());
+    }
+
+    #[test]
+    fn completions() {
+        let schema = Schema::parse(r##"{
+            "properties": {"a": {"$ref": "#/$defs/a"}, "b": {}},
+            "allOf": [{"properties": {"b": {}, "c": {}}}],
+            "$defs": {"a": {"description": "An a", "properties": {"d": {}}}}
+        }"##).unwrap();
+        let complete = |json: &str, offset: usize| {
+            schema.complete_keys(json, offset).unwrap().map(|completions| (json[completions.span.clone()].to_string(), completions.keys))
+        };
+        let keys = |json: &str, offset: usize| complete(json, offset).unwrap().1.into_iter().map(|completion| completion.key).collect::<Vec<_>>();
+
+        assert_eq!(keys("{}", 1), ["a", "b", "c"]);
+        assert_eq!(complete("{}", 1).unwrap().1[0], KeyCompletion { key: "a", description: Some("An a") });
+        assert_eq!(keys(r#"{"b": 1, }"#, 9), ["a", "c"]);
+        assert_eq!(keys(r#"{"a": {}}"#, 7), ["d"]);
+
+        // Tests replacing the key being typed, complete or not
+        assert_eq!(complete(r#"{"b": 1, "a"}"#, 11).unwrap().0, r#""a""#);
+        assert_eq!(keys(r#"{"b": 1, "a"}"#, 12), ["a", "c"]);
+        assert_eq!(keys(r#"{"a": 1, "b"#, 11), ["b", "c"]);
+
+        // Tests the end of an object with no closing brace is in it
+        assert_eq!(keys("{", 1), ["a", "b", "c"]);
+        assert_eq!(keys(r#"{"a": 1, "#, 9), ["b", "c"]);
+        assert_eq!(keys(r#"{"a": {"#, 7), ["d"]);
+        assert_eq!(keys(r#"{"b": {}"#, 8), ["a", "c"]);
+        assert_eq!(keys(r#"{"a": {"b": 1"#, 13), ["d"]);
+        assert_eq!(complete(r#"{"b": "x"#, 8), None);
+        assert_eq!(complete(r#"{"b": {}}"#, 9), None);
+
+        assert_eq!(complete("[1]", 1), None);
+        assert_eq!(complete(r#"{"a": 1}"#, 6), None);
+    }
+}
@@ -0,0 +1,278 @@
+use core::ops::Range;
+use alloc::collections::BTreeMap;
+use alloc::string::String;
+use alloc::vec::Vec;
+
+use crate::error::Error;
+use crate::locate::Kind;
+use crate::path::Path;
+use crate::scan::{key_text, Event, Walker};
+
+/// Object key found by [`find_key`] or [`find_key_by`]
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct KeyMatch {
+    /// Path to the value of the member, ending in the key
+    pub path: Path,
+    /// Span of the key including its quotes, starting at its offset
+    pub span: Range<usize>
+}
+
+/// Finds every object key equal to `name` in a raw json string, with its span and the path to its value,
+/// in the order they appear. Keys are compared without their quotes, the same as in a [`Path`].
+///
+/// # Examples
+///
+/// ```
+/// use jsonposition::find_key;
+///
+/// let json = r#"[{"name": "a"}, {"id": 2, "child": {"name": "b"}}]"#;
+///
+/// let found = find_key(json, "name").expect("Invalid JSON");
+/// assert_eq!(found.iter().map(|key| key.path.to_string()).collect::<Vec<_>>(), ["$.0.name", "$.1.child.name"]);
+/// assert_eq!(found[0].span, 2..8);
+/// ```
+///
+/// # Errors
+///
+/// Returns an [`Error`](crate::Error) if the input json is invalid.
+pub fn find_key(text: &str, name: &str) -> Result<Vec<KeyMatch>, Error> {
+    find_key_by(text, |key| key == name)
+}
+
+/// Finds every object key the predicate returns `true` for in a raw json string. See [`find_key`].
+///
+/// # Examples
+///
+/// ```
+/// use jsonposition::find_key_by;
+///
+/// let json = r#"{"id": 1, "parent_id": 2, "name": "c"}"#;
+///
+/// let found = find_key_by(json, |key| key.ends_with("id")).expect("Invalid JSON");
+/// assert_eq!(found.len(), 2);
+/// ```
+///
+/// # Errors
+///
+/// Returns an [`Error`](crate::Error) if the input json is invalid.
+pub fn find_key_by(text: &str, mut predicate: impl FnMut(&str) -> bool) -> Result<Vec<KeyMatch>, Error> {
+    let bytes = text.as_bytes();
+    let mut walker = Walker::new(text);
+    let mut found = Vec::new();
+
+    while let Some(event) = walker.next() {
+        if let Event::Key(span) = event? {
+            if predicate(&key_text(bytes, &span, false)) {
+                found.push(KeyMatch { path: walker.path().clone(), span });
+            }
+        }
+    }
+
+    Ok(found)
+}
+
+/// String or number found by [`find_values`] or [`find_values_by`]
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ValueMatch {
+    pub path: Path,
+    /// Span of the value, including the quotes of a string
+    pub span: Range<usize>,
+    /// [`Kind::String`] or [`Kind::Number`]
+    pub kind: Kind
+}
+
+/// Finds every string or number in a raw json string containing `pattern`, with its span and path,
+/// in the order they appear. Strings are searched without their quotes and numbers by their raw text.
+/// Object keys aren't searched, see [`find_key`] for those.
+///
+/// # Examples
+///
+/// ```
+/// use jsonposition::find_values;
+///
+/// let json = r#"{"files": ["main.rs", "lib.rs", 105], "size": 1024}"#;
+///
+/// let found = find_values(json, "10").expect("Invalid JSON");
+/// assert_eq!(found.iter().map(|value| value.path.to_string()).collect::<Vec<_>>(), ["$.files.2", "$.size"]);
+/// assert_eq!(&json[found[1].span.clone()], "1024");
+/// ```
+///
+/// # Errors
+///
+/// Returns an [`Error`](crate::Error) if the input json is invalid.
+pub fn find_values(text: &str, pattern: &str) -> Result<Vec<ValueMatch>, Error> {
+    find_values_by(text, |value| value.contains(pattern))
+}
+
+/// Finds every string or number the predicate returns `true` for in a raw json string. See [`find_values`].
+///
+/// A predicate can wrap any matcher, such as a regex.
+///
+/// # Examples
+///
+/// ```
+/// use jsonposition::find_values_by;
+///
+/// let json = r#"["main.rs", "README.md", "lib.rs"]"#;
+///
+/// let found = find_values_by(json, |value| value.ends_with(".rs")).expect("Invalid JSON");
+/// assert_eq!(found.len(), 2);
+/// ```
+///
+/// # Errors
+///
+/// Returns an [`Error`](crate::Error) if the input json is invalid.
+pub fn find_values_by(text: &str, mut predicate: impl FnMut(&str) -> bool) -> Result<Vec<ValueMatch>, Error> {
+    let bytes = text.as_bytes();
+    let mut walker = Walker::new(text);
+    let mut found = Vec::new();
+
+    while let Some(event) = walker.next() {
+        if let Event::Scalar(span) = event? {
+            let kind = Kind::of_scalar(&bytes[span.clone()]);
+            let matched = match kind {
+                Kind::String => predicate(&key_text(bytes, &span, false)),
+                Kind::Number => predicate(&text[span.clone()]),
+                _ => false
+            };
+            if matched {
+                found.push(ValueMatch { path: walker.path().clone(), span, kind });
+            }
+        }
+    }
+
+    Ok(found)
+}
+
+/// Key repeated in an object, found by [`duplicate_keys`]
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct DuplicateKey {
+    /// Path to the object containing the key
+    pub path: Path,
+    /// Key without its quotes, with its escape sequences decoded
+    pub key: String,
+    /// Span of the first occurrence of the key, including its quotes
+    pub first: Range<usize>,
+    /// Span of the repeated key, including its quotes
+    pub duplicate: Range<usize>
+}
+
+/// Finds every key repeated in the same object in a raw json string, in the order the repeats appear.
+/// A key appearing three times is reported twice, each time against its first occurrence.
+///
+/// Most parsers silently keep either the first or the last value of a repeated key.
+///
+/// # Examples
+///
+/// ```
+/// use jsonposition::duplicate_keys;
+///
+/// let json = r#"{"a": 1, "b": {"c": 2, "c": 3}}"#;
+///
+/// let found = duplicate_keys(json).expect("Invalid JSON");
+/// assert_eq!(found.len(), 1);
+/// assert_eq!((found[0].path.to_string(), found[0].key.as_str()), ("$.b".to_string(), "c"));
+/// assert_eq!((found[0].first.clone(), found[0].duplicate.clone()), (15..18, 23..26));
+/// ```
+///
+/// # Errors
+///
+/// Returns an [`Error`](crate::Error) if the input json is invalid.
+pub fn duplicate_keys(text: &str) -> Result<Vec<DuplicateKey>, Error> {
+    let bytes = text.as_bytes();
+    let mut walker = Walker::new(text);
+    // First span of every key seen in each open object, `None` for arrays
+    let mut open: Vec<Option<BTreeMap<String, Range<usize>>>> = Vec::new();
+    let mut found = Vec::new();
+
+    while let Some(event) = walker.next() {
+        match event? {
+            Event::StartObject(_) => open.push(Some(BTreeMap::new())),
+            Event::StartArray(_) => open.push(None),
+            Event::EndObject(_) | Event::EndArray(_) => {
+                open.pop();
+            }
+            Event::Key(span) => {
+                if let Some(Some(keys)) = open.last_mut() {
+                    let key = key_text(bytes, &span, false);
+                    match keys.get(&key) {
+                        Some(first) => found.push(DuplicateKey {
+                            path: walker.path().parent().unwrap_or_default(),
+                            key,
+                            first: first.clone(),
+                            duplicate: span
+                        }),
+                        None => {
+                            keys.insert(key, span);
+                        }
+                    }
+                }
+            }
+            Event::Scalar(_) | Event::Comma(_) => {}
+        }
+    }
+
+    Ok(found)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn keys() {
+        let json = r#"{"a": {"a": [{"b": "a"}, {"a": null}]}, "c": {}}"#;
+
+        let found = find_key(json, "a").unwrap();
+        assert_eq!(found.iter().map(|key| key.path.to_string()).collect::<Vec<_>>(), ["$.a", "$.a.a", "$.a.a.1.a"]);
+        for key in &found {
+            assert_eq!(&json[key.span.clone()], r#""a""#);
+            assert_eq!(crate::path_bytes(json.as_bytes(), key.span.start + 1).unwrap(), key.path);
+        }
+
+        assert!(find_key(json, "d").unwrap().is_empty());
+        assert_eq!(find_key_by(json, |_| true).unwrap().len(), 5);
+        assert!(find_key(r#"{"a": 1,}"#, "a").is_err());
+    }
+
+    #[test]
+    fn values() {
+        let json = r#"{"a1": [1, "x1y", true, null, {"b": -10.5}], "c": "a1"}"#;
+
+        let found = find_values(json, "1").unwrap();
+        assert_eq!(
+            found.iter().map(|value| (value.path.to_string(), &json[value.span.clone()], value.kind)).collect::<Vec<_>>(),
+            [
+                ("$.a1.0".to_string(), "1", Kind::Number),
+                ("$.a1.1".to_string(), r#""x1y""#, Kind::String),
+                ("$.a1.4.b".to_string(), "-10.5", Kind::Number),
+                ("$.c".to_string(), r#""a1""#, Kind::String)
+            ]
+        );
+
+        // Tests that keys and other scalars are skipped, and quotes aren't searched
+        assert!(find_values(json, "true").unwrap().is_empty());
+        assert!(find_values(json, "\"").unwrap().is_empty());
+        assert_eq!(find_values_by(json, |value| value.is_empty()).unwrap(), []);
+        assert!(find_values("[1,]", "1").is_err());
+    }
+
+    #[test]
+    fn duplicates() {
+        let json = r#"[{"a": 1, "b": {"a": 2}, "a": 3, "a": {"a": 4, "b": 5, "a": 6}}]"#;
+        let found = duplicate_keys(json).unwrap();
+
+        assert_eq!(
+            found.iter().map(|key| (key.path.to_string(), key.key.as_str(), key.first.start, key.duplicate.start)).collect::<Vec<_>>(),
+            [
+                ("$.0".to_string(), "a", 2, 25),
+                ("$.0".to_string(), "a", 2, 33),
+                ("$.0.a".to_string(), "a", 39, 55)
+            ]
+        );
+        assert!(found.iter().all(|key| &json[key.first.clone()] == r#""a""# && &json[key.duplicate.clone()] == r#""a""#));
+
+        assert!(duplicate_keys(r#"{"a": {"b": 1}, "b": [{"a": 2}]}"#).unwrap().is_empty());
+        assert!(duplicate_keys(r#"{"a": 1, "a": }"#).is_err());
+    }
+}
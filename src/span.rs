@@ -0,0 +1,515 @@
+use core::ops::Range;
+use alloc::string::String;
+use alloc::vec::Vec;
+
+use crate::error::Error;
+use crate::lines::indent_at;
+use crate::locate::Kind;
+use crate::options::Options;
+use crate::path::Path;
+use crate::scan::{Event, Scanner, Walker};
+
+/// Finds the byte range of the value at a path in a raw json string.
+/// The reverse of [`path`](crate::path), useful for jumping to a path typed by a user.
+/// The last member is taken if a key is repeated, as serde_json does.
+///
+/// Returns `None` if the document has no value at the path.
+///
+/// # Examples
+///
+/// ```
+/// use jsonposition::{span_of, Path};
+///
+/// let json = r#"[null, 9, {"a": [1, 2]}]"#;
+/// let path: Path = "$.2.a".parse().unwrap();
+///
+/// let span = span_of(json, &path).expect("Invalid JSON").unwrap();
+/// assert_eq!(&json[span], "[1, 2]");
+/// ```
+///
+/// # Errors
+///
+/// Returns an [`Error`](crate::Error) if the input json is invalid.
+pub fn span_of(text: &str, path: &Path) -> Result<Option<Range<usize>>, Error> {
+    let mut walker = Walker::new(text);
+    let mut found = None;
+
+    // Keeps scanning once found to validate the rest of the document, and for later members with the same key
+    while let Some(event) = walker.next() {
+        match event? {
+            Event::Scalar(span) | Event::EndObject(span) | Event::EndArray(span) if path.starts_with(walker.path()) => {
+                if walker.path() == path {
+                    found = Some(span);
+                } else if found.as_ref().is_some_and(|found| !span.contains(&found.start)) {
+                    // A repeated key on the way to the path replaces the member the value was found in
+                    found = None;
+                }
+            }
+            _ => {}
+        }
+    }
+
+    Ok(found)
+}
+
+/// Finds the raw text of the value at a path in a raw json string, exactly as it is written in the source.
+/// Shorthand for slicing the text with [`span_of`].
+///
+/// Returns `None` if the document has no value at the path.
+///
+/// # Examples
+///
+/// ```
+/// use jsonposition::get_raw;
+///
+/// let json = r#"{"a": [1,  2.50], "b": "\u00e9"}"#;
+///
+/// assert_eq!(get_raw(json, &"$.a".parse().unwrap()).expect("Invalid JSON"), Some("[1,  2.50]"));
+/// assert_eq!(get_raw(json, &"$.b".parse().unwrap()).expect("Invalid JSON"), Some(r#""\u00e9""#));
+/// assert_eq!(get_raw(json, &"$.c".parse().unwrap()).expect("Invalid JSON"), None);
+/// ```
+///
+/// # Errors
+///
+/// Returns an [`Error`](crate::Error) if the input json is invalid.
+pub fn get_raw<'a>(text: &'a str, path: &Path) -> Result<Option<&'a str>, Error> {
+    Ok(span_of(text, path)?.map(|span| &text[span]))
+}
+
+/// Copies the value at a path in a raw json string out as a standalone document,
+/// with the indentation of the line it starts on taken off every line after the first, so it starts at column 0.
+/// Use [`get_raw`] for the value exactly as it is written.
+///
+/// Returns `None` if the document has no value at the path.
+///
+/// # Examples
+///
+/// ```
+/// use jsonposition::extract;
+///
+/// let json = "{\n    \"a\": {\n        \"b\": [1, 2]\n    }\n}";
+///
+/// let extracted = extract(json, &"$.a".parse().unwrap()).expect("Invalid JSON").unwrap();
+/// assert_eq!(extracted, "{\n    \"b\": [1, 2]\n}");
+/// ```
+///
+/// # Errors
+///
+/// Returns an [`Error`](crate::Error) if the input json is invalid.
+pub fn extract(text: &str, path: &Path) -> Result<Option<String>, Error> {
+    let Some(span) = span_of(text, path)? else { return Ok(None) };
+    let indent = indent_at(text, span.start);
+
+    // Strings can't hold a raw line break, so every line after the first starts between tokens
+    let mut lines = text[span].split('\n');
+    let mut extracted = String::from(lines.next().unwrap_or_default());
+    for line in lines {
+        extracted.push('\n');
+        extracted.push_str(line.strip_prefix(indent).unwrap_or_else(|| line.trim_start_matches([' ', '\t'])));
+    }
+
+    Ok(Some(extracted))
+}
+
+/// Finds the byte range of the innermost value containing a byte offset in a raw json string.
+/// Offsets on an object key or between elements belong to the enclosing object or array.
+///
+/// Returns `None` if the offset is outside of the root value.
+///
+/// # Examples
+///
+/// ```
+/// use jsonposition::value_span;
+///
+/// let json = r#"[null, 9, {"a": "bcd"}]"#;
+///
+/// let span = value_span(json, json.find("c").unwrap()).expect("Invalid JSON").unwrap();
+/// assert_eq!(&json[span], r#""bcd""#);
+///
+/// let span = value_span(json, json.find("a").unwrap()).expect("Invalid JSON").unwrap();
+/// assert_eq!(&json[span], r#"{"a": "bcd"}"#);
+/// ```
+///
+/// # Errors
+///
+/// Returns an [`Error`](crate::Error) if the input json is invalid.
+pub fn value_span(text: &str, offset: usize) -> Result<Option<Range<usize>>, Error> {
+    let mut found = None;
+
+    // Inner containers end before outer ones,
+    // so the first span to contain the offset is the innermost.
+    for event in Scanner::new(text.as_bytes()) {
+        match event? {
+            Event::Scalar(span) | Event::EndObject(span) | Event::EndArray(span) if found.is_none() && span.contains(&offset) => {
+                found = Some(span);
+            }
+            _ => {}
+        }
+    }
+
+    Ok(found)
+}
+
+/// Finds the raw text of the innermost value containing a byte offset in a raw json string, exactly as it is written in the source.
+/// Shorthand for slicing the text with [`value_span`], so offsets on an object key give the enclosing object.
+///
+/// Returns `None` if the offset is outside of the root value.
+///
+/// # Examples
+///
+/// ```
+/// use jsonposition::value_at;
+///
+/// let json = r#"[null, 9, {"a": [1,  2]}]"#;
+///
+/// assert_eq!(value_at(json, json.find("2").unwrap()).expect("Invalid JSON"), Some("2"));
+/// assert_eq!(value_at(json, json.find(",  ").unwrap()).expect("Invalid JSON"), Some("[1,  2]"));
+/// ```
+///
+/// # Errors
+///
+/// Returns an [`Error`](crate::Error) if the input json is invalid.
+pub fn value_at(text: &str, offset: usize) -> Result<Option<&str>, Error> {
+    Ok(value_span(text, offset)?.map(|span| &text[span]))
+}
+
+/// Finds the path of the smallest value or key containing the whole of a byte range in a raw json string,
+/// such as the selection in an editor. A selection spanning several elements has the path of their parent,
+/// and one within an object key has the path of the value of that member, the same as [`locate`](crate::locate).
+///
+/// An empty range is treated as a single offset. Returns `None` if the range is not within the root value.
+///
+/// # Examples
+///
+/// ```
+/// use jsonposition::path_for_range;
+///
+/// let json = r#"[null, 9, {"a": [1, 2, 3]}]"#;
+/// let start = json.find("1").unwrap();
+///
+/// let path = path_for_range(json, start, start + 1).expect("Invalid JSON").unwrap();
+/// assert_eq!(path.to_string(), "$.2.a.0");
+///
+/// let path = path_for_range(json, start, start + 4).expect("Invalid JSON").unwrap();
+/// assert_eq!(path.to_string(), "$.2.a");
+/// ```
+///
+/// # Errors
+///
+/// Returns an [`Error`](crate::Error) if the input json is invalid.
+pub fn path_for_range(text: &str, start: usize, end: usize) -> Result<Option<Path>, Error> {
+    let (start, end) = (start.min(end), start.max(end));
+    let contains = |span: &Range<usize>| span.start <= start && end <= span.end && start < span.end;
+
+    let mut walker = Walker::new(text);
+    let mut found = None;
+
+    // Inner containers end before outer ones, so the first span to contain the range is the smallest
+    while let Some(event) = walker.next() {
+        match event? {
+            Event::Scalar(span) | Event::EndObject(span) | Event::EndArray(span) | Event::Key(span) if found.is_none() && contains(&span) => {
+                found = Some(walker.path().clone());
+            }
+            _ => {}
+        }
+    }
+
+    Ok(found)
+}
+
+/// Finds the offset of the bracket or brace matching the one at a byte offset in a raw json string,
+/// for jumping to the other end of an object or array.
+///
+/// Returns `None` if the offset is not on a `{`, `}`, `[` or `]`, including one inside a string.
+///
+/// # Examples
+///
+/// ```
+/// use jsonposition::matching_delimiter;
+///
+/// let json = r#"{"a": [1, "]"]}"#;
+///
+/// assert_eq!(matching_delimiter(json, 6).expect("Invalid JSON"), Some(13));
+/// assert_eq!(matching_delimiter(json, 14).expect("Invalid JSON"), Some(0));
+/// assert_eq!(matching_delimiter(json, 11).expect("Invalid JSON"), None);
+/// ```
+///
+/// # Errors
+///
+/// Returns an [`Error`](crate::Error) if the input json is invalid.
+pub fn matching_delimiter(text: &str, offset: usize) -> Result<Option<usize>, Error> {
+    let mut found = None;
+
+    for event in Scanner::new(text.as_bytes()) {
+        match event? {
+            Event::EndObject(span) | Event::EndArray(span) if found.is_none() => {
+                if span.start == offset {
+                    found = Some(span.end - 1);
+                } else if span.end - 1 == offset {
+                    found = Some(span.start);
+                }
+            }
+            _ => {}
+        }
+    }
+
+    Ok(found)
+}
+
+/// Finds the byte ranges enclosing an offset in a raw json string, from the innermost key or value out to the root,
+/// for expanding a selection in an editor. An object member adds a range from its key to the end of its value
+/// between the value and its object.
+///
+/// Returns an empty list if the offset is outside of the root value.
+///
+/// # Examples
+///
+/// ```
+/// use jsonposition::selection_ranges;
+///
+/// let json = r#"{"a": [1, 2]}"#;
+///
+/// let ranges: Vec<&str> = selection_ranges(json, json.find("2").unwrap()).expect("Invalid JSON").into_iter().map(|range| &json[range]).collect();
+/// assert_eq!(ranges, ["2", "[1, 2]", r#""a": [1, 2]"#, json]);
+/// ```
+///
+/// # Errors
+///
+/// Returns an [`Error`](crate::Error) if the input json is invalid.
+pub fn selection_ranges(text: &str, offset: usize) -> Result<Vec<Range<usize>>, Error> {
+    let mut ranges = Vec::new();
+    // Key of the next value, and the keys of all open containers
+    let mut pending_key: Option<Range<usize>> = None;
+    let mut keys: Vec<Option<Range<usize>>> = Vec::new();
+
+    // Inner values end before outer ones, so ranges are found innermost first
+    for event in Scanner::new(text.as_bytes()) {
+        let (span, key) = match event? {
+            Event::StartObject(_) | Event::StartArray(_) => {
+                keys.push(pending_key.take());
+                continue;
+            }
+            Event::Scalar(span) => (span, pending_key.take()),
+            Event::EndObject(span) | Event::EndArray(span) => (span, keys.pop().flatten()),
+            Event::Key(span) => {
+                if span.contains(&offset) {
+                    ranges.push(span.clone());
+                }
+                pending_key = Some(span);
+                continue;
+            }
+            Event::Comma(_) => continue
+        };
+
+        if span.contains(&offset) {
+            ranges.push(span.clone());
+        }
+        if let Some(key) = key.filter(|key| (key.start..span.end).contains(&offset)) {
+            ranges.push(key.start..span.end);
+        }
+    }
+
+    Ok(ranges)
+}
+
+/// Finds the byte range of every object, array and string in a raw json string that spans more than one line,
+/// for offering folding in an editor. Ranges are sorted by where they start, so containers come before what they contain.
+///
+/// # Examples
+///
+/// ```
+/// use jsonposition::folding_ranges;
+///
+/// let json = "{\n  \"a\": [1, 2],\n  \"b\": [\n    3\n  ]\n}";
+///
+/// let ranges: Vec<&str> = folding_ranges(json).expect("Invalid JSON").into_iter().map(|range| &json[range]).collect();
+/// assert_eq!(ranges, [json, "[\n    3\n  ]"]);
+/// ```
+///
+/// # Errors
+///
+/// Returns an [`Error`](crate::Error) if the input json is invalid.
+pub fn folding_ranges(text: &str) -> Result<Vec<Range<usize>>, Error> {
+    folding_ranges_with(text, &Options::default())
+}
+
+/// Finds the byte range of every object, array and string that spans more than one line,
+/// in a raw json string scanned with the given [`Options`].
+///
+/// # Errors
+///
+/// Returns an [`Error`](crate::Error) if the input json is invalid for the given options.
+pub fn folding_ranges_with(text: &str, options: &Options) -> Result<Vec<Range<usize>>, Error> {
+    let bytes = text.as_bytes();
+    let mut ranges = Vec::new();
+
+    for event in Scanner::with_options(bytes, options) {
+        match event? {
+            Event::EndObject(span) | Event::EndArray(span) if bytes[span.clone()].contains(&b'\n') => ranges.push(span),
+            // Only a line continuation in a json5 string can span lines
+            Event::Scalar(span) if Kind::of_scalar(&bytes[span.clone()]) == Kind::String && bytes[span.clone()].contains(&b'\n') => ranges.push(span),
+            _ => {}
+        }
+    }
+
+    // Inner containers end first, sort them after the ones containing them
+    ranges.sort_by_key(|range| range.start);
+    Ok(ranges)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn spans() {
+        let json = r#"[9, {"field1": "b", "field2": [null, null, 87, 4], "field3": "file.txt"}]"#;
+
+        let span = |p: &str| span_of(json, &p.parse().unwrap()).expect("Invalid JSON").map(|s| &json[s]);
+
+        assert_eq!(span("$"), Some(json));
+        assert_eq!(span("$.0"), Some("9"));
+        assert_eq!(span("$.1.field1"), Some(r#""b""#));
+        assert_eq!(span("$.1.field2"), Some("[null, null, 87, 4]"));
+        assert_eq!(span("/1/field2/2"), Some("87"));
+
+        // Tests missing paths
+        assert_eq!(span("$.2"), None);
+        assert_eq!(span("$.1.field4"), None);
+        assert_eq!(span("$.0.field1"), None);
+
+        // Tests repeated keys give the last member, as serde_json does
+        let json = r#"{"a": {"b": 1}, "c": [{"d": 2}, 3], "a": {"e": 4}, "c": [5]}"#;
+        let span = |p: &str| span_of(json, &p.parse().unwrap()).expect("Invalid JSON").map(|s| &json[s]);
+        assert_eq!(span("$.a"), Some(r#"{"e": 4}"#));
+        assert_eq!(span("$.a.e"), Some("4"));
+        assert_eq!(span("$.a.b"), None);
+        assert_eq!(span("$.c.0"), Some("5"));
+        assert_eq!(span("$.c.1"), None);
+    }
+
+    #[test]
+    fn raw() {
+        let json = r#"[9, {"field1": "b\"", "field2": [null,  87.0]}]"#;
+        let raw = |p: &str| get_raw(json, &p.parse().unwrap()).unwrap();
+
+        assert_eq!(raw("$.1.field1"), Some(r#""b\"""#));
+        assert_eq!(raw("$.1.field2"), Some("[null,  87.0]"));
+        assert_eq!(raw("$.2"), None);
+        assert!(get_raw("[1,]", &Path::new()).is_err());
+    }
+
+    #[test]
+    fn extracted() {
+        let json = "{\n\t\"a\": [\n\t\t1,\n\t\t{ \"b\": \"x  y\" }\n\t],\n\t\"c\": 2 }";
+        let extracted = |p: &str| extract(json, &p.parse().unwrap()).unwrap();
+
+        assert_eq!(extracted("$.a").as_deref(), Some("[\n\t1,\n\t{ \"b\": \"x  y\" }\n]"));
+        assert_eq!(extracted("$.a.1").as_deref(), Some("{ \"b\": \"x  y\" }"));
+        assert_eq!(extracted("$.c").as_deref(), Some("2"));
+        assert_eq!(extracted("$").as_deref(), Some(json));
+        assert_eq!(extracted("$.d"), None);
+        assert!(extract("[1,]", &Path::new()).is_err());
+    }
+
+    #[test]
+    fn value_spans() {
+        let json = r#" [9, {"field1": "b", "field2": [null, null, 87, 4]}] "#;
+
+        let span = |offset: usize| value_span(json, offset).expect("Invalid JSON").map(|s| &json[s]);
+
+        assert_eq!(span(json.find("87").unwrap() + 1), Some("87"));
+        assert_eq!(span(json.find("null").unwrap()), Some("null"));
+        assert_eq!(span(json.find(", null").unwrap()), Some("[null, null, 87, 4]"));
+        assert_eq!(span(json.find("field1").unwrap()), Some(r#"{"field1": "b", "field2": [null, null, 87, 4]}"#));
+        assert_eq!(span(1), Some(json.trim()));
+
+        // Tests offsets outside of the root value
+        assert_eq!(span(0), None);
+        assert_eq!(span(1000), None);
+    }
+
+    #[test]
+    fn values_at() {
+        let json = r#" [9, {"field1": "b", "field2": [null, 87]}] "#;
+        let value = |offset: usize| value_at(json, offset).unwrap();
+
+        assert_eq!(value(json.find("87").unwrap() + 1), Some("87"));
+        assert_eq!(value(json.find("field1").unwrap()), Some(r#"{"field1": "b", "field2": [null, 87]}"#));
+        assert_eq!(value(0), None);
+        assert!(value_at("[1,]", 1).is_err());
+    }
+
+    #[test]
+    fn ranges() {
+        let json = r#" [9, {"field1": "b", "field2": [null, null, 87, 4]}] "#;
+
+        let path = |s: &str, len: usize| {
+            let start = json.find(s).unwrap();
+            path_for_range(json, start, start + len).expect("Invalid JSON").map(|p| p.to_string())
+        };
+
+        assert_eq!(path("87", 2).as_deref(), Some("$.1.field2.2"));
+        assert_eq!(path("87", 0).as_deref(), Some("$.1.field2.2"));
+        assert_eq!(path("null, null", 10).as_deref(), Some("$.1.field2"));
+        assert_eq!(path("ield1", 3).as_deref(), Some("$.1.field1"));
+        assert_eq!(path(r#""field1": "b""#, 13).as_deref(), Some("$.1"));
+        assert_eq!(path("9, {", 10).as_deref(), Some("$"));
+
+        // Tests ranges outside of the root value
+        assert_eq!(path(" [", 2), None);
+        assert_eq!(path_for_range(json, 1000, 1001).unwrap(), None);
+    }
+
+    #[test]
+    fn folding() {
+        let json = "[\n  {\"a\": [1, 2]},\n  {\n    \"b\": {}\n  }\n]";
+
+        let ranges: Vec<&str> = folding_ranges(json).unwrap().into_iter().map(|range| &json[range]).collect();
+        assert_eq!(ranges, [json, "{\n    \"b\": {}\n  }"]);
+
+        assert!(folding_ranges("[1, 2]").unwrap().is_empty());
+        assert!(folding_ranges("[\n").is_err());
+
+        // Tests strings continued over lines
+        let json5 = "['a\\\nb', 'c']";
+        let ranges = folding_ranges_with(json5, &Options::new().json5(true)).unwrap();
+        assert_eq!(ranges, [0..json5.len(), 1..7]);
+    }
+
+    #[test]
+    fn selections() {
+        let json = r#" [9, {"field1": "b", "field2": [null, 87]}] "#;
+
+        let ranges = |s: &str| -> Vec<&str> {
+            selection_ranges(json, json.find(s).unwrap()).unwrap().into_iter().map(|range| &json[range]).collect()
+        };
+
+        let member = r#""field2": [null, 87]"#;
+        let object = r#"{"field1": "b", "field2": [null, 87]}"#;
+        assert_eq!(ranges("87"), ["87", "[null, 87]", member, object, json.trim()]);
+        assert_eq!(ranges("ield2"), [r#""field2""#, member, object, json.trim()]);
+        assert_eq!(ranges(": [null"), [member, object, json.trim()]);
+        assert_eq!(ranges(", {"), [json.trim()]);
+
+        // Tests offsets outside of the root value
+        assert!(selection_ranges(json, 0).unwrap().is_empty());
+    }
+
+    #[test]
+    fn delimiters() {
+        let json = r#" [{}, {"a}": [[]]}] "#;
+        let matching = |offset: usize| matching_delimiter(json, offset).unwrap();
+
+        assert_eq!(matching(1), Some(json.len() - 2));
+        assert_eq!(matching(json.len() - 2), Some(1));
+        assert_eq!(matching(2), Some(3));
+        assert_eq!(matching(json.find("[[").unwrap() + 1), Some(json.find("]]").unwrap()));
+
+        // Tests offsets not on a delimiter
+        assert_eq!(matching(json.find('}').unwrap() + 5), None);
+        assert_eq!(matching(json.find("a}").unwrap() + 1), None);
+        assert_eq!(matching(0), None);
+        assert_eq!(matching(100), None);
+    }
+}
@@ -0,0 +1,180 @@
+use core::ops::Range;
+use alloc::string::String;
+use alloc::vec::Vec;
+
+use crate::error::{Error, ErrorKind};
+use crate::locate::Kind;
+use crate::path::{Index, Path};
+use crate::scan::{key_text, Event, Scanner};
+
+/// Value in a [`SpannedValue`] tree, holding its elements or members if it is a container
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum SpannedNode<'a> {
+    Null,
+    Bool(bool),
+    /// Number, its text is the raw source so no precision is lost
+    Number,
+    /// String, its text is the raw source including quotes and escapes
+    String,
+    Array(Vec<SpannedValue<'a>>),
+    Object(Vec<SpannedMember<'a>>)
+}
+
+/// Member of an object in a [`SpannedValue`] tree
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SpannedMember<'a> {
+    /// Key without its quotes, the same as in a [`Path`]
+    pub key: String,
+    /// Span of the key including its quotes
+    pub key_span: Range<usize>,
+    pub value: SpannedValue<'a>
+}
+
+/// Json document parsed into a tree where every value carries its byte range in the source,
+/// and every object member the range of its key. Built in a single scan.
+///
+/// Scalars aren't decoded, their raw source text is kept instead.
+///
+/// # Examples
+///
+/// ```
+/// use jsonposition::{SpannedNode, SpannedValue};
+///
+/// let json = r#"{"a": [1, "b"]}"#;
+/// let root = SpannedValue::parse(json).expect("Invalid JSON");
+///
+/// let SpannedNode::Object(members) = &root.node else { unreachable!() };
+/// assert_eq!(members[0].key, "a");
+/// assert_eq!(&json[members[0].key_span.clone()], r#""a""#);
+///
+/// let element = root.get(&"$.a.1".parse().unwrap()).unwrap();
+/// assert_eq!(element.raw, r#""b""#);
+/// assert_eq!(element.span, 10..13);
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SpannedValue<'a> {
+    /// Span of the whole value
+    pub span: Range<usize>,
+    /// Raw source text of the value
+    pub raw: &'a str,
+    pub node: SpannedNode<'a>
+}
+
+/// Container still being parsed, with the key of the member whose value comes next
+struct Frame<'a> {
+    node: SpannedNode<'a>,
+    key: Option<(String, Range<usize>)>
+}
+
+impl<'a> SpannedValue<'a> {
+    /// Parses a raw json string into a tree of spanned values.
+    ///
+    /// # Errors
+    ///
+    /// Returns an [`Error`](crate::Error) if the input json is invalid.
+    pub fn parse(text: &'a str) -> Result<Self, Error> {
+        let bytes = text.as_bytes();
+        let mut stack: Vec<Frame> = Vec::new();
+        let mut root = None;
+
+        for event in Scanner::new(bytes) {
+            let value = match event? {
+                Event::StartObject(_) => {
+                    stack.push(Frame { node: SpannedNode::Object(Vec::new()), key: None });
+                    continue;
+                }
+                Event::StartArray(_) => {
+                    stack.push(Frame { node: SpannedNode::Array(Vec::new()), key: None });
+                    continue;
+                }
+                Event::Key(span) => {
+                    if let Some(frame) = stack.last_mut() {
+                        frame.key = Some((key_text(bytes, &span, false), span));
+                    }
+                    continue;
+                }
+                Event::Comma(_) => continue,
+                Event::Scalar(span) => {
+                    let node = match Kind::of_scalar(&bytes[span.clone()]) {
+                        Kind::Null => SpannedNode::Null,
+                        Kind::Bool => SpannedNode::Bool(bytes[span.start] == b't'),
+                        Kind::String => SpannedNode::String,
+                        _ => SpannedNode::Number
+                    };
+                    SpannedValue { raw: &text[span.clone()], span, node }
+                }
+                Event::EndObject(span) | Event::EndArray(span) => match stack.pop() {
+                    Some(frame) => SpannedValue { raw: &text[span.clone()], span, node: frame.node },
+                    None => continue
+                }
+            };
+
+            match stack.last_mut() {
+                Some(Frame { node: SpannedNode::Array(elements), .. }) => elements.push(value),
+                Some(Frame { node: SpannedNode::Object(members), key, .. }) => {
+                    if let Some((key, key_span)) = key.take() {
+                        members.push(SpannedMember { key, key_span, value });
+                    }
+                }
+                Some(_) => {}
+                None => root = Some(value)
+            }
+        }
+
+        // A successful scan always ends on a complete root value
+        root.ok_or_else(|| Error::new(ErrorKind::UnexpectedEnd, bytes, bytes.len()))
+    }
+
+    /// Kind of the value
+    pub fn kind(&self) -> Kind {
+        match self.node {
+            SpannedNode::Null => Kind::Null,
+            SpannedNode::Bool(_) => Kind::Bool,
+            SpannedNode::Number => Kind::Number,
+            SpannedNode::String => Kind::String,
+            SpannedNode::Array(_) => Kind::Array,
+            SpannedNode::Object(_) => Kind::Object
+        }
+    }
+
+    /// Element of an array or value of an object member, the latest one if a key is repeated.
+    pub fn child(&self, index: &Index) -> Option<&SpannedValue<'a>> {
+        match (&self.node, index) {
+            (SpannedNode::Array(elements), Index::Array(i)) => elements.get(*i),
+            (SpannedNode::Object(members), Index::Object(key)) => members.iter().rev().find(|member| member.key == *key).map(|member| &member.value),
+            _ => None
+        }
+    }
+
+    /// Value at a path below this one, `None` if there is none.
+    pub fn get(&self, path: &Path) -> Option<&SpannedValue<'a>> {
+        path.iter().try_fold(self, |value, index| value.child(index))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn tree() {
+        let json = r#" [9, {"field1": true, "field2": [null, "x"]}, {}] "#;
+        let root = SpannedValue::parse(json).unwrap();
+
+        assert_eq!(root.raw, json.trim());
+        assert_eq!(root.kind(), Kind::Array);
+
+        for (path, span, kind) in crate::all_paths(json).unwrap() {
+            let value = root.get(&path).unwrap();
+            assert_eq!((value.span.clone(), value.kind()), (span, kind), "{}", path);
+        }
+
+        let SpannedNode::Object(members) = &root.get(&"$.1".parse().unwrap()).unwrap().node else { panic!() };
+        assert_eq!(members.iter().map(|member| member.key.as_str()).collect::<Vec<_>>(), ["field1", "field2"]);
+        assert_eq!(&json[members[1].key_span.clone()], r#""field2""#);
+        assert_eq!(members[0].value.node, SpannedNode::Bool(true));
+
+        assert_eq!(root.get(&"$.3".parse().unwrap()), None);
+        assert!(SpannedValue::parse("[1,]").is_err());
+    }
+}
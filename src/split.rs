@@ -0,0 +1,209 @@
+use alloc::vec::Vec;
+use std::io::{Read, Write};
+
+use crate::parser::ParseEvent;
+use crate::path::Path;
+use crate::query::PathPattern;
+use crate::scan::key_text;
+use crate::stream::StreamScanner;
+
+/// Subtree being copied to an output
+struct Open<W> {
+    output: W,
+    /// Offset of the first byte of the subtree
+    start: usize,
+    /// Offset copied up to
+    written: usize
+}
+
+/// Splits json read from `reader` into the subtrees whose paths match `pattern`, without holding the whole document in memory.
+/// `open` is called with the path of each match, in document order, and the raw text of the match is written to the output it returns.
+/// Subtrees inside a match are part of it rather than matches of their own.
+///
+/// Only the text of the current match and of the value being scanned is held, so documents much larger than memory can be split.
+/// Returns the number of matches.
+///
+/// # Examples
+///
+/// ```
+/// use std::fs::File;
+/// use jsonposition::{split_reader, PathPattern};
+///
+/// let json = r#"{"records": [{"id": 1}, {"id": 2}], "total": 2}"#;
+/// let pattern: PathPattern = "$.records.*".parse().unwrap();
+/// let dir = std::env::temp_dir();
+///
+/// let count = split_reader(json.as_bytes(), &pattern, |path| File::create(dir.join(format!("{}.json", path)))).unwrap();
+/// assert_eq!(count, 2);
+/// assert_eq!(std::fs::read_to_string(dir.join("$.records.1.json")).unwrap(), r#"{"id": 2}"#);
+/// ```
+///
+/// # Errors
+///
+/// Returns any error from reading, opening or writing, or an [`Error`](crate::Error) converted to
+/// [`std::io::ErrorKind::InvalidData`] if the json is invalid. Outputs opened before an error may hold part of a match.
+pub fn split_reader<R, W, F>(mut reader: R, pattern: &PathPattern, mut open: F) -> std::io::Result<usize>
+where
+    R: Read,
+    W: Write,
+    F: FnMut(&Path) -> std::io::Result<W>
+{
+    let mut scanner = StreamScanner::new();
+    let mut buffer = vec![0; 8 * 1024];
+    // Text from `base` to the end of what has been fed, kept from the end of the last event or the end of what was written
+    let mut pending = Vec::new();
+    let mut base = 0;
+    let mut path = Path::new();
+    // Next index of each open array, `None` for objects
+    let mut frames: Vec<Option<usize>> = Vec::new();
+    let mut current: Option<Open<W>> = None;
+    let mut count = 0;
+
+    loop {
+        let len = match reader.read(&mut buffer) {
+            Ok(0) => {
+                scanner.finish()?;
+                0
+            }
+            Ok(len) => {
+                scanner.feed(&buffer[..len])?;
+                len
+            }
+            Err(e) if e.kind() == std::io::ErrorKind::Interrupted => continue,
+            Err(e) => return Err(e)
+        };
+        pending.extend_from_slice(&buffer[..len]);
+
+        let mut keep = base;
+        for event in scanner.events() {
+            let span = event.span();
+            keep = keep.max(span.end);
+            if let Some(open) = &mut current {
+                // Nothing inside a match is tracked, only where it ends
+                if matches!(event, ParseEvent::EndObject(_) | ParseEvent::EndArray(_)) && span.start == open.start {
+                    open.output.write_all(&pending[open.written - base..span.end - base])?;
+                    open.output.flush()?;
+                    current = None;
+                    leave(&frames, &mut path);
+                }
+                continue;
+            }
+
+            match event {
+                ParseEvent::Key(span) => path.push(key_text(&pending, &(span.start - base..span.end - base), false)),
+                ParseEvent::EndObject(_) | ParseEvent::EndArray(_) => {
+                    frames.pop();
+                    leave(&frames, &mut path);
+                }
+                ParseEvent::StartObject(_) | ParseEvent::StartArray(_) | ParseEvent::Value(..) => {
+                    if let Some(Some(i)) = frames.last_mut() {
+                        path.push(*i);
+                        *i += 1;
+                    }
+                    let scalar = matches!(event, ParseEvent::Value(..));
+                    if pattern.matches(&path) {
+                        count += 1;
+                        let mut output = open(&path)?;
+                        if scalar {
+                            output.write_all(&pending[span.start - base..span.end - base])?;
+                            output.flush()?;
+                        } else {
+                            current = Some(Open { output, start: span.start, written: span.start });
+                            continue;
+                        }
+                    }
+                    match event {
+                        ParseEvent::StartObject(_) => frames.push(None),
+                        ParseEvent::StartArray(_) => frames.push(Some(0)),
+                        _ => leave(&frames, &mut path)
+                    }
+                }
+            }
+        }
+
+        if let Some(open) = &mut current {
+            open.output.write_all(&pending[open.written - base..])?;
+            open.written = base + pending.len();
+            keep = open.written;
+        }
+        pending.drain(..keep - base);
+        base = keep;
+
+        if len == 0 {
+            return Ok(count);
+        }
+    }
+}
+
+/// Takes the finished value off the end of the path, unless it was the root
+fn leave(frames: &[Option<usize>], path: &mut Path) {
+    if !frames.is_empty() {
+        path.pop();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use alloc::rc::Rc;
+    use alloc::string::{String, ToString};
+    use core::cell::RefCell;
+
+    /// Reader returning at most `.1` bytes at a time
+    struct Chunked<'a>(&'a [u8], usize);
+
+    impl Read for Chunked<'_> {
+        fn read(&mut self, buf: &mut [u8]) -> std::io::Result<usize> {
+            let len = self.0.len().min(self.1).min(buf.len());
+            buf[..len].copy_from_slice(&self.0[..len]);
+            self.0 = &self.0[len..];
+            Ok(len)
+        }
+    }
+
+    /// Path and text of each part written so far
+    type Parts = Rc<RefCell<Vec<(String, Vec<u8>)>>>;
+
+    /// Writer appending to the last part
+    struct Part(Parts);
+
+    impl Write for Part {
+        fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+            self.0.borrow_mut().last_mut().unwrap().1.extend_from_slice(buf);
+            Ok(buf.len())
+        }
+
+        fn flush(&mut self) -> std::io::Result<()> {
+            Ok(())
+        }
+    }
+
+    fn split(text: &str, pattern: &str, chunk: usize) -> std::io::Result<Vec<(String, String)>> {
+        let parts = Parts::default();
+        let pattern = pattern.parse().unwrap();
+        let count = split_reader(Chunked(text.as_bytes(), chunk), &pattern, |path| {
+            parts.borrow_mut().push((path.to_string(), Vec::new()));
+            Ok(Part(parts.clone()))
+        })?;
+        assert_eq!(count, parts.borrow().len());
+        Ok(parts.take().into_iter().map(|(path, raw)| (path, String::from_utf8(raw).unwrap())).collect())
+    }
+
+    #[test]
+    fn parts() {
+        let text = r#" {"a": [1, {"b": "x\"]"}, [ ]], "c": {"a": [true]}, "d": -12.5e1} "#;
+        let pairs = |parts: &[(&str, &str)]| parts.iter().map(|(path, raw)| (path.to_string(), raw.to_string())).collect::<Vec<_>>();
+
+        for chunk in [1, 2, 3, 7, 100] {
+            assert_eq!(split(text, "$.a.*", chunk).unwrap(), pairs(&[("$.a.0", "1"), ("$.a.1", r#"{"b": "x\"]"}"#), ("$.a.2", "[ ]")]), "{}", chunk);
+            assert_eq!(split(text, "$..a", chunk).unwrap(), pairs(&[("$.a", r#"[1, {"b": "x\"]"}, [ ]]"#), ("$.c.a", "[true]")]), "{}", chunk);
+            assert_eq!(split(text, "$.d", chunk).unwrap(), pairs(&[("$.d", "-12.5e1")]), "{}", chunk);
+            assert_eq!(split(text, "$", chunk).unwrap(), pairs(&[("$", text.trim())]), "{}", chunk);
+        }
+        assert!(split(text, "$.e", 4).unwrap().is_empty());
+
+        let error = split(r#"{"a": [1, 2,]}"#, "$.a.*", 3).unwrap_err();
+        assert_eq!(error.kind(), std::io::ErrorKind::InvalidData);
+        assert!(split(r#"{"a": 1"#, "$.a", 3).is_err());
+    }
+}
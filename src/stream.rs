@@ -0,0 +1,504 @@
+use alloc::collections::VecDeque;
+use alloc::vec::Vec;
+#[cfg(feature = "std")]
+use std::io::Read;
+
+use crate::error::{Error, ErrorKind};
+use crate::lines::Position;
+use crate::locate::Kind;
+use crate::parser::ParseEvent;
+use crate::path::Path;
+use crate::scan::{decode_key, is_delimiter, is_whitespace, Event, Expect, PathTracker};
+
+/// Progress through a number, following the grammar in RFC 8259
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Number {
+    Minus,
+    Zero,
+    Integer,
+    Dot,
+    Fraction,
+    Exponent,
+    ExponentSign,
+    ExponentDigits
+}
+
+impl Number {
+    fn next(self, b: u8) -> Option<Number> {
+        match (self, b) {
+            (Number::Minus, b'0') => Some(Number::Zero),
+            (Number::Minus, b'1'..=b'9') => Some(Number::Integer),
+            (Number::Integer, b'0'..=b'9') => Some(Number::Integer),
+            (Number::Zero | Number::Integer, b'.') => Some(Number::Dot),
+            (Number::Dot | Number::Fraction, b'0'..=b'9') => Some(Number::Fraction),
+            (Number::Zero | Number::Integer | Number::Fraction, b'e' | b'E') => Some(Number::Exponent),
+            (Number::Exponent, b'+' | b'-') => Some(Number::ExponentSign),
+            (Number::Exponent | Number::ExponentSign | Number::ExponentDigits, b'0'..=b'9') => Some(Number::ExponentDigits),
+            _ => None
+        }
+    }
+
+    fn is_complete(self) -> bool {
+        matches!(self, Number::Zero | Number::Integer | Number::Fraction | Number::ExponentDigits)
+    }
+}
+
+/// Token that has started but not yet ended
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum Token {
+    /// A string, whether it is a key, and how many bytes of an escape are left
+    String { key: bool, escape: u8 },
+    Number(Number),
+    /// `true`, `false` or `null`, with how much of it has been matched
+    Literal { word: &'static [u8], matched: usize }
+}
+
+/// Incremental scanner fed a json document a chunk at a time, as it arrives from a socket or other reader.
+///
+/// The scanner keeps only the state needed to continue, not the text itself,
+/// and can be asked for the path it is currently inside at any point.
+/// Offsets in its events and errors count every byte fed so far.
+///
+/// A key is only part of the path once the whole key has been fed,
+/// and a number at the end of the document is only complete once [`StreamScanner::finish`] is called.
+///
+/// # Examples
+///
+/// ```
+/// use jsonposition::StreamScanner;
+///
+/// let mut scanner = StreamScanner::new();
+/// scanner.feed(br#"{"a": [1, "#).expect("Invalid JSON");
+/// assert_eq!(scanner.path().to_string(), "$.a.1");
+///
+/// scanner.feed(br#"2]}"#).expect("Invalid JSON");
+/// scanner.finish().expect("Invalid JSON");
+/// assert!(scanner.path().is_root());
+/// assert_eq!(scanner.events().count(), 7);
+/// ```
+#[derive(Debug, Clone)]
+pub struct StreamScanner {
+    offset: usize,
+    position: Position,
+    /// Start offset of each open container, and whether it is an object
+    stack: Vec<(usize, bool)>,
+    expect: Expect,
+    token: Option<Token>,
+    token_start: usize,
+    /// Raw text of the key being scanned
+    key: Vec<u8>,
+    tracker: PathTracker,
+    events: VecDeque<ParseEvent>,
+    failed: Option<Error>
+}
+
+impl Default for StreamScanner {
+    fn default() -> Self {
+        StreamScanner::new()
+    }
+}
+
+impl StreamScanner {
+    /// Creates a scanner for strict json, before any input has been fed.
+    pub fn new() -> Self {
+        StreamScanner {
+            offset: 0,
+            position: Position::default(),
+            stack: Vec::new(),
+            expect: Expect::Value,
+            token: None,
+            token_start: 0,
+            key: Vec::new(),
+            tracker: PathTracker::default(),
+            events: VecDeque::new(),
+            failed: None
+        }
+    }
+
+    /// Scans the next chunk of the document.
+    ///
+    /// # Errors
+    ///
+    /// Returns an [`Error`](crate::Error) if the chunk makes the json invalid.
+    /// Once an error is returned, every later call returns it again.
+    pub fn feed(&mut self, chunk: &[u8]) -> Result<(), Error> {
+        if let Some(e) = &self.failed {
+            return Err(e.clone());
+        }
+        for b in chunk {
+            if let Err(e) = self.byte(*b) {
+                self.failed = Some(e.clone());
+                return Err(e);
+            }
+            self.advance(*b);
+        }
+        Ok(())
+    }
+
+    /// Marks the end of the document, completing a number at the end of it.
+    ///
+    /// # Errors
+    ///
+    /// Returns an [`Error`](crate::Error) if the document ended before its root value was complete.
+    pub fn finish(&mut self) -> Result<(), Error> {
+        if let Some(e) = &self.failed {
+            return Err(e.clone());
+        }
+        let result = match self.token.clone() {
+            Some(Token::Number(number)) if number.is_complete() => {
+                self.end_scalar();
+                Ok(())
+            }
+            Some(Token::Literal { word, matched }) if matched == word.len() => {
+                self.end_scalar();
+                Ok(())
+            }
+            Some(_) => Err(self.error(ErrorKind::UnexpectedEnd)),
+            None => Ok(())
+        };
+        let result = result.and_then(|_| match self.expect {
+            Expect::Done => Ok(()),
+            _ => Err(self.error(ErrorKind::UnexpectedEnd))
+        });
+        if let Err(e) = &result {
+            self.failed = Some(e.clone());
+        }
+        result
+    }
+
+    /// Path the scanner is currently inside, the same as [`path_bytes`](crate::path_bytes)
+    /// at the offset of the next byte, apart from keys not yet fed in full.
+    pub fn path(&self) -> &Path {
+        self.tracker.path()
+    }
+
+    /// Number of bytes fed so far
+    pub fn offset(&self) -> usize {
+        self.offset
+    }
+
+    /// Removes and returns the oldest event not yet taken.
+    pub fn next_event(&mut self) -> Option<ParseEvent> {
+        self.events.pop_front()
+    }
+
+    /// Removes and returns every event not yet taken, oldest first.
+    /// Events are kept until they are taken, so long running streams should take them regularly.
+    pub fn events(&mut self) -> alloc::collections::vec_deque::Drain<'_, ParseEvent> {
+        self.events.drain(..)
+    }
+
+    /// Whether the scanner is partway through an object key
+    #[cfg(feature = "std")]
+    fn in_key(&self) -> bool {
+        matches!(self.token, Some(Token::String { key: true, .. }))
+    }
+
+    /// Whether enough has been fed to know the path to `offset`.
+    /// A key containing the offset is part of the path, so it has to be fed to its end.
+    #[cfg(feature = "std")]
+    pub(crate) fn reached(&self, offset: usize) -> bool {
+        self.offset >= offset && !self.in_key()
+    }
+
+    /// Feeds as much of a chunk as is needed to know the path to `offset`
+    #[cfg(feature = "std")]
+    pub(crate) fn feed_to(&mut self, chunk: &[u8], offset: usize) -> Result<(), Error> {
+        let (before, after) = chunk.split_at(offset.saturating_sub(self.offset).min(chunk.len()));
+        self.feed(before)?;
+        for b in after {
+            if self.reached(offset) {
+                break;
+            }
+            self.feed(&[*b])?;
+        }
+        // Only the path is needed, so events are dropped to keep memory bounded
+        self.events.clear();
+        Ok(())
+    }
+
+    fn error(&self, kind: ErrorKind) -> Error {
+        Error::at(kind, self.offset, self.position)
+    }
+
+    fn advance(&mut self, b: u8) {
+        self.offset += 1;
+        if b == b'\n' {
+            self.position.line += 1;
+            self.position.column = 0;
+        } else if b & 0xc0 != 0x80 {
+            // Counts chars rather than bytes by skipping utf-8 continuation bytes
+            self.position.column += 1;
+        }
+    }
+
+    fn in_object(&self) -> bool {
+        matches!(self.stack.last(), Some((_, true)))
+    }
+
+    fn expects_value(&self) -> bool {
+        matches!(self.expect, Expect::Value | Expect::FirstValue)
+    }
+
+    fn after_value(&mut self) {
+        self.expect = if self.stack.is_empty() { Expect::Done } else { Expect::CommaOrEnd };
+    }
+
+    /// Emits the number or literal that was being scanned
+    fn end_scalar(&mut self) {
+        let kind = match self.token.take() {
+            Some(Token::Literal { word: b"null", .. }) => Kind::Null,
+            Some(Token::Literal { .. }) => Kind::Bool,
+            _ => Kind::Number
+        };
+        self.events.push_back(ParseEvent::Value(self.token_start..self.offset, kind));
+        self.after_value();
+    }
+
+    fn byte(&mut self, b: u8) -> Result<(), Error> {
+        match self.token.clone() {
+            Some(Token::String { key, escape }) => return self.string_byte(b, key, escape),
+            Some(Token::Number(number)) => match number.next(b) {
+                Some(next) => {
+                    self.token = Some(Token::Number(next));
+                    return Ok(());
+                }
+                None if number.is_complete() && is_delimiter(b) => self.end_scalar(),
+                None => return Err(self.error(ErrorKind::InvalidCharacter))
+            },
+            Some(Token::Literal { word, matched }) if matched < word.len() => {
+                if b != word[matched] {
+                    return Err(Error::at(ErrorKind::InvalidCharacter, self.token_start, self.position_of_token(matched)));
+                }
+                self.token = Some(Token::Literal { word, matched: matched + 1 });
+                return Ok(());
+            }
+            Some(Token::Literal { .. }) if is_delimiter(b) => self.end_scalar(),
+            Some(Token::Literal { .. }) => return Err(self.error(ErrorKind::InvalidCharacter)),
+            None => {}
+        }
+        self.structure(b)
+    }
+
+    /// Position of the start of the current token, which is `len` ascii bytes long so far
+    fn position_of_token(&self, len: usize) -> Position {
+        Position { line: self.position.line, column: self.position.column - len }
+    }
+
+    fn string_byte(&mut self, b: u8, key: bool, escape: u8) -> Result<(), Error> {
+        let escape = match (escape, b) {
+            (0, b'"') => {
+                self.token = None;
+                let span = self.token_start..self.offset + 1;
+                if key {
+                    self.tracker.key(decode_key(&self.key, false));
+                    self.key.clear();
+                    self.events.push_back(ParseEvent::Key(span));
+                    self.expect = Expect::Colon;
+                } else {
+                    self.events.push_back(ParseEvent::Value(span, Kind::String));
+                    self.after_value();
+                }
+                return Ok(());
+            }
+            (_, 0..=0x1f) => return Err(self.error(ErrorKind::InvalidCharacter)),
+            (0, b'\\') => u8::MAX,
+            (0, _) => 0,
+            (u8::MAX, b'"' | b'\\' | b'/' | b'b' | b'f' | b'n' | b'r' | b't') => 0,
+            (u8::MAX, b'u') => 4,
+            (1..=4, b) if b.is_ascii_hexdigit() => escape - 1,
+            _ => return Err(self.error(ErrorKind::InvalidCharacter))
+        };
+        if key {
+            self.key.push(b);
+        }
+        self.token = Some(Token::String { key, escape });
+        Ok(())
+    }
+
+    fn start_token(&mut self, token: Token) {
+        self.token = Some(token);
+        self.token_start = self.offset;
+    }
+
+    fn structure(&mut self, b: u8) -> Result<(), Error> {
+        let at = self.offset;
+        match b {
+            b if is_whitespace(b) => {}
+            b'{' | b'[' if self.expects_value() => {
+                let object = b == b'{';
+                self.stack.push((at, object));
+                if object {
+                    self.expect = Expect::FirstKey;
+                    self.tracker.update(b"", &Event::StartObject(at));
+                    self.events.push_back(ParseEvent::StartObject(at));
+                } else {
+                    self.expect = Expect::FirstValue;
+                    self.tracker.update(b"", &Event::StartArray(at));
+                    self.events.push_back(ParseEvent::StartArray(at));
+                }
+            }
+            b'}' if self.in_object() && matches!(self.expect, Expect::FirstKey | Expect::CommaOrEnd) => {
+                let (open, _) = self.stack.pop().unwrap_or_default();
+                self.after_value();
+                self.tracker.update(b"", &Event::EndObject(open..at + 1));
+                self.events.push_back(ParseEvent::EndObject(open..at + 1));
+            }
+            b']' if !self.in_object() && matches!(self.expect, Expect::FirstValue | Expect::CommaOrEnd) => {
+                let (open, _) = self.stack.pop().unwrap_or_default();
+                self.after_value();
+                self.tracker.update(b"", &Event::EndArray(open..at + 1));
+                self.events.push_back(ParseEvent::EndArray(open..at + 1));
+            }
+            b'}' => return Err(self.error(ErrorKind::OrphanCurlyBrace)),
+            b']' => return Err(self.error(ErrorKind::OrphanSquareBrace)),
+            b',' if self.expect == Expect::CommaOrEnd => {
+                self.expect = if self.in_object() { Expect::Key } else { Expect::Value };
+                self.tracker.update(b"", &Event::Comma(at));
+            }
+            b',' => return Err(self.error(ErrorKind::InvalidComma)),
+            b':' if self.expect == Expect::Colon => self.expect = Expect::Value,
+            b':' => return Err(self.error(ErrorKind::InvalidColon)),
+            b'"' if matches!(self.expect, Expect::Key | Expect::FirstKey) => self.start_token(Token::String { key: true, escape: 0 }),
+            b'"' if self.expects_value() => self.start_token(Token::String { key: false, escape: 0 }),
+            b'"' => return Err(self.error(ErrorKind::InvalidQuote)),
+            b'-' if self.expects_value() => self.start_token(Token::Number(Number::Minus)),
+            b'0' if self.expects_value() => self.start_token(Token::Number(Number::Zero)),
+            b'1'..=b'9' if self.expects_value() => self.start_token(Token::Number(Number::Integer)),
+            b't' | b'f' | b'n' if self.expects_value() => {
+                let word: &'static [u8] = match b {
+                    b't' => b"true",
+                    b'f' => b"false",
+                    _ => b"null"
+                };
+                self.start_token(Token::Literal { word, matched: 1 });
+            }
+            _ => return Err(self.error(ErrorKind::InvalidCharacter))
+        }
+        Ok(())
+    }
+}
+
+/// Constructs the path to a byte offset in json read from `reader`, without holding the whole document in memory.
+/// Reading stops as soon as the offset is reached, so only the json before it is validated.
+///
+/// # Examples
+///
+/// ```
+/// use jsonposition::path_from_reader;
+///
+/// let json = r#"[null, 9, {"a": "b"}]"#;
+///
+/// let path = path_from_reader(json.as_bytes(), json.find("b").unwrap()).expect("Invalid JSON");
+/// assert_eq!(path.to_string(), "$.2.a");
+/// ```
+///
+/// # Errors
+///
+/// Returns any error from reading, or an [`Error`](crate::Error) converted to [`std::io::ErrorKind::InvalidData`]
+/// if the json before the offset is invalid.
+#[cfg(feature = "std")]
+pub fn path_from_reader<R: Read>(mut reader: R, offset: usize) -> std::io::Result<Path> {
+    let mut scanner = StreamScanner::new();
+    let mut buffer = vec![0; 8 * 1024];
+    while !scanner.reached(offset) {
+        match reader.read(&mut buffer) {
+            Ok(0) => {
+                scanner.finish()?;
+                break;
+            }
+            Ok(len) => scanner.feed_to(&buffer[..len], offset)?,
+            Err(e) if e.kind() == std::io::ErrorKind::Interrupted => continue,
+            Err(e) => return Err(e)
+        }
+    }
+    Ok(scanner.path().clone())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn scan(text: &str, chunk: usize) -> Result<Vec<ParseEvent>, Error> {
+        let mut scanner = StreamScanner::new();
+        for part in text.as_bytes().chunks(chunk.max(1)) {
+            scanner.feed(part)?;
+        }
+        scanner.finish()?;
+        Ok(scanner.events().collect())
+    }
+
+    #[test]
+    fn matches_parser() {
+        let valid = [r#" [{}, {"é": "}\"é", "b": [[], 1, {"c": null}]}, -0.5e+3, true, false] "#, "1", r#""a""#, "0"];
+        for text in valid {
+            let parsed: Vec<ParseEvent> = crate::Parser::new(text).map(Result::unwrap).collect();
+            for chunk in 1..=4 {
+                assert_eq!(scan(text, chunk).unwrap(), parsed, "{}", text);
+            }
+        }
+
+        // Tests errors match the error of the whole text
+        let invalid = ["", "01", "1.", "[1,]", r#"{"a": 1,}"#, r#"{"a" 1}"#, "[1 2]", "[", "]", "{]", "nul", "nulx", "truex", r#""\x""#, "\"\u{1}\"", "1 2", "[1] ,", "[\n 1, é]"];
+        for text in invalid {
+            let expected = crate::Parser::new(text).find_map(Result::err).unwrap();
+            for chunk in 1..=3 {
+                assert_eq!(scan(text, chunk).unwrap_err(), expected, "{}", text);
+            }
+        }
+    }
+
+    /// Reader returning a single byte at a time
+    struct Trickle<'a>(&'a [u8]);
+
+    impl Read for Trickle<'_> {
+        fn read(&mut self, buf: &mut [u8]) -> std::io::Result<usize> {
+            match self.0.split_first() {
+                Some((b, rest)) if !buf.is_empty() => {
+                    buf[0] = *b;
+                    self.0 = rest;
+                    Ok(1)
+                }
+                _ => Ok(0)
+            }
+        }
+    }
+
+    #[test]
+    fn reader() {
+        let text = r#" [{}, {"é": "}", "b\"\u00e9": [[], 1, {"c": null}]}, 9] "#;
+        for offset in 0..=text.len() + 1 {
+            let expected = crate::path_bytes(text.as_bytes(), offset).unwrap();
+            assert_eq!(path_from_reader(text.as_bytes(), offset).unwrap(), expected, "{}", offset);
+            assert_eq!(path_from_reader(Trickle(text.as_bytes()), offset).unwrap(), expected, "{}", offset);
+        }
+
+        // Tests only the json before the offset is validated
+        assert_eq!(path_from_reader(&b"[1, 2] ]"[..], 5).unwrap().to_string(), "$.1");
+        let error = path_from_reader(&b"[1, 2"[..], 10).unwrap_err();
+        assert_eq!(error.kind(), std::io::ErrorKind::InvalidData);
+    }
+
+    #[test]
+    fn paths() {
+        let text = r#"[{}, {"é": "}", "b": [[], 1]}, 9]"#;
+        let keys: Vec<_> = crate::Parser::new(text).filter_map(|e| match e.unwrap() {
+            ParseEvent::Key(span) => Some(span.start + 1..span.end),
+            _ => None
+        }).collect();
+
+        let mut scanner = StreamScanner::new();
+        for (i, b) in text.bytes().enumerate() {
+            // Keys only join the path once complete
+            if !keys.iter().any(|key| key.contains(&i)) {
+                assert_eq!(scanner.path(), &crate::path_bytes(text.as_bytes(), i).unwrap(), "{}", i);
+            }
+            scanner.feed(&[b]).unwrap();
+        }
+
+        // Tests errors are kept
+        assert!(scanner.feed(b"]").is_err());
+        assert!(scanner.feed(b" ").is_err());
+        assert!(scanner.finish().is_err());
+    }
+}
@@ -0,0 +1,150 @@
+use serde::Deserialize;
+
+use crate::path::Path;
+use crate::scan::{PathTracker, Scanner};
+use crate::span::{span_of, value_span};
+
+fn invalid(err: crate::Error) -> serde_json::Error {
+    serde::de::Error::custom(err)
+}
+
+/// Deserializes only the innermost value containing a byte offset in a raw json string,
+/// into a [`serde_json::Value`] or any other type implementing [`Deserialize`].
+/// The rest of the document is validated but never deserialized.
+///
+/// Returns `None` if the offset is outside of the root value.
+///
+/// # Examples
+///
+/// ```
+/// use jsonposition::value_at_offset;
+/// use serde_json::{json, Value};
+///
+/// let json = r#"[null, 9, {"a": [1, 2]}]"#;
+///
+/// let value: Value = value_at_offset(json, json.find("[1").unwrap()).expect("Invalid JSON").unwrap();
+/// assert_eq!(value, json!([1, 2]));
+///
+/// let number: u32 = value_at_offset(json, json.find("9").unwrap()).expect("Invalid JSON").unwrap();
+/// assert_eq!(number, 9);
+/// ```
+///
+/// # Errors
+///
+/// Returns a [`serde_json::Error`] if the input json is invalid, or if the value doesn't deserialize into `T`.
+pub fn value_at_offset<'a, T: Deserialize<'a>>(text: &'a str, offset: usize) -> Result<Option<T>, serde_json::Error> {
+    match value_span(text, offset).map_err(invalid)? {
+        Some(span) => serde_json::from_str(&text[span]).map(Some),
+        None => Ok(None)
+    }
+}
+
+/// Deserializes only the value at a path in a raw json string,
+/// into a [`serde_json::Value`] or any other type implementing [`Deserialize`].
+/// The rest of the document is validated but never deserialized.
+///
+/// Returns `None` if the document has no value at the path.
+///
+/// # Examples
+///
+/// ```
+/// use jsonposition::value_at_path;
+///
+/// let json = r#"{"users": [{"name": "Ada"}, {"name": "Grace"}]}"#;
+///
+/// let name: &str = value_at_path(json, &"$.users.1.name".parse().unwrap()).expect("Invalid JSON").unwrap();
+/// assert_eq!(name, "Grace");
+/// ```
+///
+/// # Errors
+///
+/// Returns a [`serde_json::Error`] if the input json is invalid, or if the value doesn't deserialize into `T`.
+pub fn value_at_path<'a, T: Deserialize<'a>>(text: &'a str, path: &Path) -> Result<Option<T>, serde_json::Error> {
+    match span_of(text, path).map_err(invalid)? {
+        Some(span) => serde_json::from_str(&text[span]).map(Some),
+        None => Ok(None)
+    }
+}
+
+/// Finds the path to where a [`serde_json::Error`] was detected while deserializing a raw json string,
+/// so the error can be reported as `$.users.3.email` instead of a line and column.
+///
+/// The text isn't validated, so a syntax error gets the path up to where it was found.
+/// A type error is found at the end of the value with the wrong type, giving the path to that value,
+/// and a missing field at the end of its object, giving the path to the object.
+///
+/// Returns `None` if the error has no line and column, such as an io error.
+///
+/// # Examples
+///
+/// ```
+/// use std::collections::HashMap;
+/// use jsonposition::path_of_error;
+///
+/// let json = r#"{"users": [{"age": 30}, {"age": "x"}]}"#;
+///
+/// let err = serde_json::from_str::<HashMap<String, Vec<HashMap<String, u32>>>>(json).unwrap_err();
+/// assert_eq!(path_of_error(json, &err).unwrap().to_string(), "$.users.1.age");
+/// ```
+pub fn path_of_error(text: &str, err: &serde_json::Error) -> Option<Path> {
+    if err.line() == 0 {
+        return None;
+    }
+
+    // Lines are one based, columns are one based and counted in bytes, pointing at the last byte read
+    let start = match err.line() {
+        1 => 0,
+        line => text.match_indices('\n').nth(line - 2)?.0 + 1
+    };
+    let offset = start + err.column().saturating_sub(1);
+
+    // Unlike `path_unchecked`, the event at the offset is included, so the end of a container gives its own path
+    let bytes = text.as_bytes();
+    let mut tracker = PathTracker::default();
+    for event in Scanner::new(bytes) {
+        match event {
+            Ok(event) if event.start() <= offset => tracker.update(bytes, &event),
+            _ => break
+        }
+    }
+    Some(tracker.into_path())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::{json, Value};
+
+    #[test]
+    fn values() {
+        let json = r#"{"a": [1, {"b": "c"}], "d": 2.5}"#;
+
+        let value: Option<Value> = value_at_path(json, &"$.a.1".parse().unwrap()).unwrap();
+        assert_eq!(value, Some(json!({"b": "c"})));
+        let value: Option<f64> = value_at_offset(json, json.find("2.5").unwrap()).unwrap();
+        assert_eq!(value, Some(2.5));
+        let json_repeated = r#"{"a": 1, "a": 2}"#;
+        let value: Option<Value> = value_at_path(json_repeated, &"$.a".parse().unwrap()).unwrap();
+        assert_eq!(value, serde_json::from_str::<Value>(json_repeated).unwrap().get("a").cloned());
+
+        // Tests missing values and errors
+        assert_eq!(value_at_path::<Value>(json, &"$.e".parse().unwrap()).unwrap(), None);
+        assert_eq!(value_at_offset::<Value>(json, 100).unwrap(), None);
+        assert!(value_at_path::<u32>(json, &"$.a".parse().unwrap()).is_err());
+        assert!(value_at_offset::<Value>("[1,]", 1).unwrap_err().to_string().contains("orphan square brace"));
+    }
+
+    #[test]
+    fn errors() {
+        type Users = Vec<std::collections::HashMap<String, (String, String)>>;
+        let path = |json: &str| path_of_error(json, &serde_json::from_str::<Users>(json).unwrap_err()).map(|path| path.to_string());
+
+        // Tests a type error on a later line, a missing element and a syntax error
+        assert_eq!(path("[\n  {\"a\": [\"b\", \"c\"]},\n  {\"é\": [\"d\", 3]}\n]").as_deref(), Some("$.1.é.1"));
+        assert_eq!(path(r#"[{"a": ["b", "c"]}, {"d": ["e"]}]"#).as_deref(), Some("$.1.d"));
+        assert_eq!(path(r#"[{"a": ["b", "c"] "#).as_deref(), Some("$.0.a"));
+
+        let io = serde_json::Error::io(std::io::Error::other("closed"));
+        assert_eq!(path_of_error("[1]", &io), None);
+    }
+}
@@ -0,0 +1,143 @@
+use core::ops::Range;
+
+use crate::error::Error;
+use crate::locate::Kind;
+use crate::options::Options;
+use crate::path::Path;
+use crate::scan::{key_text, Event, Walker};
+
+/// Callbacks for a position aware traversal of a json document with [`visit`].
+///
+/// Every method does nothing by default, so only the ones needed have to be implemented.
+/// The path passed to each is the path of the value the event belongs to,
+/// so a key has the path of its value. Spans are byte ranges into the original text.
+pub trait Visit {
+    /// An object starts with a `{` at `start`
+    fn enter_object(&mut self, _path: &Path, _start: usize) {}
+
+    /// An object ends, with the span of the whole object
+    fn leave_object(&mut self, _path: &Path, _span: Range<usize>) {}
+
+    /// An array starts with a `[` at `start`
+    fn enter_array(&mut self, _path: &Path, _start: usize) {}
+
+    /// An array ends, with the span of the whole array
+    fn leave_array(&mut self, _path: &Path, _span: Range<usize>) {}
+
+    /// An object key, without its quotes and with its escape sequences decoded, and its span including them
+    fn key(&mut self, _path: &Path, _key: &str, _span: Range<usize>) {}
+
+    /// A string, number, boolean or null
+    fn value(&mut self, _path: &Path, _span: Range<usize>, _kind: Kind) {}
+}
+
+/// Drives a [`Visit`] over a raw json string in a single scan, without building a tree or a list of paths.
+///
+/// Callbacks are made as the document is scanned, so a visitor may already have seen part of an invalid document
+/// when the error is returned.
+///
+/// # Examples
+///
+/// ```
+/// use core::ops::Range;
+/// use jsonposition::{visit, Kind, Path, Visit};
+///
+/// /// Collects the path of every number
+/// struct Numbers(Vec<String>);
+///
+/// impl Visit for Numbers {
+///     fn value(&mut self, path: &Path, _span: Range<usize>, kind: Kind) {
+///         if kind == Kind::Number {
+///             self.0.push(path.to_string());
+///         }
+///     }
+/// }
+///
+/// let mut numbers = Numbers(Vec::new());
+/// visit(r#"{"a": [1, "b", 2]}"#, &mut numbers).expect("Invalid JSON");
+/// assert_eq!(numbers.0, ["$.a.0", "$.a.2"]);
+/// ```
+///
+/// # Errors
+///
+/// Returns an [`Error`](crate::Error) if the input json is invalid.
+pub fn visit(text: &str, visitor: &mut impl Visit) -> Result<(), Error> {
+    visit_with(text, &Options::default(), visitor)
+}
+
+/// Drives a [`Visit`] over a raw json string scanned with the given [`Options`].
+///
+/// # Errors
+///
+/// Returns an [`Error`](crate::Error) if the input json is invalid for the given options.
+pub fn visit_with(text: &str, options: &Options, visitor: &mut impl Visit) -> Result<(), Error> {
+    let bytes = text.as_bytes();
+    let mut walker = Walker::with_options(text, options);
+
+    while let Some(event) = walker.next() {
+        let path = walker.path();
+        match event? {
+            Event::StartObject(start) => visitor.enter_object(path, start),
+            Event::EndObject(span) => visitor.leave_object(path, span),
+            Event::StartArray(start) => visitor.enter_array(path, start),
+            Event::EndArray(span) => visitor.leave_array(path, span),
+            Event::Key(span) => visitor.key(path, &key_text(bytes, &span, options.json5), span),
+            Event::Scalar(span) => visitor.value(path, span.clone(), Kind::of_scalar(&bytes[span])),
+            Event::Comma(_) => {}
+        }
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[derive(Default)]
+    struct Log(Vec<String>);
+
+    impl Visit for Log {
+        fn enter_object(&mut self, path: &Path, start: usize) {
+            self.0.push(format!("{{ {} {}", path, start));
+        }
+
+        fn leave_object(&mut self, path: &Path, span: Range<usize>) {
+            self.0.push(format!("}} {} {:?}", path, span));
+        }
+
+        fn enter_array(&mut self, path: &Path, start: usize) {
+            self.0.push(format!("[ {} {}", path, start));
+        }
+
+        fn leave_array(&mut self, path: &Path, span: Range<usize>) {
+            self.0.push(format!("] {} {:?}", path, span));
+        }
+
+        fn key(&mut self, path: &Path, key: &str, span: Range<usize>) {
+            self.0.push(format!("key {} {} {:?}", path, key, span));
+        }
+
+        fn value(&mut self, path: &Path, span: Range<usize>, kind: Kind) {
+            self.0.push(format!("{} {} {:?}", kind, path, span));
+        }
+    }
+
+    #[test]
+    fn events() {
+        let mut log = Log::default();
+        visit(r#"[1, {"a": null}]"#, &mut log).unwrap();
+
+        assert_eq!(log.0, [
+            "[ $ 0",
+            "number $.0 1..2",
+            "{ $.1 4",
+            "key $.1.a a 5..8",
+            "null $.1.a 10..14",
+            "} $.1 4..15",
+            "] $ 0..16"
+        ].map(ToString::to_string));
+
+        assert!(visit("[1,]", &mut Log::default()).is_err());
+    }
+}
@@ -0,0 +1,111 @@
+use wasm_bindgen::prelude::*;
+
+use crate::lines::{path_with_unit, OffsetUnit};
+use crate::locate::Kind;
+
+// Offsets and spans are in UTF-16 code units, the same as JavaScript string indices,
+// so a cursor position from a text area or editor can be passed straight through.
+
+/// Constructs the dot path to a UTF-16 offset in a raw json string, such as `$.2.a`
+#[wasm_bindgen(js_name = dotPath)]
+pub fn dot_path(json: &str, offset: usize) -> Result<String, JsError> {
+    Ok(path_with_unit(json, offset, OffsetUnit::Utf16)?.to_string())
+}
+
+/// Constructs the JSON Pointer to a UTF-16 offset in a raw json string, such as `/2/a`
+#[wasm_bindgen(js_name = pointerPath)]
+pub fn pointer_path(json: &str, offset: usize) -> Result<String, JsError> {
+    Ok(path_with_unit(json, offset, OffsetUnit::Utf16)?.to_pointer())
+}
+
+/// Finds the innermost value or key containing a UTF-16 offset in a raw json string,
+/// `undefined` if the offset is outside of the root value
+#[wasm_bindgen]
+pub fn locate(json: &str, offset: usize) -> Result<Option<Location>, JsError> {
+    let byte_offset = OffsetUnit::Utf16.to_byte_offset(json, offset);
+
+    Ok(crate::locate(json, byte_offset)?.map(|location| Location {
+        path: location.path.to_string(),
+        pointer: location.path.to_pointer(),
+        start: utf16_offset(json, location.span.start),
+        end: utf16_offset(json, location.span.end),
+        kind: location.kind
+    }))
+}
+
+/// Innermost value or key containing an offset, as returned by `locate`
+#[wasm_bindgen]
+pub struct Location {
+    path: String,
+    pointer: String,
+    start: usize,
+    end: usize,
+    kind: Kind
+}
+
+#[wasm_bindgen]
+impl Location {
+    /// Dot path to the value, or to the value of the member for a key
+    #[wasm_bindgen(getter)]
+    pub fn path(&self) -> String {
+        self.path.clone()
+    }
+
+    /// JSON Pointer to the value, or to the value of the member for a key
+    #[wasm_bindgen(getter)]
+    pub fn pointer(&self) -> String {
+        self.pointer.clone()
+    }
+
+    /// UTF-16 offset of the start of the value or key
+    #[wasm_bindgen(getter)]
+    pub fn start(&self) -> usize {
+        self.start
+    }
+
+    /// UTF-16 offset of the end of the value or key
+    #[wasm_bindgen(getter)]
+    pub fn end(&self) -> usize {
+        self.end
+    }
+
+    /// Kind of the value, such as `number` or `object key`
+    #[wasm_bindgen(getter)]
+    pub fn kind(&self) -> String {
+        self.kind.to_string()
+    }
+
+    /// Whether the offset is within an object key rather than a value
+    #[wasm_bindgen(getter, js_name = inKey)]
+    pub fn in_key(&self) -> bool {
+        self.kind == Kind::ObjectKey
+    }
+}
+
+/// Converts a byte offset on a char boundary to UTF-16 code units
+fn utf16_offset(text: &str, offset: usize) -> usize {
+    text[..offset].encode_utf16().count()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn bindings() {
+        let json = r#"{"é😀": [null, 9]}"#;
+        let nine = json[..json.find('9').unwrap()].encode_utf16().count();
+
+        assert_eq!(dot_path(json, nine).unwrap(), "$.é😀.1");
+        assert_eq!(pointer_path(json, nine).unwrap(), "/é😀/1");
+
+        let location = locate(json, nine).unwrap().unwrap();
+        assert_eq!((location.start(), location.end()), (nine, nine + 1));
+        assert_eq!(location.kind(), "number");
+        assert!(!location.in_key());
+
+        let location = locate(json, 2).unwrap().unwrap();
+        assert_eq!((location.start(), location.end()), (1, 6));
+        assert!(location.in_key());
+    }
+}